@@ -79,8 +79,7 @@ impl FunctionHandler for AssignAction {
                     Value::String(s) => Some(evalexpr::Value::String(s)),
                     Value::Number(n) => {
                          if let Some(i) = n.as_i64() { Some(evalexpr::Value::Int(i)) }
-                         else if let Some(f) = n.as_f64() { Some(evalexpr::Value::Float(f)) }
-                         else { None }
+                         else { n.as_f64().map(evalexpr::Value::Float) }
                     },
                     Value::Bool(b) => Some(evalexpr::Value::Boolean(b)),
                     _ => None,
@@ -2,6 +2,7 @@ use async_trait::async_trait;
 use serde_json::{Value, json};
 use crate::actions::{FunctionHandler, ExecutionMode};
 use crate::runtime::context::Context;
+use crate::runtime::error::NodeError;
 use anyhow::{Result, anyhow};
 use std::fmt::Debug;
 use reqwest::Client;
@@ -11,6 +12,12 @@ pub struct HttpAction {
     client: Client,
 }
 
+impl Default for HttpAction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HttpAction {
     pub fn new() -> Self {
         Self {
@@ -36,7 +43,7 @@ impl FunctionHandler for HttpAction {
         Ok(())
     }
 
-    async fn execute(&self, params: Value, _ctx: &Context) -> Result<Value> {
+    async fn execute(&self, params: Value, ctx: &Context) -> Result<Value> {
         let url = params.get("url").and_then(|v| v.as_str())
             .ok_or_else(|| anyhow!("Invalid url"))?;
         
@@ -60,7 +67,15 @@ impl FunctionHandler for HttpAction {
             }
         }
 
-        let response = builder.send().await?;
+        let mut response = tokio::select! {
+            response = builder.send() => response?,
+            _ = ctx.cancelled() => return Err(NodeError::Cancelled.into()),
+        };
+
+        // Opt-in: treat 4xx/5xx as failures so retry policies can react to them
+        if params.get("error_for_status").and_then(|v| v.as_bool()).unwrap_or(false) {
+            response = response.error_for_status()?;
+        }
         let status = response.status().as_u16();
         
         // Parse JSON response if possible, else text
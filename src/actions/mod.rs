@@ -3,6 +3,7 @@ use serde_json::Value;
 use crate::runtime::context::Context;
 use anyhow::Result;
 use std::fmt::Debug;
+use std::time::Duration;
 
 pub mod builtin;
 pub mod http;
@@ -24,6 +25,10 @@ pub trait FunctionHandler: Send + Sync + Debug {
     fn execution_mode(&self) -> ExecutionMode {
         ExecutionMode::Async
     }
+    /// default timeout of the handler, used when the DSL node does not declare one
+    fn default_timeout(&self) -> Option<Duration> {
+        None
+    }
     fn validate(&self, params: &Value) -> Result<()>;
     async fn execute(&self, params: Value, ctx: &Context) -> Result<Value>;
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 pub mod actions;
 
 use crate::runtime::engine::Engine;
-use crate::runtime::context::Context;
+use crate::runtime::instance::InstanceStatus;
 use crate::nodes::common::{StartDefinition, EndDefinition};
 use crate::nodes::flow::{ForkDefinition, JoinDefinition};
 use crate::actions::builtin::AssignAction;
@@ -12,7 +12,6 @@ use std::sync::Arc;
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 use serde_json::json;
-use tracing::{info, warn};
 use anyhow::Result;
 
 pub struct BenchmarkRunner {
@@ -45,75 +44,58 @@ impl BenchmarkRunner {
             let branch_prefix = format!("b{}_", i);
 
             // First node to initialize a variable
-            branch_nodes.push(Node {
-                id: format!("{}assign_0", branch_prefix),
-                kind: NodeType::Function {
-                    name: "assign".to_string(),
-                    params: HashMap::from([
-                        ("expression".to_string(), json!(format!("{}_temp_0 = 1", branch_prefix)))
-                    ]),
-                    output: None,
-                },
-            });
+            branch_nodes.push(Node::new(format!("{}assign_0", branch_prefix), NodeType::Function {
+                name: "assign".to_string(),
+                params: HashMap::from([
+                    ("expression".to_string(), json!(format!("{}_temp_0 = 1", branch_prefix)))
+                ]),
+                output: None,
+            }));
 
             // 9 more consecutive assign nodes
             for j in 1..10 {
-                branch_nodes.push(Node {
-                    id: format!("{}assign_{}", branch_prefix, j),
-                    kind: NodeType::Function {
-                        name: "assign".to_string(),
-                        params: HashMap::from([
-                            ("expression".to_string(), json!(format!("{}_temp_{} = {}_temp_{} + 1", branch_prefix, j, branch_prefix, j-1)))
-                        ]),
-                        output: None,
-                    },
-                });
-            }
-
-            // The last node in the chain will set the 'finished' variable
-            branch_nodes.push(Node {
-                id: format!("{}assign_final", branch_prefix),
-                kind: NodeType::Function {
+                branch_nodes.push(Node::new(format!("{}assign_{}", branch_prefix, j), NodeType::Function {
                     name: "assign".to_string(),
                     params: HashMap::from([
-                        ("assignments".to_string(), json!([
-                            {
-                                "key": format!("finished_branch_{}", i),
-                                "value": true
-                            }
-                        ]))
+                        ("expression".to_string(), json!(format!("{}_temp_{} = {}_temp_{} + 1", branch_prefix, j, branch_prefix, j-1)))
                     ]),
                     output: None,
-                },
-            });
+                }));
+            }
+
+            // The last node in the chain will set the 'finished' variable
+            branch_nodes.push(Node::new(format!("{}assign_final", branch_prefix), NodeType::Function {
+                name: "assign".to_string(),
+                params: HashMap::from([
+                    ("assignments".to_string(), json!([
+                        {
+                            "key": format!("finished_branch_{}", i),
+                            "value": true
+                        }
+                    ]))
+                ]),
+                output: None,
+            }));
             
             branches.push(Branch {
-                nodes: branch_nodes
+                nodes: branch_nodes,
+                edges: Vec::new(),
             });
         }
 
         let workflow_id = format!("bench_chain_{}", branch_count);
-        let mut nodes_vec = vec![
-            Node { id: "start".to_string(), kind: NodeType::Start },
-            Node { 
-                id: "par".to_string(), 
-                kind: NodeType::Parallel { branches } 
-            },
-            // We need a final join node after the parallel section
-            Node {
-                 id: "final_join".to_string(),
-                 kind: NodeType::Join { expect_count: branch_count }
-            },
-            Node { id: "end".to_string(), kind: NodeType::End { output: "overall_finished".to_string() } }
+        let nodes_vec = vec![
+            Node::new("start", NodeType::Start),
+            Node::new("par", NodeType::Parallel { branches }),
+            Node::new("end", NodeType::End { output: String::new() })
         ];
 
         // Add edges within the branches if not implicitly handled by Parallel expander
         // The expander will handle linear connections within branches.
 
-        let mut edges_vec = vec![
+        let edges_vec = vec![
             Edge { source: "start".to_string(), target: "par".to_string(), condition: None, branch_type: None, branch_index: None },
-            Edge { source: "par".to_string(), target: "final_join".to_string(), condition: None, branch_type: None, branch_index: None },
-            Edge { source: "final_join".to_string(), target: "end".to_string(), condition: None, branch_type: None, branch_index: None },
+            Edge { source: "par".to_string(), target: "end".to_string(), condition: None, branch_type: None, branch_index: None },
         ];
         
         let workflow = Workflow {
@@ -122,10 +104,11 @@ impl BenchmarkRunner {
             variables: HashMap::new(),
             nodes: nodes_vec,
             edges: edges_vec,
+            ..Default::default()
         };
 
         // 2. Compile
-        let config = CompilerConfig { enable_fusion: !self.no_jit };
+        let config = CompilerConfig { enable_fusion: !self.no_jit, ..Default::default() };
         let mut compiler = Compiler::new_with_config(config);
         let blueprint = compiler.compile(workflow)?;
         self.engine.register_blueprint(blueprint.clone());
@@ -135,24 +118,10 @@ impl BenchmarkRunner {
         
         let start = Instant::now();
         
-        // Poll for completion - now we need to check all branch finished flags
-        loop {
-            tokio::time::sleep(Duration::from_micros(100)).await;
-            let mut all_finished = true;
-            for i in 0..branch_count {
-                let var_name = format!("finished_branch_{}", i);
-                if self.engine.get_instance_var(instance_id, &var_name).await != Some(json!(true)) {
-                    all_finished = false;
-                    break;
-                }
-            }
-
-            if all_finished {
-                 break;
-            }
-            if start.elapsed().as_secs() > 60 {
-                anyhow::bail!("Benchmark timeout");
-            }
+        // The Parallel block joins before End, so completion implies every branch finished
+        let status = self.engine.await_completion(instance_id, Duration::from_secs(60)).await?;
+        if status != InstanceStatus::Completed {
+            anyhow::bail!("Benchmark instance ended as {}", status);
         }
 
         let duration = start.elapsed();
@@ -178,13 +147,7 @@ impl BenchmarkRunner {
         println!("------------------------------------------------------------------");
 
         // Start workers
-        let mut handles = Vec::new();
-        for _ in 0..worker_count {
-            let e = self.engine.clone();
-            handles.push(tokio::spawn(async move {
-                e.run_worker().await;
-            }));
-        }
+        let workers = self.engine.spawn_workers(worker_count);
 
         let mut current_branches = 100;
         let _fib_n = 25; // Not used in this benchmark mode
@@ -221,7 +184,7 @@ impl BenchmarkRunner {
             // Ramp up strategy (Aggressive 2x)
             if avg_tps > last_avg_tps * 0.98 { 
                 last_avg_tps = avg_tps;
-                current_branches = current_branches * 2;
+                current_branches *= 2;
             } else {
                 println!("⚠️  Saturation detected at {} branches.", current_branches);
                 break;
@@ -271,7 +234,7 @@ impl BenchmarkRunner {
         println!("Total Assign Ops:   {}", total_tasks_processed * (10 + 1));
         println!("==================================================================");
 
-        for h in handles { h.abort(); }
+        workers.abort();
         Ok(())
     }
 }
\ No newline at end of file
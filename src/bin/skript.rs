@@ -1,23 +1,35 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use skript::runtime::engine::Engine;
-use skript::runtime::storage::{InMemoryStateStore, InMemoryTaskQueue};
-use skript::runtime::redis_storage::{RedisStateStore, RedisTaskQueue};
+use skript::runtime::instance::InstanceStatus;
+use skript::runtime::redis_connection::RedisConnections;
+use skript::runtime::redis_storage::{RedisBlueprintStore, RedisCommitter, RedisStateStore, RedisTaskQueue};
 use skript::actions::builtin::{LogAction, AssignAction};
+use skript::actions::http::HttpAction;
 use skript::nodes::common::{StartDefinition, EndDefinition};
 use skript::nodes::flow::{IfDefinition, ForkDefinition, JoinDefinition, IterationDefinition, LoopDefinition};
-use skript::compiler::core::Compiler;
-use skript::compiler::loader::load_workflow_from_yaml;
+use skript::compiler::core::{Compiler, CompilerConfig};
+use skript::compiler::lint::LintLevel;
+use skript::compiler::diagnostic::{Diagnostic, SourceMap};
+use skript::compiler::expander::Expander;
+use skript::compiler::graph::Graph;
+use skript::compiler::loader::load_workflow_with_source_map;
+use skript::runtime::blueprint::Blueprint;
 use std::sync::Arc;
 use std::collections::HashMap;
-use std::path::PathBuf;
-use anyhow::Result;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result, anyhow};
 use tracing::{info, error};
-use tracing_subscriber;
 use std::fs;
+use std::time::Duration;
+use uuid::Uuid;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    /// How to print compiler diagnostics
+    #[arg(long, global = true, value_enum, default_value_t = MessageFormat::Human)]
+    message_format: MessageFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -33,6 +45,14 @@ enum Commands {
         /// Initial variables (key=value)
         #[arg(long, short = 'D', value_parser = parse_key_val)]
         vars: Vec<(String, serde_json::Value)>,
+
+        /// How to print the workflow output
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+
+        /// Give up waiting for the instance after this many seconds
+        #[arg(long)]
+        timeout: Option<u64>,
     },
 
     /// Start a worker node connecting to Redis (Distributed Mode)
@@ -45,9 +65,18 @@ enum Commands {
         #[arg(long, default_value = "worker")]
         name: String,
 
-        /// Directory containing workflow YAML files to preload
+        /// Directory of workflow YAML files and compiled blueprint JSON files to preload
+        /// (others are fetched from Redis once submitted)
         #[arg(long)]
         workflows: Option<PathBuf>,
+
+        /// Seconds in-flight tasks may run after SIGTERM/SIGINT before they are re-queued
+        #[arg(long, default_value_t = 30)]
+        grace: u64,
+
+        /// Number of tasks executed concurrently by this process
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+        concurrency: u64,
     },
 
     /// Submit a workflow to Redis for workers to execute (Client Mode)
@@ -64,6 +93,88 @@ enum Commands {
         #[arg(long, short = 'D', value_parser = parse_key_val)]
         vars: Vec<(String, serde_json::Value)>,
     },
+    /// Cancel a running workflow instance in Redis (Client Mode)
+    Cancel {
+        /// Instance ID returned by `submit`
+        #[arg(long, short)]
+        instance: Uuid,
+
+        /// Reason recorded on the instance
+        #[arg(long, default_value = "Cancelled by user")]
+        reason: String,
+
+        /// Redis connection URL
+        #[arg(long, default_value = "redis://127.0.0.1:6379/0")]
+        redis: String,
+    },
+    /// Suspend a running workflow instance in Redis (Client Mode)
+    Suspend {
+        /// Instance ID returned by `submit`
+        #[arg(long, short)]
+        instance: Uuid,
+
+        /// Redis connection URL
+        #[arg(long, default_value = "redis://127.0.0.1:6379/0")]
+        redis: String,
+    },
+
+    /// Resume a suspended workflow instance in Redis (Client Mode)
+    Resume {
+        /// Instance ID returned by `submit`
+        #[arg(long, short)]
+        instance: Uuid,
+
+        /// Redis connection URL
+        #[arg(long, default_value = "redis://127.0.0.1:6379/0")]
+        redis: String,
+    },
+    /// Inspect and re-drive dead-lettered tasks in Redis
+    Dlq {
+        #[command(subcommand)]
+        command: DlqCommand,
+    },
+    /// Compile workflows without running them, exiting non-zero if any has errors
+    Check {
+        /// Workflow YAML files, or directories of them
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Treat lint warnings as errors
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+    /// Compile a workflow and write the resulting blueprint as JSON
+    Compile {
+        /// Path to the workflow YAML file
+        #[arg(long, short)]
+        file: PathBuf,
+
+        /// Where to write the blueprint (stdout if omitted)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+
+        /// Keep nodes as written instead of fusing sync chains
+        #[arg(long)]
+        no_fusion: bool,
+    },
+    /// Render a workflow as a graph at one compilation stage
+    Graph {
+        /// Path to the workflow YAML file
+        #[arg(long, short)]
+        file: PathBuf,
+
+        /// Graph language to print
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+
+        /// Compilation stage to show
+        #[arg(long, value_enum, default_value_t = GraphStage::Blueprint)]
+        stage: GraphStage,
+
+        /// Where to write the graph (stdout if omitted)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
     /// Run automated benchmark
     Bench {
         /// Disable JIT Fusion Optimization
@@ -72,6 +183,74 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum DlqCommand {
+    /// List dead letters, oldest first
+    List {
+        /// Redis connection URL
+        #[arg(long, default_value = "redis://127.0.0.1:6379/0")]
+        redis: String,
+    },
+    /// Print a dead letter with its task payload
+    Show {
+        /// Dead letter ID
+        id: Uuid,
+
+        /// Redis connection URL
+        #[arg(long, default_value = "redis://127.0.0.1:6379/0")]
+        redis: String,
+    },
+    /// Re-queue dead-lettered tasks, reopening their failed instances
+    Replay {
+        /// Dead letter ID (all dead letters if omitted)
+        id: Option<Uuid>,
+
+        /// Redis connection URL
+        #[arg(long, default_value = "redis://127.0.0.1:6379/0")]
+        redis: String,
+    },
+    /// Delete all dead letters
+    Purge {
+        /// Redis connection URL
+        #[arg(long, default_value = "redis://127.0.0.1:6379/0")]
+        redis: String,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// The output value alone (strings unquoted)
+    Text,
+    /// A JSON object with instance id, status, output and error
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum MessageFormat {
+    /// Messages with the offending source line
+    Human,
+    /// One JSON object per line, for editor integration
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GraphStage {
+    /// The workflow as written
+    Dsl,
+    /// After Parallel nodes are expanded into Fork/Join pairs
+    Expanded,
+    /// The compiled blueprint, with fused nodes
+    Blueprint,
+}
+
 fn parse_key_val(s: &str) -> Result<(String, serde_json::Value), String> {
     let pos = s.find('=').ok_or_else(|| format!("invalid KEY=value: no `=` found in `{}`", s))?;
     let key = s[..pos].to_string();
@@ -92,102 +271,407 @@ fn register_standard_components(engine: &mut Engine) {
 
     engine.register_function(Arc::new(LogAction));
     engine.register_function(Arc::new(AssignAction));
+    engine.register_function(Arc::new(HttpAction::new()));
+}
+
+/// Loads and compiles a workflow file, printing its diagnostics (warnings included) to stderr.
+fn compile_file(path: &Path, compiler: &mut Compiler, format: MessageFormat) -> Result<Blueprint> {
+    let file = path.to_string_lossy();
+    let (workflow, sources) = match load_workflow_with_source_map(&file) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            report(&[Diagnostic::from_error(&e)], None, format);
+            return Err(anyhow!("Could not load {}", file));
+        }
+    };
+
+    let result = compiler.compile(workflow);
+    let mut diagnostics = compiler.diagnostics().to_vec();
+    for diagnostic in &mut diagnostics {
+        sources.locate(diagnostic);
+    }
+    report(&diagnostics, Some(&sources), format);
+    result.map_err(|_| anyhow!("Could not compile {}", file))
+}
+
+fn report(diagnostics: &[Diagnostic], sources: Option<&SourceMap>, format: MessageFormat) {
+    for diagnostic in diagnostics {
+        match format {
+            MessageFormat::Human => eprintln!("{}\n", diagnostic.render(sources)),
+            MessageFormat::Json => match serde_json::to_string(diagnostic) {
+                Ok(json) => eprintln!("{}", json),
+                Err(e) => error!("Failed to serialize diagnostic: {}", e),
+            },
+        }
+    }
+}
+
+/// Builds the graph of a workflow file at `stage`, printing diagnostics like `compile_file`.
+fn workflow_graph(path: &Path, stage: GraphStage, format: MessageFormat) -> Result<Graph> {
+    if stage == GraphStage::Blueprint {
+        let mut engine = Engine::new();
+        register_standard_components(&mut engine);
+        let mut compiler = Compiler::new().with_registry(engine.node_registry());
+        return Ok(Graph::from_blueprint(&compile_file(path, &mut compiler, format)?));
+    }
+
+    let file = path.to_string_lossy();
+    let (workflow, sources) = load_workflow_with_source_map(&file).map_err(|e| {
+        report(&[Diagnostic::from_error(&e)], None, format);
+        anyhow!("Could not load {}", file)
+    })?;
+    if stage == GraphStage::Dsl {
+        return Ok(Graph::from_workflow(&workflow));
+    }
+    match Expander::new().expand(workflow) {
+        Ok(expanded) => Ok(Graph::from_workflow(&expanded)),
+        Err(e) => {
+            let mut diagnostic = Diagnostic::from_error(&e);
+            sources.locate(&mut diagnostic);
+            report(&[diagnostic], Some(&sources), format);
+            Err(anyhow!("Could not expand {}", file))
+        }
+    }
+}
+
+/// Workflow files under `path`, sorted, or `path` itself when it is a file.
+fn workflow_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(path)
+        .with_context(|| format!("Failed to read directory {}", path.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| matches!(p.extension().and_then(|s| s.to_str()), Some("yaml" | "yml")))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Reads a blueprint written by `skript compile`.
+fn load_blueprint(path: &Path) -> Result<Blueprint> {
+    let json = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read blueprint from {}", path.display()))?;
+    serde_json::from_str(&json)
+        .with_context(|| format!("Failed to parse blueprint from {}", path.display()))
+}
+
+/// Resolves on SIGINT (Ctrl-C) or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => { signal.recv().await; }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Engine over the shared Redis storage. Store and queue share connections, each task's
+/// writes are committed in a single round-trip, and blueprints are shared through Redis.
+fn redis_engine(redis: String) -> Engine {
+    let client = redis::Client::open(redis).expect("Invalid Redis URL");
+    let connections = RedisConnections::new(client);
+    let store = Arc::new(RedisStateStore::with_connections(connections.clone()));
+    let queue = Arc::new(RedisTaskQueue::with_connections(connections.clone(), "skript:distributed:tasks".to_string()));
+    let committer = Arc::new(RedisCommitter::new(&store, &queue));
+
+    let mut engine = Engine::new_with_storage(store, queue);
+    engine.set_committer(committer);
+    engine.set_blueprint_store(Arc::new(RedisBlueprintStore::with_connections(connections)));
+    engine
+}
+
+async fn run_dlq(command: DlqCommand) -> Result<()> {
+    match command {
+        DlqCommand::List { redis } => {
+            let engine = redis_engine(redis);
+            for letter in engine.dead_letters().await? {
+                println!("{}\t{}\t{}\t{}", letter.id, letter.failed_at, letter.node_id.as_deref().unwrap_or("-"), letter.error);
+            }
+        }
+        DlqCommand::Show { id, redis } => {
+            let engine = redis_engine(redis);
+            let letter = engine.get_dead_letter(id).await?
+                .ok_or_else(|| anyhow::anyhow!("Dead letter not found: {}", id))?;
+            let mut report = serde_json::to_value(&letter)?;
+            // Show the task as a JSON object when it parses
+            if let Ok(task) = serde_json::from_str::<serde_json::Value>(&letter.payload) {
+                report["payload"] = task;
+            }
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        DlqCommand::Replay { id, redis } => {
+            let engine = redis_engine(redis);
+            let explicit = id.is_some();
+            let ids = match id {
+                Some(id) => vec![id],
+                None => engine.dead_letters().await?.into_iter().map(|l| l.id).collect(),
+            };
+            let total = ids.len();
+            let mut failed = 0;
+            for id in ids {
+                match engine.replay_dead_letter(id).await {
+                    Ok(true) => info!("Dead letter {} replayed.", id),
+                    // When replaying all, another process may have replayed it in the meantime
+                    Ok(false) if !explicit => info!("Dead letter {} already gone.", id),
+                    Ok(false) => {
+                        error!("Dead letter not found: {}", id);
+                        failed += 1;
+                    }
+                    Err(e) => {
+                        error!("Failed to replay dead letter {}: {}", id, e);
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                anyhow::bail!("{} of {} dead letters failed to replay", failed, total);
+            }
+        }
+        DlqCommand::Purge { redis } => {
+            let engine = redis_engine(redis);
+            let count = engine.purge_dead_letters().await?;
+            info!("Purged {} dead letters.", count);
+        }
+    }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+    // Logs go to stderr, so stdout only carries the workflow output
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
     let cli = Cli::parse();
+    let message_format = cli.message_format;
 
     match cli.command {
+        Commands::Graph { file, format, stage, output } => {
+            let graph = workflow_graph(&file, stage, message_format)?;
+            let rendered = match format {
+                GraphFormat::Dot => graph.to_dot(),
+                GraphFormat::Mermaid => graph.to_mermaid(),
+            };
+            match output {
+                Some(path) => fs::write(&path, rendered)
+                    .with_context(|| format!("Failed to write graph to {}", path.display()))?,
+                None => print!("{}", rendered),
+            }
+        }
         Commands::Bench { no_jit } => {
             use skript::benchmark::BenchmarkRunner;
             let runner = BenchmarkRunner::new(no_jit);
             runner.auto_tune().await?;
         }
-        Commands::Run { file, vars } => {
+        Commands::Check { files, deny_warnings } => {
+            // Same components as `run` and the workers, so kinds they cannot run are errors
+            let mut engine = Engine::new();
+            register_standard_components(&mut engine);
+            let config = CompilerConfig {
+                lint_level: if deny_warnings { LintLevel::Deny } else { LintLevel::Warn },
+                ..Default::default()
+            };
+            let mut compiler = Compiler::new_with_config(config).with_registry(engine.node_registry());
+
+            let mut checked = 0;
+            let mut failed = 0;
+            for path in &files {
+                for file in workflow_files(path)? {
+                    checked += 1;
+                    if compile_file(&file, &mut compiler, message_format).is_err() {
+                        failed += 1;
+                    }
+                }
+            }
+
+            if failed > 0 {
+                return Err(anyhow!("{} of {} workflows failed to compile", failed, checked));
+            }
+            info!("{} workflows checked.", checked);
+        }
+        Commands::Compile { file, output, no_fusion } => {
+            let mut engine = Engine::new();
+            register_standard_components(&mut engine);
+            let config = CompilerConfig { enable_fusion: !no_fusion, ..Default::default() };
+            let mut compiler = Compiler::new_with_config(config).with_registry(engine.node_registry());
+
+            let blueprint = compile_file(&file, &mut compiler, message_format)?;
+            let json = serde_json::to_string_pretty(&blueprint)?;
+            match output {
+                Some(path) => {
+                    fs::write(&path, json + "\n")
+                        .with_context(|| format!("Failed to write blueprint to {}", path.display()))?;
+                    info!("Blueprint {}@{} written to {}", blueprint.id, blueprint.version, path.display());
+                }
+                None => println!("{}", json),
+            }
+        }
+        Commands::Run { file, vars, output, timeout } => {
             info!("Running in Standalone Memory Mode");
             let mut engine = Engine::new(); // Defaults to Memory
             register_standard_components(&mut engine);
 
-            let workflow = load_workflow_from_yaml(file.to_str().unwrap())?;
-            let workflow_id = workflow.id.clone();
-            
-            let mut compiler = Compiler::new();
-            let blueprint = compiler.compile(workflow)?;
+            let mut compiler = Compiler::new().with_registry(engine.node_registry());
+            let blueprint = compile_file(&file, &mut compiler, message_format)?;
+            let workflow_id = blueprint.id.clone();
             engine.register_blueprint(blueprint);
 
             let initial_vars: HashMap<_, _> = vars.into_iter().collect();
             let instance_id = engine.start_workflow(&workflow_id, initial_vars).await?;
             
             info!("Workflow started: {}", instance_id);
-            engine.run_worker().await;
-            info!("Workflow finished.");
+
+            let engine = Arc::new(engine);
+            let workers = engine.spawn_workers(1);
+
+            let wait_timeout = timeout.map(Duration::from_secs).unwrap_or(Duration::MAX);
+            let status = engine.await_completion(instance_id, wait_timeout).await;
+            workers.abort();
+            let status = status?;
+            info!("Workflow finished: {}", status);
+
+            let result = engine.get_output(instance_id).await?;
+            let failure = engine.get_failure(instance_id).await?;
+            match output {
+                OutputFormat::Json => {
+                    let report = serde_json::json!({
+                        "instance_id": instance_id,
+                        "status": status.as_str(),
+                        "output": result,
+                        "error": failure.map(|f| f.error),
+                    });
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                OutputFormat::Text => {
+                    match result {
+                        Some(serde_json::Value::String(s)) => println!("{}", s),
+                        Some(value) => println!("{}", serde_json::to_string_pretty(&value)?),
+                        None => {}
+                    }
+                    if let Some(failure) = failure {
+                        eprintln!("Workflow {}: {}", status, failure.error);
+                    }
+                }
+            }
+
+            if status != InstanceStatus::Completed {
+                std::process::exit(1);
+            }
         }
 
-        Commands::Worker { redis, name, workflows } => {
+        Commands::Worker { redis, name, workflows, grace, concurrency } => {
             info!("[{}] Starting Worker... Redis: {}", name, redis);
-            
-            let client = redis::Client::open(redis).expect("Invalid Redis URL");
-            let store = Arc::new(RedisStateStore::new(client.clone()));
-            let queue = Arc::new(RedisTaskQueue::new(client, "skript:distributed:tasks".to_string()));
 
-            let mut engine = Engine::new_with_storage(store, queue);
+            let mut engine = redis_engine(redis);
             register_standard_components(&mut engine);
+            engine.set_shutdown_grace(Duration::from_secs(grace));
 
             if let Some(dir) = workflows {
                 info!("Loading workflows from: {:?}", dir);
                 if let Ok(entries) = fs::read_dir(dir) {
-                    let mut compiler = Compiler::new();
+                    let mut compiler = Compiler::new().with_registry(engine.node_registry());
                     for entry in entries.flatten() {
                         let path = entry.path();
-                        if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
-                            if ext == "yaml" || ext == "yml" {
-                                match load_workflow_from_yaml(path.to_str().unwrap()) {
-                                    Ok(wf) => {
-                                        info!("Loaded workflow: {}", wf.id);
-                                        match compiler.compile(wf) {
-                                            Ok(bp) => engine.register_blueprint(bp),
-                                            Err(e) => error!("Failed to compile {}: {}", path.display(), e),
-                                        }
-                                    },
-                                    Err(e) => error!("Failed to load {}: {}", path.display(), e),
-                                }
+                        let loaded = match path.extension().and_then(|s| s.to_str()) {
+                            Some("yaml" | "yml") => compile_file(&path, &mut compiler, message_format),
+                            Some("json") => load_blueprint(&path),
+                            _ => continue,
+                        };
+                        match loaded {
+                            Ok(bp) => {
+                                info!("Loaded workflow: {}", bp.id);
+                                engine.register_blueprint(bp);
                             }
+                            Err(e) => error!("{:#}", e),
                         }
                     }
                 }
             }
 
-            info!("Worker ready.");
-            engine.run_worker().await;
+            let shutdown = engine.shutdown_handle();
+            tokio::spawn(async move {
+                shutdown_signal().await;
+                info!("[{}] Shutdown signal received, draining...", name);
+                shutdown.shutdown();
+            });
+
+            let engine = Arc::new(engine);
+            let workers = engine.spawn_workers(concurrency as usize);
+            info!("Worker ready with {} concurrent executors.", workers.size());
+            workers.join().await;
         }
 
         Commands::Submit { file, redis, vars } => {
             info!("Submitting to Redis: {}", redis);
-            
-            let client = redis::Client::open(redis).expect("Invalid Redis URL");
-            let store = Arc::new(RedisStateStore::new(client.clone()));
-            let queue = Arc::new(RedisTaskQueue::new(client, "skript:distributed:tasks".to_string()));
 
-            let mut engine = Engine::new_with_storage(store, queue);
+            let mut engine = redis_engine(redis);
             register_standard_components(&mut engine);
 
-            let workflow = load_workflow_from_yaml(file.to_str().unwrap())?;
-            let workflow_id = workflow.id.clone();
-            
-            let mut compiler = Compiler::new();
-            let blueprint = compiler.compile(workflow)?;
-            
-            // In a real system, we would push this Blueprint to Redis so workers can fetch it.
-            // For now, we just register it locally to allow 'start_workflow' validation to pass.
-            engine.register_blueprint(blueprint);
+            // Workers run the same standard components, so what they cannot run is rejected here
+            let mut compiler = Compiler::new().with_registry(engine.node_registry());
+            let blueprint = compile_file(&file, &mut compiler, message_format)?;
+            let workflow_id = blueprint.id.clone();
+
+            // Workers fetch the blueprint from Redis when they pick up the first task
+            engine.publish_blueprint(blueprint).await?;
 
             let initial_vars: HashMap<_, _> = vars.into_iter().collect();
             let instance_id = engine.start_workflow(&workflow_id, initial_vars).await?;
             
             info!("Workflow submitted successfully! Instance ID: {}", instance_id);
         }
+
+        Commands::Cancel { instance, reason, redis } => {
+            let engine = redis_engine(redis);
+
+            // Workers drop queued tasks and abort running ones once they observe the status
+            if engine.cancel(instance, &reason).await? {
+                info!("Instance {} cancelled.", instance);
+            } else {
+                let status = engine.get_status(instance).await?;
+                info!("Instance {} already finished ({:?}), nothing to cancel.", instance, status);
+            }
+        }
+
+        Commands::Suspend { instance, redis } => {
+            let engine = redis_engine(redis);
+            if engine.suspend(instance).await? {
+                info!("Instance {} suspended.", instance);
+            } else {
+                let status = engine.get_status(instance).await?;
+                info!("Instance {} is not running ({:?}), nothing to suspend.", instance, status);
+            }
+        }
+
+        Commands::Resume { instance, redis } => {
+            let engine = redis_engine(redis);
+            if engine.resume(instance).await? {
+                info!("Instance {} resumed.", instance);
+            } else {
+                let status = engine.get_status(instance).await?;
+                info!("Instance {} is not suspended ({:?}), nothing to resume.", instance, status);
+            }
+        }
+
+        Commands::Dlq { command } => run_dlq(command).await?,
     }
 
     Ok(())
@@ -1,25 +1,47 @@
 use crate::dsl::{Workflow, Node, NodeType, Edge};
-use crate::runtime::blueprint::{Blueprint, BlueprintNode, NodeIndex};
+use crate::runtime::blueprint::{Blueprint, BlueprintNode, NodeIndex, POLICY_PARAM};
+use crate::runtime::registry::NodeRegistry;
 use crate::compiler::expander::Expander;
 use crate::compiler::optimizer::Optimizer;
+use crate::compiler::lint::{self, LintFinding, LintLevel};
+use crate::compiler::diagnostic::{Diagnostic, Severity};
 use crate::actions::ExecutionMode;
 use std::collections::HashMap;
-use anyhow::{Result, anyhow};
-use serde_json::json;
+use std::sync::Arc;
+use anyhow::Result;
+use serde_json::{json, Value};
 
 pub struct CompilerConfig {
     pub enable_fusion: bool,
+    /// What to do with structural problems found in the graph
+    pub lint_level: LintLevel,
 }
 
 impl Default for CompilerConfig {
     fn default() -> Self {
-        Self { enable_fusion: true }
+        Self { enable_fusion: true, lint_level: LintLevel::default() }
     }
 }
 
 pub struct Compiler {
     id_map: HashMap<String, NodeIndex>,
     config: CompilerConfig,
+    // Node definitions to validate against; without one, nodes are only checked when prepared
+    registry: Option<Arc<NodeRegistry>>,
+    // Lint findings of the last compilation
+    findings: Vec<LintFinding>,
+    // Everything reported by the last compilation, including the error that stopped it
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Code of the error returned when lint findings are denied; the findings themselves are
+/// reported as separate diagnostics.
+const LINT_FAILED: &str = "lint-failed";
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Compiler {
@@ -31,10 +53,42 @@ impl Compiler {
         Self {
             id_map: HashMap::new(),
             config,
+            registry: None,
+            findings: Vec::new(),
+            diagnostics: Vec::new(),
         }
     }
 
+    /// Rejects unknown node kinds and params the node definitions do not accept, e.g. the
+    /// registry of the engine that will run the blueprint (`Engine::node_registry`).
+    pub fn with_registry(mut self, registry: Arc<NodeRegistry>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Compiles the workflow. Errors are `Diagnostic`s; they and any lint warnings are also
+    /// available from `diagnostics` afterwards.
     pub fn compile(&mut self, raw_workflow: Workflow) -> Result<Blueprint> {
+        self.id_map.clear();
+        self.findings.clear();
+        self.diagnostics.clear();
+
+        let result = self.compile_workflow(raw_workflow);
+        if let Err(e) = &result {
+            let diagnostic = Diagnostic::from_error(e);
+            if diagnostic.code != LINT_FAILED {
+                self.diagnostics.push(diagnostic);
+            }
+        }
+        result
+    }
+
+    /// Diagnostics of the last `compile` call, in the order they were found.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    fn compile_workflow(&mut self, raw_workflow: Workflow) -> Result<Blueprint> {
         // 0. Pass 0: Expand
         let expander = Expander::new();
         let workflow = expander.expand(raw_workflow)?;
@@ -42,7 +96,7 @@ impl Compiler {
         // 1. Pass 1: Indexing
         for (idx, node) in workflow.nodes.iter().enumerate() {
             if self.id_map.insert(node.id.clone(), idx).is_some() {
-                return Err(anyhow!("Duplicate node ID: {}", node.id));
+                return Err(Diagnostic::error("duplicate-node", format!("Duplicate node ID: {}", node.id)).with_node(&node.id).into());
             }
         }
 
@@ -50,20 +104,50 @@ impl Compiler {
         let mut blueprint_nodes = Vec::with_capacity(workflow.nodes.len());
         
         let mut adjacency: HashMap<String, Vec<&Edge>> = HashMap::new();
+        let mut error_edges: HashMap<String, &Edge> = HashMap::new();
         for edge in &workflow.edges {
-            adjacency.entry(edge.source.clone()).or_default().push(edge);
+            if edge.branch_type.as_deref() == Some("error") {
+                // Error edges are resolved separately, so they never become a node's `next`
+                if error_edges.insert(edge.source.clone(), edge).is_some() {
+                    return Err(Diagnostic::error("multiple-branches", format!("Multiple error branches for node {}", edge.source)).with_node(&edge.source).into());
+                }
+            } else {
+                adjacency.entry(edge.source.clone()).or_default().push(edge);
+            }
         }
 
+        // Compensation actions become extra nodes appended after the workflow nodes
+        let mut compensation_nodes = Vec::new();
+
         for node in &workflow.nodes {
-            let bp_node = self.transform_node(node, &adjacency)?;
+            if let Some(finding) = adjacency.get(&node.id).and_then(|edges| lint::ignored_edges(node, edges)) {
+                self.findings.push(finding);
+            }
+            let mut bp_node = self.transform_node(node, &adjacency)?;
+            self.apply_policies(node, &mut bp_node)?;
+            if let Some(compensation) = self.compile_compensation(node)? {
+                let index = workflow.nodes.len() + compensation_nodes.len();
+                set_policy(&mut bp_node, "compensate", json!(index));
+                compensation_nodes.push(compensation);
+            }
+            if let Some(edge) = error_edges.get(&node.id) {
+                let target = self.resolve_target(&edge.target)?;
+                set_policy(&mut bp_node, "on_error", json!(target));
+            }
             blueprint_nodes.push(bp_node);
         }
+        blueprint_nodes.extend(compensation_nodes);
+
+        // Validate before fusion merges nodes into one
+        if let Some(registry) = &self.registry {
+            validate_nodes(registry, &blueprint_nodes)?;
+        }
         
         // 3. Start Node
         let start_node_id = workflow.nodes.iter()
             .find(|n| matches!(n.kind, NodeType::Start))
             .map(|n| n.id.clone())
-            .ok_or_else(|| anyhow!("Start node not found"))?;
+            .ok_or_else(|| Diagnostic::error("missing-start", "Start node not found"))?;
             
         let start_index = *self.id_map.get(&start_node_id).unwrap();
 
@@ -72,10 +156,18 @@ impl Compiler {
             name: workflow.name,
             nodes: blueprint_nodes,
             start_index,
+            variables: workflow.variables.into_iter().collect(),
+            inputs: workflow.inputs.into_iter().collect(),
+            default_timeout: workflow.default_timeout,
+            deadline: workflow.deadline,
+            version: String::new(),
         };
 
+        // Lint the graph while every DSL node still has its own blueprint node
+        self.lint(&blueprint)?;
+
         // 4. Pass 3: Optimize (Fusion)
-        if self.config.enable_fusion {
+        let mut blueprint = if self.config.enable_fusion {
             let optimizer = Optimizer::new();
             // TODO: Ideally, this lookup should come from a registry.
             // For now, we hardcode based on known types.
@@ -87,10 +179,37 @@ impl Compiler {
                 }
             };
             
-            optimizer.optimize(blueprint, lookup)
+            optimizer.optimize(blueprint, lookup)?
         } else {
-            Ok(blueprint)
+            blueprint
+        };
+
+        // 5. Version the final graph
+        blueprint.version = blueprint.content_hash();
+        Ok(blueprint)
+    }
+
+    /// Lint findings of the last `compile` call.
+    pub fn lint_findings(&self) -> &[LintFinding] {
+        &self.findings
+    }
+
+    fn lint(&mut self, blueprint: &Blueprint) -> Result<()> {
+        if self.config.lint_level == LintLevel::Allow {
+            self.findings.clear();
+            return Ok(());
         }
+        self.findings.extend(lint::lint_blueprint(blueprint));
+
+        let deny = self.config.lint_level == LintLevel::Deny;
+        let severity = if deny { Severity::Error } else { Severity::Warning };
+        self.diagnostics.extend(self.findings.iter().map(|f| f.to_diagnostic(severity)));
+        if deny && !self.findings.is_empty() {
+            let messages: Vec<String> = self.findings.iter().map(ToString::to_string).collect();
+            let message = format!("Workflow {} failed lint checks: {}", blueprint.id, messages.join("; "));
+            return Err(Diagnostic::error(LINT_FAILED, message).into());
+        }
+        Ok(())
     }
 
     fn transform_node(&self, node: &Node, adjacency: &HashMap<String, Vec<&Edge>>) -> Result<BlueprintNode> {
@@ -100,15 +219,20 @@ impl Compiler {
             NodeType::Start => {
                 let next = edges.first().map(|e| self.resolve_target(&e.target)).transpose()?;
                 Ok(BlueprintNode {
+                    id: node.id.clone(),
                     kind: "start".to_string(),
                     params: json!({ "next": next }),
                 })
             }
             NodeType::End { output } => Ok(BlueprintNode {
+                id: node.id.clone(),
                 kind: "end".to_string(),
                 params: json!({ "output": output }),
             }),
             NodeType::Function { name, params, output } => {
+                if params.contains_key(POLICY_PARAM) {
+                    return Err(Diagnostic::error("reserved-param", format!("Node {}: `{}` is reserved for execution policies", node.id, POLICY_PARAM)).with_node(&node.id).into());
+                }
                 let next = edges.first().map(|e| self.resolve_target(&e.target)).transpose()?;
                 
                 // Combine user params with system params
@@ -123,6 +247,7 @@ impl Compiler {
                 }
                 
                 Ok(BlueprintNode {
+                    id: node.id.clone(),
                     kind: name.clone(),
                     params: full_params,
                 })
@@ -135,13 +260,13 @@ impl Compiler {
                     "expression": expression
                 });
                 
-                if let Some(obj) = full_params.as_object_mut() {
-                    if let Some(n) = next {
-                         obj.insert("next".to_string(), json!(n));
-                    }
+                if let Some(obj) = full_params.as_object_mut()
+                    && let Some(n) = next {
+                    obj.insert("next".to_string(), json!(n));
                 }
 
                 Ok(BlueprintNode {
+                    id: node.id.clone(),
                     kind: "assign".to_string(),
                     params: full_params,
                 })
@@ -154,18 +279,19 @@ impl Compiler {
                      let target_idx = self.resolve_target(&edge.target)?;
                      if edge.branch_type.as_deref() == Some("body") {
                          if body.is_some() {
-                             return Err(anyhow!("Multiple body branches for iteration node {}", node.id));
+                             return Err(Diagnostic::error("multiple-branches", format!("Multiple body branches for iteration node {}", node.id)).with_node(&node.id).into());
                          }
                          body = Some(target_idx);
                      } else {
                          if next.is_some() {
-                             return Err(anyhow!("Multiple next branches for iteration node {}", node.id));
+                             return Err(Diagnostic::error("multiple-branches", format!("Multiple next branches for iteration node {}", node.id)).with_node(&node.id).into());
                          }
                          next = Some(target_idx);
                      }
                 }
 
                 Ok(BlueprintNode {
+                    id: node.id.clone(),
                     kind: "iteration".to_string(),
                     params: json!({
                         "collection": collection,
@@ -189,6 +315,7 @@ impl Compiler {
                 }
                 
                 Ok(BlueprintNode {
+                    id: node.id.clone(),
                     kind: "loop".to_string(),
                     params: json!({
                         "condition": condition,
@@ -219,27 +346,28 @@ impl Compiler {
                                      "target": target_idx
                                  }));
                              } else {
-                                 return Err(anyhow!("Branch {} for node {} has no condition", idx, node.id));
+                                 return Err(Diagnostic::error("invalid-branch", format!("Branch {} for node {} has no condition", idx, node.id)).with_node(&node.id).into());
                              }
                          } else {
-                             return Err(anyhow!("Branch index {} out of bounds for node {}", idx, node.id));
+                             return Err(Diagnostic::error("invalid-branch", format!("Branch index {} out of bounds for node {}", idx, node.id)).with_node(&node.id).into());
                          }
                      } else if edge.branch_type.as_deref() == Some("else") {
                          if else_next.is_some() {
-                             return Err(anyhow!("Multiple else branches found for node {}", node.id));
+                             return Err(Diagnostic::error("multiple-branches", format!("Multiple else branches found for node {}", node.id)).with_node(&node.id).into());
                          }
                          else_next = Some(target_idx);
                      } else {
                          // Fallback: treat as else if no condition/index? Or error?
                          // If ambiguous, treat as else if not set.
                          if else_next.is_some() {
-                              return Err(anyhow!("Multiple else/default branches found for node {}", node.id));
+                              return Err(Diagnostic::error("multiple-branches", format!("Multiple else/default branches found for node {}", node.id)).with_node(&node.id).into());
                          }
                          else_next = Some(target_idx);
                      }
                  }
                  
                  Ok(BlueprintNode {
+                     id: node.id.clone(),
                      kind: "if".to_string(),
                      params: json!({
                          "branches": compiled_branches,
@@ -248,7 +376,7 @@ impl Compiler {
                  })
             }
             NodeType::Parallel { .. } => {
-                Err(anyhow!("Parallel node '{}' should have been expanded", node.id))
+                Err(Diagnostic::error("unexpanded-parallel", format!("Parallel node '{}' should have been expanded", node.id)).with_node(&node.id).into())
             }
             NodeType::Fork { branch_start_ids, join_id } => {
                 let mut targets = Vec::new();
@@ -258,6 +386,7 @@ impl Compiler {
                 let join_target = self.resolve_target(join_id)?;
                 
                 Ok(BlueprintNode {
+                    id: node.id.clone(),
                     kind: "fork".to_string(),
                     params: json!({
                         "targets": targets,
@@ -268,6 +397,7 @@ impl Compiler {
             NodeType::Join { expect_count } => {
                  let next = edges.first().map(|e| self.resolve_target(&e.target)).transpose()?;
                  Ok(BlueprintNode {
+                     id: node.id.clone(),
                      kind: "join".to_string(),
                      params: json!({
                          "next": next,
@@ -278,9 +408,56 @@ impl Compiler {
         }
     }
     
+    /// Carries node-level execution policies (e.g. `retry`) into the blueprint params,
+    /// where the engine picks them up when preparing the node.
+    fn apply_policies(&self, node: &Node, bp_node: &mut BlueprintNode) -> Result<()> {
+        if let Some(retry) = &node.retry {
+            set_policy(bp_node, "retry", serde_json::to_value(retry)?);
+        }
+        if let Some(timeout) = node.timeout {
+            set_policy(bp_node, "timeout", json!(timeout));
+        }
+        Ok(())
+    }
+
+    fn compile_compensation(&self, node: &Node) -> Result<Option<BlueprintNode>> {
+        let Some(compensation) = &node.compensate else {
+            return Ok(None);
+        };
+        if !matches!(node.kind, NodeType::Function { .. }) {
+            return Err(Diagnostic::error("invalid-compensation", format!("Only Function nodes can declare a compensation: {}", node.id)).with_node(&node.id).into());
+        }
+        Ok(Some(BlueprintNode {
+            id: format!("{}.compensate", node.id),
+            kind: compensation.name.clone(),
+            params: serde_json::to_value(&compensation.params)?,
+        }))
+    }
+
     fn resolve_target(&self, target_id: &str) -> Result<NodeIndex> {
         self.id_map.get(target_id)
             .cloned()
-            .ok_or_else(|| anyhow!("Target node not found: {}", target_id))
+            .ok_or_else(|| Diagnostic::error("unknown-target", format!("Target node not found: {}", target_id)).with_node(target_id).into())
+    }
+}
+
+/// Checks every node against its definition in the registry.
+/// Stores a policy under the reserved `POLICY_PARAM`, so it never mixes with the node's own params.
+fn set_policy(bp_node: &mut BlueprintNode, name: &str, value: Value) {
+    if let Some(obj) = bp_node.params.as_object_mut() {
+        let policy = obj.entry(POLICY_PARAM).or_insert_with(|| json!({}));
+        if let Some(policy) = policy.as_object_mut() {
+            policy.insert(name.to_string(), value);
+        }
+    }
+}
+
+fn validate_nodes(registry: &NodeRegistry, nodes: &[BlueprintNode]) -> Result<()> {
+    for node in nodes {
+        let definition = registry.get(&node.kind)
+            .ok_or_else(|| Diagnostic::error("unknown-node-kind", format!("Node {}: unknown node kind `{}`", node.id, node.kind)).with_node(&node.id))?;
+        definition.validate(&node.params)
+            .map_err(|e| Diagnostic::error("invalid-params", format!("Node {}: invalid `{}` params: {}", node.id, node.kind, e)).with_node(&node.id))?;
     }
+    Ok(())
 }
@@ -0,0 +1,223 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// Position in a workflow source file. Line and column are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    /// Length of the offending token, in characters
+    pub length: usize,
+}
+
+/// A problem found while loading or compiling a workflow.
+///
+/// Compiler errors are `Diagnostic`s wrapped in `anyhow::Error`, so callers can downcast
+/// them; `Display` only shows the message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable identifier of the kind of problem, e.g. `unknown-target`
+    pub code: String,
+    pub message: String,
+    /// The node the problem is about (or the missing node a reference points to)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code: code.to_string(),
+            message: message.into(),
+            node_id: None,
+            span: None,
+        }
+    }
+
+    pub fn error(code: &str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, code, message)
+    }
+
+    pub fn warning(code: &str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, code, message)
+    }
+
+    pub fn with_node(mut self, node_id: &str) -> Self {
+        self.node_id = Some(node_id.to_string());
+        self
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// The diagnostic inside `error`, or a generic one carrying its message.
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        match error.downcast_ref::<Diagnostic>() {
+            Some(diagnostic) => diagnostic.clone(),
+            None => Self::error("error", format!("{:#}", error)),
+        }
+    }
+
+    /// Renders the diagnostic like a compiler message, with the source line it points at.
+    pub fn render(&self, sources: Option<&SourceMap>) -> String {
+        let mut out = format!("{}[{}]: {}", self.severity.as_str(), self.code, self.message);
+        let Some(span) = &self.span else {
+            return out;
+        };
+        out.push_str(&format!("\n  --> {}:{}:{}", span.file, span.line, span.column));
+
+        let line = sources
+            .filter(|s| s.file() == span.file)
+            .and_then(|s| s.line(span.line));
+        if let Some(line) = line {
+            let gutter = " ".repeat(span.line.to_string().len());
+            let marker = format!(
+                "{}{}",
+                " ".repeat(span.column.saturating_sub(1)),
+                "^".repeat(span.length.max(1)),
+            );
+            out.push_str(&format!("\n{} |\n{} | {}\n{} | {}", gutter, span.line, line, gutter, marker));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Keys whose value names another node.
+const REFERENCE_KEYS: &[&str] = &["source", "target", "next", "else", "body", "on_error"];
+
+/// Where node ids are declared and referenced in a workflow's YAML source.
+///
+/// Built from a line scan of block-style YAML (`id: x`, `target: x`); flow-style mappings
+/// are not indexed.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    file: String,
+    lines: Vec<String>,
+    // First `id:` per node id
+    nodes: HashMap<String, Span>,
+    // First reference per node id
+    references: HashMap<String, Span>,
+}
+
+impl SourceMap {
+    pub fn from_yaml(file: &str, source: &str) -> Self {
+        let mut map = Self {
+            file: file.to_string(),
+            lines: source.lines().map(str::to_string).collect(),
+            ..Default::default()
+        };
+
+        for (index, line) in source.lines().enumerate() {
+            let Some((key, value, column)) = scalar_entry(line) else {
+                continue;
+            };
+            let span = Span {
+                file: file.to_string(),
+                line: index + 1,
+                column: column + 1,
+                length: value.raw_len,
+            };
+            if key == "id" {
+                map.nodes.entry(value.text.to_string()).or_insert(span);
+            } else if REFERENCE_KEYS.contains(&key) {
+                map.references.entry(value.text.to_string()).or_insert(span);
+            }
+        }
+        map
+    }
+
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The 1-based source line.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        self.lines.get(line.checked_sub(1)?).map(String::as_str)
+    }
+
+    /// Where the node is declared.
+    pub fn node_span(&self, node_id: &str) -> Option<&Span> {
+        self.nodes.get(node_id)
+    }
+
+    /// Points a diagnostic without a span at its node's declaration, or at the first
+    /// reference to it when the node does not exist.
+    pub fn locate(&self, diagnostic: &mut Diagnostic) {
+        if diagnostic.span.is_some() {
+            return;
+        }
+        if let Some(node_id) = &diagnostic.node_id {
+            diagnostic.span = self.nodes.get(node_id)
+                .or_else(|| self.references.get(node_id))
+                .cloned();
+        }
+    }
+}
+
+struct ScalarValue<'a> {
+    text: &'a str,
+    // Length in the source, quotes included
+    raw_len: usize,
+}
+
+/// Splits `  - key: "value"  # comment` into the key, the unquoted value and the
+/// 0-based column of the value.
+fn scalar_entry(line: &str) -> Option<(&str, ScalarValue<'_>, usize)> {
+    let mut rest = line.trim_start();
+    if let Some(item) = rest.strip_prefix("- ") {
+        rest = item.trim_start();
+    }
+    let key_start = line.len() - rest.len();
+    let (key, after) = rest.split_once(':')?;
+    let value = after.trim_start();
+    if value.starts_with('#') {
+        return None;
+    }
+    let value_start = key_start + key.len() + 1 + (after.len() - value.len());
+
+    let (text, raw_len) = if let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let end = value[1..].find(quote)?;
+        let text = &value[1..1 + end];
+        (text, text.chars().count() + 2)
+    } else {
+        let text = value.split(" #").next().unwrap_or_default().trim_end();
+        (text, text.chars().count())
+    };
+    if text.is_empty() {
+        return None;
+    }
+    let column = line[..value_start].chars().count();
+    Some((key.trim(), ScalarValue { text, raw_len }, column))
+}
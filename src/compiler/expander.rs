@@ -1,42 +1,98 @@
 use crate::dsl::{Workflow, Node, NodeType, Edge, Branch};
+use crate::compiler::diagnostic::Diagnostic;
 use anyhow::Result;
 
 pub struct Expander {
     // 可以添加状态，如生成的 ID 计数器
 }
 
+impl Default for Expander {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Expander {
     pub fn new() -> Self {
         Self {}
     }
 
-    pub fn expand(&self, workflow: Workflow) -> Result<Workflow> {
+    pub fn expand(&self, mut workflow: Workflow) -> Result<Workflow> {
         let mut new_nodes = Vec::new();
-        let mut new_edges = workflow.edges.clone();
+        let mut new_edges = std::mem::take(&mut workflow.edges);
+
+        // 先把节点上的 next / else / body / on_error 语法糖展开为 Edge，并收集各分支内部的 Edge，
+        // 这样 Parallel 的 next 也会随下面的重定向改由 Join 发出
+        self.lower_shorthand(&mut workflow.nodes, &mut new_edges)?;
         
         // 待处理的边重定向：Map<OldTarget, NewTarget>
         // 当 Parallel 节点被替换为 Fork 时，指向 Parallel 的边需要改指 Fork
         // 当 Parallel 节点有 next 时，Join 节点需要指向 next
-        // 但这里的 DSL 结构中，next 是通过 Edge 定义的。
+        // next 语法糖已在上一步展开为 Edge，与显式定义的 Edge 一并处理。
         
         // 我们采用一种简单的策略：
         // 1. 遍历所有节点。
-        // 2. 如果是 Parallel，生成一堆新节点和边，替换掉它。
+        // 2. 如果是 Parallel，生成一堆新节点和边，替换掉它 (分支内的 Parallel 递归展开)。
         // 3. 修正指向 Parallel 的边。
+        self.expand_nodes(std::mem::take(&mut workflow.nodes), &mut new_nodes, &mut new_edges)?;
 
-        for node in workflow.nodes {
+        Ok(Workflow {
+            nodes: new_nodes,
+            edges: new_edges,
+            ..workflow
+        })
+    }
+
+    fn expand_nodes(&self, nodes: Vec<Node>, new_nodes: &mut Vec<Node>, new_edges: &mut Vec<Edge>) -> Result<()> {
+        for node in nodes {
             if let NodeType::Parallel { branches } = node.kind {
-                self.expand_parallel(node.id, branches, &mut new_nodes, &mut new_edges)?;
+                self.expand_parallel(node.id, branches, new_nodes, new_edges)?;
             } else {
                 new_nodes.push(node);
             }
         }
+        Ok(())
+    }
 
-        Ok(Workflow {
-            nodes: new_nodes,
-            edges: new_edges,
-            ..workflow
-        })
+    fn lower_shorthand(&self, nodes: &mut [Node], edges: &mut Vec<Edge>) -> Result<()> {
+        for node in nodes.iter_mut() {
+            if let NodeType::Parallel { branches } = &mut node.kind {
+                for branch in branches.iter_mut() {
+                    edges.append(&mut branch.edges);
+                    self.lower_shorthand(&mut branch.nodes, edges)?;
+                }
+            }
+
+            let declared = [
+                (Shorthand::Next, node.next.take()),
+                (Shorthand::Else, node.else_next.take()),
+                (Shorthand::Body, node.body.take()),
+                (Shorthand::OnError, node.on_error.take()),
+            ];
+            for (shorthand, target) in declared {
+                let Some(target) = target else { continue };
+                shorthand.check_placement(node)?;
+
+                match edges.iter().find(|e| e.source == node.id && shorthand.matches(e)) {
+                    Some(edge) if edge.target == target => {}
+                    Some(edge) => {
+                        let message = format!(
+                            "Node {} declares `{}: {}` but an edge routes it to {}",
+                            node.id, shorthand.keyword(), target, edge.target
+                        );
+                        return Err(Diagnostic::error("shorthand-conflict", message).with_node(&node.id).into());
+                    }
+                    None => edges.push(Edge {
+                        source: node.id.clone(),
+                        target,
+                        condition: None,
+                        branch_type: shorthand.branch_type().map(str::to_string),
+                        branch_index: None,
+                    }),
+                }
+            }
+        }
+        Ok(())
     }
 
     fn expand_parallel(
@@ -46,8 +102,8 @@ impl Expander {
         new_nodes: &mut Vec<Node>,
         new_edges: &mut Vec<Edge>,
     ) -> Result<()> {
-        let fork_id = format!("{}_fork", parallel_id);
-        let join_id = format!("{}_join", parallel_id);
+        let fork_id = fork_id(&parallel_id);
+        let join_id = join_id(&parallel_id);
 
         let mut branch_start_ids = Vec::new();
 
@@ -56,17 +112,15 @@ impl Expander {
                 continue;
             }
 
-            // 1. 提取分支内节点
-            let branch_len = branch.nodes.len();
-            for (i, node) in branch.nodes.iter().enumerate() {
-                new_nodes.push(node.clone());
-                
-                // 2. 自动生成分支内部的连接边 (线性连接)
-                if i < branch_len - 1 {
-                    let next_node = &branch.nodes[i+1];
+            // 1. 分支内没有任何跳转时按声明顺序线性连接，否则分支是一个子图
+            //    (分支的 edges、节点语法糖以及顶层 edges 中从分支节点出发的边)
+            let ids: Vec<&str> = branch.nodes.iter().map(|n| n.id.as_str()).collect();
+            let linked = new_edges.iter().any(|e| ids.contains(&e.source.as_str()) && !is_error_edge(e));
+            if !linked {
+                for pair in ids.windows(2) {
                     new_edges.push(Edge {
-                        source: node.id.clone(),
-                        target: next_node.id.clone(),
+                        source: pair[0].to_string(),
+                        target: pair[1].to_string(),
                         condition: None,
                         branch_type: None,
                         branch_index: None,
@@ -74,40 +128,42 @@ impl Expander {
                 }
             }
 
-            // 记录分支头尾
-            let head_id = branch.nodes[0].id.clone();
-            let tail_id = branch.nodes[branch_len - 1].id.clone();
+            // 2. 记录分支入口和候选出口：嵌套的 Parallel 从 Fork 进入、从 Join 离开，End 不汇聚
+            branch_start_ids.push(entry_id(&branch.nodes[0]));
+            let exits: Vec<String> = branch.nodes.iter()
+                .filter(|n| !matches!(n.kind, NodeType::End { .. }))
+                .map(exit_id)
+                .collect();
 
-            branch_start_ids.push(head_id);
+            // 3. 提取分支内节点，递归展开嵌套的 Parallel
+            self.expand_nodes(branch.nodes, new_nodes, new_edges)?;
 
-            // 3. 连接 分支尾 -> Join
-            new_edges.push(Edge {
-                source: tail_id,
-                target: join_id.clone(),
-                condition: None,
-                branch_type: None,
-                branch_index: None,
-            });
+            // 4. 没有后继的节点即分支出口，连接 出口 -> Join
+            for exit in exits {
+                if !new_edges.iter().any(|e| e.source == exit && !is_error_edge(e)) {
+                    new_edges.push(Edge {
+                        source: exit,
+                        target: join_id.clone(),
+                        condition: None,
+                        branch_type: None,
+                        branch_index: None,
+                    });
+                }
+            }
         }
 
-        // 4. 创建 Fork 节点
-        new_nodes.push(Node {
-            id: fork_id.clone(),
-            kind: NodeType::Fork {
-                branch_start_ids: branch_start_ids.clone(),
-                join_id: join_id.clone(),
-            },
-        });
-
-        // 5. 创建 Join 节点
-        new_nodes.push(Node {
-            id: join_id.clone(),
-            kind: NodeType::Join {
-                expect_count: branch_start_ids.len(),
-            },
-        });
-
-        // 6. 修正外部边：指向 Parallel 的 -> 指向 Fork
+        // 5. 创建 Fork 节点
+        new_nodes.push(Node::new(fork_id.clone(), NodeType::Fork {
+            branch_start_ids: branch_start_ids.clone(),
+            join_id: join_id.clone(),
+        }));
+
+        // 6. 创建 Join 节点
+        new_nodes.push(Node::new(join_id.clone(), NodeType::Join {
+            expect_count: branch_start_ids.len(),
+        }));
+
+        // 7. 修正外部边：指向 Parallel 的 -> 指向 Fork
         for edge in new_edges.iter_mut() {
             if edge.target == parallel_id {
                 edge.target = fork_id.clone();
@@ -121,3 +177,83 @@ impl Expander {
         Ok(())
     }
 }
+
+fn fork_id(parallel_id: &str) -> String {
+    format!("{}_fork", parallel_id)
+}
+
+fn join_id(parallel_id: &str) -> String {
+    format!("{}_join", parallel_id)
+}
+
+/// 进入节点时的目标 ID (Parallel 展开后为其 Fork)
+fn entry_id(node: &Node) -> String {
+    match node.kind {
+        NodeType::Parallel { .. } => fork_id(&node.id),
+        _ => node.id.clone(),
+    }
+}
+
+/// 离开节点时的来源 ID (Parallel 展开后为其 Join)
+fn exit_id(node: &Node) -> String {
+    match node.kind {
+        NodeType::Parallel { .. } => join_id(&node.id),
+        _ => node.id.clone(),
+    }
+}
+
+/// 错误分支不算节点的后继
+fn is_error_edge(edge: &Edge) -> bool {
+    edge.branch_type.as_deref() == Some("error")
+}
+
+/// 节点级的跳转语法糖，每种对应一类 Edge
+#[derive(Clone, Copy)]
+enum Shorthand {
+    Next,
+    Else,
+    Body,
+    OnError,
+}
+
+impl Shorthand {
+    fn keyword(&self) -> &'static str {
+        match self {
+            Shorthand::Next => "next",
+            Shorthand::Else => "else",
+            Shorthand::Body => "body",
+            Shorthand::OnError => "on_error",
+        }
+    }
+
+    fn branch_type(&self) -> Option<&'static str> {
+        match self {
+            Shorthand::Next => None,
+            Shorthand::Else => Some("else"),
+            Shorthand::Body => Some("body"),
+            Shorthand::OnError => Some("error"),
+        }
+    }
+
+    /// 显式 Edge 是否与该语法糖表达同一条跳转
+    fn matches(&self, edge: &Edge) -> bool {
+        match self {
+            Shorthand::Next => edge.branch_type.is_none() && edge.condition.is_none() && edge.branch_index.is_none(),
+            _ => edge.branch_type.as_deref() == self.branch_type(),
+        }
+    }
+
+    fn check_placement(&self, node: &Node) -> Result<()> {
+        let allowed = match self {
+            Shorthand::Next => !matches!(node.kind, NodeType::End { .. } | NodeType::If { .. }),
+            Shorthand::Else => matches!(node.kind, NodeType::If { .. }),
+            Shorthand::Body => matches!(node.kind, NodeType::Iteration { .. } | NodeType::Loop { .. }),
+            Shorthand::OnError => true,
+        };
+        if !allowed {
+            let message = format!("Node {} cannot declare `{}`", node.id, self.keyword());
+            return Err(Diagnostic::error("invalid-shorthand", message).with_node(&node.id).into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,396 @@
+use crate::dsl::{Edge, Node, NodeType, Workflow};
+use crate::runtime::blueprint::Blueprint;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// How a node is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    /// Start and End
+    Terminal,
+    Task,
+    /// If
+    Decision,
+    /// Loop and Iteration
+    Loop,
+    /// Parallel, and the Fork it expands to
+    Fork,
+    Join,
+    /// Chain of sync nodes merged by the optimizer
+    Fused,
+    /// Referenced by an edge but not declared
+    Missing,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphNode {
+    pub id: String,
+    /// Lines separated by `\n`
+    pub label: String,
+    pub shape: Shape,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub label: Option<String>,
+    /// Error, join and compensation edges, which are not taken on the normal path
+    pub dashed: bool,
+}
+
+/// Nodes drawn together in a box, e.g. a branch of a Parallel node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cluster {
+    pub label: String,
+    pub nodes: Vec<String>,
+    pub clusters: Vec<Cluster>,
+}
+
+/// A workflow at one compilation stage, ready to render as Graphviz DOT or Mermaid.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Graph {
+    pub id: String,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    pub clusters: Vec<Cluster>,
+}
+
+impl Graph {
+    /// The workflow as written (`nodes`/`edges` plus node-level shorthand), or as
+    /// produced by `Expander::expand` with Fork/Join nodes.
+    pub fn from_workflow(workflow: &Workflow) -> Self {
+        let mut graph = Graph { id: workflow.id.clone(), ..Default::default() };
+        let mut nodes: Vec<&Node> = Vec::new();
+        let mut edges: Vec<&Edge> = workflow.edges.iter().collect();
+        graph.clusters = graph.add_nodes(&workflow.nodes, &mut nodes, &mut edges);
+
+        for edge in edges {
+            let source = nodes.iter().find(|n| n.id == edge.source).copied();
+            graph.push_edge(&edge.source, &edge.target, workflow_edge_label(source, edge), edge.branch_type.as_deref() == Some("error"));
+        }
+        for node in &nodes {
+            graph.add_shorthand_edges(node);
+        }
+
+        graph.add_missing_nodes();
+        graph
+    }
+
+    /// The compiled graph the engine runs, including `fused` nodes.
+    pub fn from_blueprint(blueprint: &Blueprint) -> Self {
+        let mut graph = Graph { id: blueprint.id.clone(), ..Default::default() };
+        // Blueprints read from older sources may lack node ids
+        let key = |index: usize| match blueprint.nodes.get(index) {
+            Some(node) if !node.id.is_empty() => node.id.clone(),
+            _ => format!("#{}", index),
+        };
+
+        for (i, node) in blueprint.nodes.iter().enumerate() {
+            let (label, shape) = match node.kind.as_str() {
+                "start" | "end" => (key(i), Shape::Terminal),
+                "if" => (key(i), Shape::Decision),
+                "loop" | "iteration" => (format!("{}\n{}", key(i), node.kind), Shape::Loop),
+                "fork" => (format!("{}\nfork", key(i)), Shape::Fork),
+                "join" => (format!("{}\njoin", key(i)), Shape::Join),
+                "fused" => {
+                    let ops: Vec<&str> = node.params.get("ops").and_then(Value::as_array).into_iter().flatten()
+                        .filter_map(|op| op.get("kind").and_then(Value::as_str))
+                        .collect();
+                    (format!("{}\nfused: {}", key(i), ops.join(" → ")), Shape::Fused)
+                }
+                kind => (format!("{}\n{}", key(i), kind), Shape::Task),
+            };
+            graph.nodes.push(GraphNode { id: key(i), label, shape });
+        }
+
+        for (i, node) in blueprint.nodes.iter().enumerate() {
+            let params = &node.params;
+            let index = |name: &str| params.get(name).and_then(Value::as_u64).map(|t| key(t as usize));
+
+            if let Some(next) = index("next") {
+                graph.push_edge(&key(i), &next, None, false);
+            }
+            for (b, target) in params.get("targets").and_then(Value::as_array).into_iter().flatten().enumerate() {
+                if let Some(target) = target.as_u64() {
+                    graph.push_edge(&key(i), &key(target as usize), Some(format!("branch {}", b)), false);
+                }
+            }
+            if let Some(join) = index("join_target") {
+                graph.push_edge(&key(i), &join, Some("join".to_string()), true);
+            }
+            for branch in params.get("branches").and_then(Value::as_array).into_iter().flatten() {
+                if let Some(target) = branch.get("target").and_then(Value::as_u64) {
+                    let condition = branch.get("condition").and_then(Value::as_str).map(str::to_string);
+                    graph.push_edge(&key(i), &key(target as usize), condition, false);
+                }
+            }
+            let labelled = [("else_next", "else", false), ("body", "body", false)];
+            for (name, label, dashed) in labelled {
+                if let Some(target) = index(name) {
+                    graph.push_edge(&key(i), &target, Some(label.to_string()), dashed);
+                }
+            }
+            for (name, label) in [("on_error", "error"), ("compensate", "compensate")] {
+                if let Some(target) = node.policy(name).and_then(Value::as_u64) {
+                    graph.push_edge(&key(i), &key(target as usize), Some(label.to_string()), true);
+                }
+            }
+        }
+
+        graph.add_missing_nodes();
+        graph
+    }
+
+    /// Graphviz DOT source.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "digraph {} {{", dot_quote(&self.id));
+        let _ = writeln!(out, "  node [fontname=\"Helvetica\"];");
+        let _ = writeln!(out, "  edge [fontname=\"Helvetica\", fontsize=10];");
+
+        let clustered: HashSet<&str> = self.clustered_nodes();
+        for node in self.nodes.iter().filter(|n| !clustered.contains(n.id.as_str())) {
+            let _ = writeln!(out, "  {}", self.dot_node(node));
+        }
+        let mut counter = 0;
+        for cluster in &self.clusters {
+            self.dot_cluster(cluster, 1, &mut counter, &mut out);
+        }
+
+        for edge in &self.edges {
+            let mut attrs = Vec::new();
+            if let Some(label) = &edge.label {
+                attrs.push(format!("label={}", dot_quote(label)));
+            }
+            if edge.dashed {
+                attrs.push("style=dashed".to_string());
+            }
+            let attrs = if attrs.is_empty() { String::new() } else { format!(" [{}]", attrs.join(", ")) };
+            let _ = writeln!(out, "  {} -> {}{};", dot_quote(&edge.source), dot_quote(&edge.target), attrs);
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Mermaid flowchart source. Node ids are replaced by `n<index>`, since Mermaid
+    /// does not accept arbitrary characters in them.
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("flowchart TD\n");
+
+        let clustered: HashSet<&str> = self.clustered_nodes();
+        for node in self.nodes.iter().filter(|n| !clustered.contains(n.id.as_str())) {
+            let _ = writeln!(out, "  {}", self.mermaid_node(node));
+        }
+        let mut counter = 0;
+        for cluster in &self.clusters {
+            self.mermaid_cluster(cluster, 1, &mut counter, &mut out);
+        }
+
+        for edge in &self.edges {
+            let arrow = if edge.dashed { "-.->" } else { "-->" };
+            let label = edge.label.as_ref()
+                .map(|l| format!("|\"{}\"|", mermaid_escape(l)))
+                .unwrap_or_default();
+            let _ = writeln!(out, "  {} {}{} {}", self.mermaid_id(&edge.source), arrow, label, self.mermaid_id(&edge.target));
+        }
+        out
+    }
+
+    /// Adds the nodes, flattening Parallel branches into clusters and collecting their
+    /// edges; returns those clusters.
+    fn add_nodes<'a>(&mut self, dsl_nodes: &'a [Node], all: &mut Vec<&'a Node>, edges: &mut Vec<&'a Edge>) -> Vec<Cluster> {
+        let mut clusters = Vec::new();
+        for node in dsl_nodes {
+            all.push(node);
+            self.nodes.push(GraphNode { id: node.id.clone(), label: workflow_node_label(node), shape: workflow_node_shape(node) });
+
+            if let NodeType::Parallel { branches } = &node.kind {
+                let mut cluster = Cluster { label: node.id.clone(), nodes: Vec::new(), clusters: Vec::new() };
+                for (b, branch) in branches.iter().enumerate() {
+                    let nested = self.add_nodes(&branch.nodes, all, edges);
+                    edges.extend(&branch.edges);
+                    if let Some(head) = branch.nodes.first() {
+                        self.push_edge(&node.id, &head.id, Some(format!("branch {}", b)), false);
+                    }
+                    cluster.clusters.push(Cluster {
+                        label: format!("branch {}", b),
+                        nodes: branch.nodes.iter().map(|n| n.id.clone()).collect(),
+                        clusters: nested,
+                    });
+                }
+                clusters.push(cluster);
+            }
+        }
+        clusters
+    }
+
+    fn add_shorthand_edges(&mut self, node: &Node) {
+        if let Some(next) = &node.next {
+            self.push_edge(&node.id, next, None, false);
+        }
+        if let Some(else_next) = &node.else_next {
+            self.push_edge(&node.id, else_next, Some("else".to_string()), false);
+        }
+        if let Some(body) = &node.body {
+            self.push_edge(&node.id, body, Some("body".to_string()), false);
+        }
+        if let Some(on_error) = &node.on_error {
+            self.push_edge(&node.id, on_error, Some("error".to_string()), true);
+        }
+        if let NodeType::Fork { branch_start_ids, join_id } = &node.kind {
+            for (b, head) in branch_start_ids.iter().enumerate() {
+                self.push_edge(&node.id, head, Some(format!("branch {}", b)), false);
+            }
+            self.push_edge(&node.id, join_id, Some("join".to_string()), true);
+        }
+    }
+
+    fn push_edge(&mut self, source: &str, target: &str, label: Option<String>, dashed: bool) {
+        self.edges.push(GraphEdge { source: source.to_string(), target: target.to_string(), label, dashed });
+    }
+
+    fn add_missing_nodes(&mut self) {
+        let mut known: HashSet<String> = self.nodes.iter().map(|n| n.id.clone()).collect();
+        let endpoints: Vec<String> = self.edges.iter().flat_map(|e| [e.source.clone(), e.target.clone()]).collect();
+        for id in endpoints {
+            if known.insert(id.clone()) {
+                self.nodes.push(GraphNode { label: format!("{}\n(missing)", id), id, shape: Shape::Missing });
+            }
+        }
+    }
+
+    fn clustered_nodes(&self) -> HashSet<&str> {
+        fn collect<'a>(cluster: &'a Cluster, out: &mut HashSet<&'a str>) {
+            out.extend(cluster.nodes.iter().map(String::as_str));
+            for nested in &cluster.clusters {
+                collect(nested, out);
+            }
+        }
+        let mut out = HashSet::new();
+        for cluster in &self.clusters {
+            collect(cluster, &mut out);
+        }
+        out
+    }
+
+    fn node(&self, id: &str) -> Option<&GraphNode> {
+        self.nodes.iter().find(|n| n.id == id)
+    }
+
+    fn dot_node(&self, node: &GraphNode) -> String {
+        let shape = match node.shape {
+            Shape::Terminal => "shape=oval",
+            Shape::Task => "shape=box",
+            Shape::Decision => "shape=diamond",
+            Shape::Loop => "shape=hexagon",
+            Shape::Fork => "shape=trapezium",
+            Shape::Join => "shape=invtrapezium",
+            Shape::Fused => "shape=box, peripheries=2",
+            Shape::Missing => "shape=box, style=dashed",
+        };
+        format!("{} [label={}, {}];", dot_quote(&node.id), dot_quote(&node.label), shape)
+    }
+
+    fn dot_cluster(&self, cluster: &Cluster, depth: usize, counter: &mut usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        let _ = writeln!(out, "{}subgraph cluster_{} {{", indent, counter);
+        *counter += 1;
+        let _ = writeln!(out, "{}  label={};", indent, dot_quote(&cluster.label));
+        let _ = writeln!(out, "{}  style=rounded;", indent);
+        for node in cluster.nodes.iter().filter_map(|id| self.node(id)) {
+            let _ = writeln!(out, "{}  {}", indent, self.dot_node(node));
+        }
+        for nested in &cluster.clusters {
+            self.dot_cluster(nested, depth + 1, counter, out);
+        }
+        let _ = writeln!(out, "{}}}", indent);
+    }
+
+    fn mermaid_id(&self, id: &str) -> String {
+        match self.nodes.iter().position(|n| n.id == id) {
+            Some(index) => format!("n{}", index),
+            None => mermaid_escape(id),
+        }
+    }
+
+    fn mermaid_node(&self, node: &GraphNode) -> String {
+        let label = format!("\"{}\"", mermaid_escape(&node.label));
+        let shaped = match node.shape {
+            Shape::Terminal => format!("([{}])", label),
+            Shape::Task | Shape::Missing => format!("[{}]", label),
+            Shape::Decision => format!("{{{}}}", label),
+            Shape::Loop => format!("{{{{{}}}}}", label),
+            Shape::Fork => format!("[/{}\\]", label),
+            Shape::Join => format!("[\\{}/]", label),
+            Shape::Fused => format!("[[{}]]", label),
+        };
+        format!("{}{}", self.mermaid_id(&node.id), shaped)
+    }
+
+    fn mermaid_cluster(&self, cluster: &Cluster, depth: usize, counter: &mut usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        let _ = writeln!(out, "{}subgraph c{} [\"{}\"]", indent, counter, mermaid_escape(&cluster.label));
+        *counter += 1;
+        for node in cluster.nodes.iter().filter_map(|id| self.node(id)) {
+            let _ = writeln!(out, "{}  {}", indent, self.mermaid_node(node));
+        }
+        for nested in &cluster.clusters {
+            self.mermaid_cluster(nested, depth + 1, counter, out);
+        }
+        let _ = writeln!(out, "{}end", indent);
+    }
+}
+
+fn workflow_node_label(node: &Node) -> String {
+    match &node.kind {
+        NodeType::Start | NodeType::End { .. } | NodeType::If { .. } => node.id.clone(),
+        NodeType::Function { name, .. } => format!("{}\n{}", node.id, name),
+        NodeType::Assign { .. } => format!("{}\nassign", node.id),
+        NodeType::Parallel { .. } => format!("{}\nparallel", node.id),
+        NodeType::Iteration { collection, item_var } => format!("{}\nfor {} in {}", node.id, item_var, collection),
+        NodeType::Loop { condition } => format!("{}\nwhile {}", node.id, condition),
+        NodeType::Fork { .. } => format!("{}\nfork", node.id),
+        NodeType::Join { expect_count } => format!("{}\njoin {}", node.id, expect_count),
+    }
+}
+
+fn workflow_node_shape(node: &Node) -> Shape {
+    match &node.kind {
+        NodeType::Start | NodeType::End { .. } => Shape::Terminal,
+        NodeType::Function { .. } | NodeType::Assign { .. } => Shape::Task,
+        NodeType::If { .. } => Shape::Decision,
+        NodeType::Iteration { .. } | NodeType::Loop { .. } => Shape::Loop,
+        NodeType::Parallel { .. } | NodeType::Fork { .. } => Shape::Fork,
+        NodeType::Join { .. } => Shape::Join,
+    }
+}
+
+/// The condition of an If edge (inline or by `branch_index`), else its branch type.
+fn workflow_edge_label(source: Option<&Node>, edge: &Edge) -> Option<String> {
+    if let Some(condition) = &edge.condition {
+        return Some(condition.clone());
+    }
+    if let (Some(Node { kind: NodeType::If { branches }, .. }), Some(index)) = (source, edge.branch_index)
+        && let Some(condition) = branches.get(index).and_then(|b| b.get("condition")) {
+        return Some(condition.clone());
+    }
+    match (source.map(|n| &n.kind), edge.branch_type.as_deref()) {
+        (_, Some(branch_type)) => Some(branch_type.to_string()),
+        // The compiler treats an unconditional If edge as the else branch
+        (Some(NodeType::If { .. }), None) => Some("else".to_string()),
+        _ => None,
+    }
+}
+
+fn dot_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace('\n', "<br/>")
+}
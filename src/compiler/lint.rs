@@ -0,0 +1,172 @@
+use crate::dsl::{Edge, Node, NodeType};
+use crate::runtime::blueprint::Blueprint;
+use crate::compiler::diagnostic::{Diagnostic, Severity};
+use std::collections::HashSet;
+use std::fmt;
+
+/// What the compiler does with lint findings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LintLevel {
+    /// Skip the analysis
+    Allow,
+    /// Report findings as warnings (`Compiler::diagnostics`)
+    #[default]
+    Warn,
+    /// Report findings as errors and fail compilation if there is any
+    Deny,
+}
+
+/// Structural problems the compiler can detect in a workflow graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// Not reachable from the start node
+    Unreachable,
+    /// Reachable, but no path leads to an End node
+    NoPathToEnd,
+    /// If node that continues nowhere when no condition matches
+    IfWithoutFallback,
+    /// Loop or Iteration node without a body edge
+    MissingBody,
+    /// Outgoing edges the node never follows
+    IgnoredEdges,
+}
+
+impl Lint {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Lint::Unreachable => "unreachable",
+            Lint::NoPathToEnd => "no_path_to_end",
+            Lint::IfWithoutFallback => "if_without_fallback",
+            Lint::MissingBody => "missing_body",
+            Lint::IgnoredEdges => "ignored_edges",
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    pub lint: Lint,
+    pub node_id: String,
+    pub message: String,
+}
+
+impl LintFinding {
+    fn new(lint: Lint, node_id: &str, message: String) -> Self {
+        Self { lint, node_id: node_id.to_string(), message }
+    }
+
+    pub fn to_diagnostic(&self, severity: Severity) -> Diagnostic {
+        Diagnostic::new(severity, &self.lint.as_str().replace('_', "-"), self.message.clone())
+            .with_node(&self.node_id)
+    }
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.lint, self.message)
+    }
+}
+
+/// Reports the edges of `node` that the compiler drops: nodes with a single exit only
+/// follow their first edge, End nodes follow none.
+pub fn ignored_edges(node: &Node, edges: &[&Edge]) -> Option<LintFinding> {
+    let followed = match node.kind {
+        NodeType::End { .. } => 0,
+        NodeType::Start | NodeType::Function { .. } | NodeType::Assign { .. } | NodeType::Join { .. } => 1,
+        NodeType::Loop { .. } => {
+            // The last body edge and the last other edge win
+            let body = edges.iter().filter(|e| e.branch_type.as_deref() == Some("body")).count();
+            body.min(1) + (edges.len() - body).min(1)
+        }
+        _ => return None,
+    };
+    if edges.len() <= followed {
+        return None;
+    }
+    let targets: Vec<&str> = edges.iter().map(|e| e.target.as_str()).collect();
+    Some(LintFinding::new(
+        Lint::IgnoredEdges,
+        &node.id,
+        format!("Node {} follows {} of its {} outgoing edges (to {})", node.id, followed, edges.len(), targets.join(", ")),
+    ))
+}
+
+/// Analyses the control flow of an unfused blueprint.
+pub fn lint_blueprint(blueprint: &Blueprint) -> Vec<LintFinding> {
+    let nodes = &blueprint.nodes;
+    let mut findings = Vec::new();
+
+    // Compensation nodes only run when an instance is rolled back
+    let compensations: HashSet<usize> = nodes.iter()
+        .filter_map(|n| n.policy("compensate").and_then(|v| v.as_u64()))
+        .map(|i| i as usize)
+        .collect();
+
+    let successors: Vec<Vec<usize>> = nodes.iter()
+        .map(|n| n.targets().into_iter().filter(|&t| t < nodes.len()).collect())
+        .collect();
+    let mut predecessors = vec![Vec::new(); nodes.len()];
+    for (u, targets) in successors.iter().enumerate() {
+        for &v in targets {
+            predecessors[v].push(u);
+        }
+    }
+
+    let reachable = visit(&[blueprint.start_index], &successors);
+    let ends: Vec<usize> = (0..nodes.len()).filter(|&i| nodes[i].kind == "end").collect();
+    let reaches_end = visit(&ends, &predecessors);
+
+    for (i, node) in nodes.iter().enumerate() {
+        if compensations.contains(&i) {
+            continue;
+        }
+        if !reachable.contains(&i) {
+            findings.push(LintFinding::new(Lint::Unreachable, &node.id, format!("Node {} is unreachable from the start node", node.id)));
+            continue;
+        }
+        if !reaches_end.contains(&i) {
+            findings.push(LintFinding::new(Lint::NoPathToEnd, &node.id, format!("No path leads from node {} to an End node", node.id)));
+        }
+
+        match node.kind.as_str() {
+            "if" => {
+                let has_else = node.params.get("else_next").is_some_and(|v| !v.is_null());
+                let always_true = node.params.get("branches").and_then(|v| v.as_array()).is_some_and(|branches| {
+                    branches.iter().any(|b| b.get("condition").and_then(|c| c.as_str()).is_some_and(|c| c.trim() == "true"))
+                });
+                if !has_else && !always_true {
+                    findings.push(LintFinding::new(
+                        Lint::IfWithoutFallback,
+                        &node.id,
+                        format!("If node {} has no else branch, the flow stops when no condition matches", node.id),
+                    ));
+                }
+            }
+            "loop" | "iteration" if node.params.get("body").is_none_or(|v| v.is_null()) => {
+                findings.push(LintFinding::new(Lint::MissingBody, &node.id, format!("Node {} has no body edge", node.id)));
+            }
+            _ => {}
+        }
+    }
+
+    findings
+}
+
+fn visit(roots: &[usize], edges: &[Vec<usize>]) -> HashSet<usize> {
+    let mut seen: HashSet<usize> = roots.iter().copied().collect();
+    let mut stack = roots.to_vec();
+    while let Some(u) = stack.pop() {
+        for &v in &edges[u] {
+            if seen.insert(v) {
+                stack.push(v);
+            }
+        }
+    }
+    seen
+}
@@ -1,15 +1,24 @@
 use anyhow::{Result, Context as AnyhowContext};
 use std::fs;
 use crate::dsl::Workflow;
+use crate::compiler::diagnostic::{Diagnostic, SourceMap, Span};
 
 pub fn load_workflow_from_yaml(file_path: &str) -> Result<Workflow> {
+    load_workflow_with_source_map(file_path).map(|(workflow, _)| workflow)
+}
+
+/// Loads a workflow along with the positions of its nodes in the file, so compiler
+/// diagnostics can point at the source. Parse errors are returned as `Diagnostic`s.
+pub fn load_workflow_with_source_map(file_path: &str) -> Result<(Workflow, SourceMap)> {
     let yaml_content = fs::read_to_string(file_path)
         .with_context(|| format!("Failed to read YAML file from {}", file_path))?;
+    let source_map = SourceMap::from_yaml(file_path, &yaml_content);
 
     // Try parsing as generic Value first to check for "workflow" wrapper
     let value: serde_yaml::Value = serde_yaml::from_str(&yaml_content)
-        .with_context(|| format!("Failed to parse YAML from {}", file_path))?;
+        .map_err(|e| yaml_diagnostic("yaml-syntax", file_path, "Failed to parse YAML", &e))?;
 
+    let wrapped = value.get("workflow").is_some();
     let mut final_value = if let Some(inner) = value.get("workflow") {
         inner.clone()
     } else {
@@ -19,22 +28,40 @@ pub fn load_workflow_from_yaml(file_path: &str) -> Result<Workflow> {
     // Merge 'nodes' and 'edges' from root if missing in workflow object
     if let Some(map) = final_value.as_mapping_mut() {
         let nodes_key = serde_yaml::Value::String("nodes".to_string());
-        if !map.contains_key(&nodes_key) {
-            if let Some(nodes) = value.get("nodes") {
-                map.insert(nodes_key, nodes.clone());
-            }
+        if !map.contains_key(&nodes_key)
+            && let Some(nodes) = value.get("nodes") {
+            map.insert(nodes_key, nodes.clone());
         }
 
         let edges_key = serde_yaml::Value::String("edges".to_string());
-        if !map.contains_key(&edges_key) {
-            if let Some(edges) = value.get("edges") {
-                map.insert(edges_key, edges.clone());
-            }
+        if !map.contains_key(&edges_key)
+            && let Some(edges) = value.get("edges") {
+            map.insert(edges_key, edges.clone());
         }
     }
 
-    let workflow: Workflow = serde_yaml::from_value(final_value)
-        .with_context(|| format!("Failed to deserialize Workflow structure from {}", file_path))?;
+    let workflow: Workflow = match serde_yaml::from_value(final_value) {
+        Ok(workflow) => workflow,
+        Err(e) => {
+            // Values carry no positions; without the wrapper, parsing the text again locates the error
+            let located = if wrapped { None } else { serde_yaml::from_str::<Workflow>(&yaml_content).err() };
+            let e = located.filter(|e| e.location().is_some()).unwrap_or(e);
+            return Err(yaml_diagnostic("invalid-workflow", file_path, "Failed to deserialize Workflow structure", &e).into());
+        }
+    };
 
-    Ok(workflow)
+    Ok((workflow, source_map))
+}
+
+fn yaml_diagnostic(code: &str, file_path: &str, context: &str, error: &serde_yaml::Error) -> Diagnostic {
+    let diagnostic = Diagnostic::error(code, format!("{} from {}: {}", context, file_path, error));
+    match error.location() {
+        Some(location) => diagnostic.with_span(Span {
+            file: file_path.to_string(),
+            line: location.line(),
+            column: location.column(),
+            length: 1,
+        }),
+        None => diagnostic,
+    }
 }
@@ -1,4 +1,7 @@
 pub mod loader;
 pub mod core;
 pub mod expander;
-pub mod optimizer;
\ No newline at end of file
+pub mod optimizer;
+pub mod lint;
+pub mod diagnostic;
+pub mod graph;
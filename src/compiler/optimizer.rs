@@ -1,4 +1,4 @@
-use crate::runtime::blueprint::{Blueprint, BlueprintNode};
+use crate::runtime::blueprint::{Blueprint, BlueprintNode, POLICY_PARAM};
 use crate::actions::ExecutionMode;
 use std::collections::{HashMap, HashSet};
 use anyhow::Result;
@@ -6,6 +6,12 @@ use serde_json::{json, Value};
 
 pub struct Optimizer;
 
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Optimizer {
     pub fn new() -> Self {
         Self
@@ -21,7 +27,7 @@ impl Optimizer {
         let mut in_degree: Vec<usize> = vec![0; n_count];
 
         for (u, node) in nodes.iter().enumerate() {
-            let targets = extract_targets(node);
+            let targets = node.targets();
             for &v in &targets {
                 if v < n_count {
                     adj[u].push(v);
@@ -95,7 +101,6 @@ impl Optimizer {
 
             if let Some(chain) = chains.get(&i) {
                 // Create Fused Node
-                let head_node = &nodes[chain[0]];
                 let tail_node_idx = *chain.last().unwrap();
                 let tail_node = &nodes[tail_node_idx];
                 
@@ -112,7 +117,7 @@ impl Optimizer {
                 // The "next" of the fused node is the "next" of the tail node
                 // But we need to preserve the structure expected by FusedNode runtime
                 // The runtime FusedNode expects "ops" and "next".
-                // extract_targets(tail_node) gives us the next index (if any).
+                // tail_node.targets() gives us the next index (if any).
                 // But wait, BlueprintNode stores "next" in params usually.
                 // We should copy the control flow params from the tail node to the fused node.
                 
@@ -126,6 +131,7 @@ impl Optimizer {
                 
                 let new_idx = new_nodes.len();
                 new_nodes.push(BlueprintNode {
+                    id: nodes[i].id.clone(),
                     kind: "fused".to_string(),
                     params: fused_params,
                 });
@@ -153,66 +159,25 @@ impl Optimizer {
         let new_start_index = *old_to_new.get(&blueprint.start_index).unwrap_or(&blueprint.start_index); // Fallback should not happen if valid
 
         Ok(Blueprint {
-            id: blueprint.id,
-            name: blueprint.name,
             nodes: new_nodes,
             start_index: new_start_index,
+            ..blueprint
         })
     }
 }
 
+/// The engine enforces policies per node. A fused node would lose them, so such nodes are never fused.
 fn is_sync(node: &BlueprintNode, lookup: &impl Fn(&str) -> Option<ExecutionMode>) -> bool {
     lookup(&node.kind) == Some(ExecutionMode::Sync)
-}
-
-// Helper to extract all outgoing node indices from a node's params
-fn extract_targets(node: &BlueprintNode) -> Vec<usize> {
-    let mut targets = Vec::new();
-    
-    // Common patterns
-    if let Some(next) = node.params.get("next").and_then(|v| v.as_u64()) {
-        targets.push(next as usize);
-    }
-    
-    if let Some(ts) = node.params.get("targets").and_then(|v| v.as_array()) {
-        for t in ts {
-            if let Some(idx) = t.as_u64() {
-                targets.push(idx as usize);
-            }
-        }
-    }
-    
-    if let Some(join) = node.params.get("join_target").and_then(|v| v.as_u64()) {
-        targets.push(join as usize);
-    }
-
-    if let Some(branches) = node.params.get("branches").and_then(|v| v.as_array()) {
-        for b in branches {
-             if let Some(t) = b.get("target").and_then(|v| v.as_u64()) {
-                 targets.push(t as usize);
-             }
-        }
-    }
-    
-    if let Some(else_next) = node.params.get("else_next").and_then(|v| v.as_u64()) {
-        targets.push(else_next as usize);
-    }
-
-    // Loop/Iteration
-    if let Some(body) = node.params.get("body").and_then(|v| v.as_u64()) {
-        targets.push(body as usize);
-    }
-    
-    targets
+        && node.params.get(POLICY_PARAM).is_none()
 }
 
 fn remap_node_targets(node: &mut BlueprintNode, map: &HashMap<usize, usize>) {
     // Helper to remap a Value containing an index
     let remap_val = |v: &mut Value| {
-        if let Some(idx) = v.as_u64() {
-            if let Some(&new_idx) = map.get(&(idx as usize)) {
-                *v = json!(new_idx);
-            }
+        if let Some(idx) = v.as_u64()
+            && let Some(&new_idx) = map.get(&(idx as usize)) {
+            *v = json!(new_idx);
         }
     };
 
@@ -246,4 +211,15 @@ fn remap_node_targets(node: &mut BlueprintNode, map: &HashMap<usize, usize>) {
     if let Some(body) = node.params.get_mut("body") {
         remap_val(body);
     }
+
+    if let Some(policy) = node.params.get_mut(POLICY_PARAM) {
+        if let Some(on_error) = policy.get_mut("on_error") {
+            remap_val(on_error);
+        }
+
+        // Not a control-flow edge, but still an index into the node list
+        if let Some(compensate) = policy.get_mut("compensate") {
+            remap_val(compensate);
+        }
+    }
 }
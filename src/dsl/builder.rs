@@ -1,4 +1,6 @@
-use crate::dsl::{Workflow, Node, Edge, NodeType, Branch};
+use crate::dsl::{Workflow, Node, Edge, NodeType, Branch, Compensation};
+use crate::runtime::retry::RetryPolicy;
+use crate::runtime::input::InputSpec;
 use std::collections::HashMap;
 use serde_json::Value;
 
@@ -6,8 +8,11 @@ pub struct WorkflowBuilder {
     id: String,
     name: String,
     variables: HashMap<String, Value>,
+    inputs: HashMap<String, InputSpec>,
     pub nodes: Vec<Node>, // Made public for manual manipulation in tests if needed
     edges: Vec<Edge>,
+    default_timeout: Option<u64>,
+    deadline: Option<u64>,
 }
 
 impl WorkflowBuilder {
@@ -16,8 +21,11 @@ impl WorkflowBuilder {
             id: id.to_string(),
             name: id.to_string(),
             variables: HashMap::new(),
+            inputs: HashMap::new(),
             nodes: Vec::new(),
             edges: Vec::new(),
+            default_timeout: None,
+            deadline: None,
         }
     }
 
@@ -26,24 +34,35 @@ impl WorkflowBuilder {
         self
     }
 
+    /// Default node timeout in milliseconds.
+    pub fn default_timeout(mut self, millis: u64) -> Self {
+        self.default_timeout = Some(millis);
+        self
+    }
+
+    /// Overall instance deadline in milliseconds, counted from start.
+    pub fn deadline(mut self, millis: u64) -> Self {
+        self.deadline = Some(millis);
+        self
+    }
+
     pub fn var(mut self, key: &str, value: impl Into<Value>) -> Self {
         self.variables.insert(key.to_string(), value.into());
         self
     }
 
+    pub fn input(mut self, name: &str, spec: InputSpec) -> Self {
+        self.inputs.insert(name.to_string(), spec);
+        self
+    }
+
     pub fn start(mut self, id: &str) -> Self {
-        self.nodes.push(Node {
-            id: id.to_string(),
-            kind: NodeType::Start,
-        });
+        self.nodes.push(Node::new(id, NodeType::Start));
         self
     }
 
     pub fn end(mut self, id: &str, output_var: &str) -> Self {
-        self.nodes.push(Node {
-            id: id.to_string(),
-            kind: NodeType::End { output: output_var.to_string() },
-        });
+        self.nodes.push(Node::new(id, NodeType::End { output: output_var.to_string() }));
         self
     }
 
@@ -54,29 +73,26 @@ impl WorkflowBuilder {
             function_name: function_name.to_string(),
             params: HashMap::new(),
             output: None,
+            retry: None,
+            timeout: None,
+            compensate: None,
         }
     }
 
     pub fn if_node(mut self, id: &str) -> Self {
-        self.nodes.push(Node {
-            id: id.to_string(),
-            kind: NodeType::If { branches: Vec::new() },
-        });
+        self.nodes.push(Node::new(id, NodeType::If { branches: Vec::new() }));
         self
     }
 
     /// 添加并行块
     pub fn parallel(mut self, id: &str, branches: Vec<Vec<Node>>) -> Self {
         let branches_structs = branches.into_iter()
-            .map(|nodes| Branch { nodes })
+            .map(|nodes| Branch { nodes, edges: Vec::new() })
             .collect();
             
-        self.nodes.push(Node {
-            id: id.to_string(),
-            kind: NodeType::Parallel {
-                branches: branches_structs,
-            },
-        });
+        self.nodes.push(Node::new(id, NodeType::Parallel {
+            branches: branches_structs,
+        }));
         self
     }
 
@@ -113,13 +129,28 @@ impl WorkflowBuilder {
         self
     }
 
+    /// Routes failures of `source` (after retries) to `target`.
+    pub fn connect_error(mut self, source: &str, target: &str) -> Self {
+        self.edges.push(Edge {
+            source: source.to_string(),
+            target: target.to_string(),
+            condition: None,
+            branch_type: Some("error".to_string()),
+            branch_index: None,
+        });
+        self
+    }
+
     pub fn build(self) -> Workflow {
         Workflow {
             id: self.id,
             name: self.name,
             variables: self.variables,
+            inputs: self.inputs,
             nodes: self.nodes,
             edges: self.edges,
+            default_timeout: self.default_timeout,
+            deadline: self.deadline,
         }
     }
 }
@@ -130,6 +161,9 @@ pub struct FunctionBuilder {
     function_name: String,
     params: HashMap<String, Value>,
     output: Option<String>,
+    retry: Option<RetryPolicy>,
+    timeout: Option<u64>,
+    compensate: Option<Compensation>,
 }
 
 impl FunctionBuilder {
@@ -143,14 +177,37 @@ impl FunctionBuilder {
         self
     }
 
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Node timeout in milliseconds.
+    pub fn timeout(mut self, millis: u64) -> Self {
+        self.timeout = Some(millis);
+        self
+    }
+
+    /// Function to call when a later failure rolls this node back.
+    pub fn compensate(mut self, function_name: &str, params: HashMap<String, Value>) -> Self {
+        self.compensate = Some(Compensation {
+            name: function_name.to_string(),
+            params,
+        });
+        self
+    }
+
     pub fn build(mut self) -> WorkflowBuilder {
+        let kind = NodeType::Function {
+            name: self.function_name,
+            params: self.params,
+            output: self.output,
+        };
         self.workflow_builder.nodes.push(Node {
-            id: self.id,
-            kind: NodeType::Function {
-                name: self.function_name,
-                params: self.params,
-                output: self.output,
-            },
+            retry: self.retry,
+            timeout: self.timeout,
+            compensate: self.compensate,
+            ..Node::new(self.id, kind)
         });
         self.workflow_builder
     }
@@ -3,19 +3,31 @@ pub mod builder;
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 use serde_json::Value;
+use crate::runtime::retry::RetryPolicy;
+use crate::runtime::input::InputSpec;
 
 /// 原始 DSL 定义的 Workflow
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct Workflow {
     pub id: String,
     #[serde(default)]
     pub name: String,
+    /// 变量默认值，启动实例时被调用方传入的同名变量覆盖
     #[serde(default)]
     pub variables: HashMap<String, Value>,
+    /// 声明的输入参数 (类型、是否必填、默认值)，启动实例时校验
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub inputs: HashMap<String, InputSpec>,
     #[serde(default)]
     pub nodes: Vec<Node>,
     #[serde(default)]
     pub edges: Vec<Edge>,
+    /// 节点默认超时时间 (毫秒)，节点未声明 timeout 时使用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_timeout: Option<u64>,
+    /// 实例整体截止时间 (毫秒，从启动开始计算)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<u64>,
 }
 
 /// DSL 中的节点类型
@@ -67,6 +79,9 @@ pub enum NodeType {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Branch {
     pub nodes: Vec<Node>,
+    /// 分支内部的边；为空且节点也没有声明跳转时，节点按声明顺序线性连接
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub edges: Vec<Edge>,
 }
 
 /// DSL 中的节点
@@ -75,6 +90,52 @@ pub struct Node {
     pub id: String,
     #[serde(flatten)]
     pub kind: NodeType,
+    /// 节点失败时的重试策略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// 节点执行超时时间 (毫秒)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// 补偿动作 (仅 Function 节点)：实例失败时按完成顺序的逆序执行
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compensate: Option<Compensation>,
+    /// 语法糖：后继节点，由 Expander 展开为一条无条件的 Edge
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    /// 语法糖 (仅 If)：所有条件都不满足时的后继节点，展开为 `branch_type: else` 的 Edge
+    #[serde(default, rename = "else", skip_serializing_if = "Option::is_none")]
+    pub else_next: Option<String>,
+    /// 语法糖 (仅 Iteration / Loop)：循环体入口，展开为 `branch_type: body` 的 Edge
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// 语法糖：失败后的错误处理节点，展开为 `branch_type: error` 的 Edge
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<String>,
+}
+
+impl Node {
+    /// 创建节点，策略与语法糖字段留空
+    pub fn new(id: impl Into<String>, kind: NodeType) -> Self {
+        Self {
+            id: id.into(),
+            kind,
+            retry: None,
+            timeout: None,
+            compensate: None,
+            next: None,
+            else_next: None,
+            body: None,
+            on_error: None,
+        }
+    }
+}
+
+/// 补偿动作，调用一个已注册的 Function
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Compensation {
+    pub name: String,
+    #[serde(default)]
+    pub params: HashMap<String, Value>,
 }
 
 /// DSL 中的边
@@ -83,6 +144,6 @@ pub struct Edge {
     pub source: String,
     pub target: String,
     pub condition: Option<String>,
-    pub branch_type: Option<String>, // "else", "body" 等
+    pub branch_type: Option<String>, // "else", "body", "error" 等
     pub branch_index: Option<usize>,
 }
\ No newline at end of file
@@ -2,6 +2,7 @@ use crate::runtime::node::{Node, NodeDefinition};
 use crate::runtime::context::Context;
 use crate::runtime::syscall::Syscall;
 use crate::runtime::task::Task;
+use crate::runtime::instance::WORKFLOW_OUTPUT_VAR;
 use async_trait::async_trait;
 use serde_json::Value;
 use anyhow::Result;
@@ -61,7 +62,7 @@ impl Node for EndNode {
 
                 info!("Workflow Output: {:?}", val);
 
-                ctx.set_var("_WORKFLOW_OUTPUT", val).await;
+                ctx.set_var(WORKFLOW_OUTPUT_VAR, val).await;
 
             } else {
 
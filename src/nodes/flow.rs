@@ -7,6 +7,14 @@ use serde_json::{Value, json};
 use anyhow::{Result, anyhow};
 use evalexpr::{build_operator_tree, Node as EvalNode, ContextWithMutableVariables, HashMapContext, DefaultNumericTypes};
 
+/// Parses a `${var}` condition the way the If and Loop nodes do when prepared.
+fn check_condition(cond_str: &str) -> Result<()> {
+    let clean_cond = cond_str.replace("${", "").replace("}", "");
+    build_operator_tree::<DefaultNumericTypes>(&clean_cond)
+        .map_err(|e| anyhow!("Invalid condition `{}`: {}", cond_str, e))?;
+    Ok(())
+}
+
 // --- ITERATION NODE ---
 
 #[derive(Debug)]
@@ -21,7 +29,15 @@ pub struct IterationDefinition;
 
 impl NodeDefinition for IterationDefinition {
     fn name(&self) -> &str { "iteration" }
-    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    fn validate(&self, params: &Value) -> Result<()> {
+        if params.get("collection").and_then(|v| v.as_str()).is_none() {
+            return Err(anyhow!("Missing collection"));
+        }
+        if params.get("item_var").and_then(|v| v.as_str()).is_none() {
+            return Err(anyhow!("Missing item_var"));
+        }
+        Ok(())
+    }
     fn prepare(&self, params: Value) -> Result<Box<dyn Node>> {
         let collection_var = params.get("collection").and_then(|v| v.as_str())
              .map(|s| s.replace("${", "").replace("}", ""))
@@ -130,7 +146,10 @@ impl NodeDefinition for LoopDefinition {
 
     fn name(&self) -> &str { "loop" }
 
-    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    fn validate(&self, params: &Value) -> Result<()> {
+        let cond_str = params.get("condition").and_then(|v| v.as_str()).ok_or(anyhow!("Missing condition"))?;
+        check_condition(cond_str)
+    }
 
     fn prepare(&self, params: Value) -> Result<Box<dyn Node>> {
 
@@ -194,9 +213,7 @@ impl Node for LoopNode {
 
                     if let Some(i) = n.as_i64() { Some(evalexpr::Value::Int(i)) }
 
-                    else if let Some(f) = n.as_f64() { Some(evalexpr::Value::Float(f)) }
-
-                    else { None }
+                    else { n.as_f64().map(evalexpr::Value::Float) }
 
                 },
 
@@ -292,7 +309,13 @@ impl NodeDefinition for IfDefinition {
 
     fn name(&self) -> &str { "if" }
 
-    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    fn validate(&self, params: &Value) -> Result<()> {
+        for b in params.get("branches").and_then(|v| v.as_array()).into_iter().flatten() {
+            let cond_str = b.get("condition").and_then(|v| v.as_str()).ok_or(anyhow!("Missing condition"))?;
+            check_condition(cond_str)?;
+        }
+        Ok(())
+    }
 
     
 
@@ -366,9 +389,7 @@ impl Node for IfNode {
 
                     if let Some(i) = n.as_i64() { Some(evalexpr::Value::Int(i)) }
 
-                    else if let Some(f) = n.as_f64() { Some(evalexpr::Value::Float(f)) }
-
-                    else { None }
+                    else { n.as_f64().map(evalexpr::Value::Float) }
 
                 },
 
@@ -418,13 +439,9 @@ impl Node for IfNode {
 
         
 
-        if !matched {
-
-            if let Some(idx) = self.else_next {
+        if !matched && let Some(idx) = self.else_next {
 
-                syscall.jump(idx);
-
-            }
+            syscall.jump(idx);
 
         }
 
@@ -546,31 +563,15 @@ impl NodeDefinition for JoinDefinition {
 
 impl Node for JoinNode {
 
-    async fn execute(&self, ctx: &Context, task: &Task, syscall: &mut dyn Syscall) -> Result<()> {
-
-        let should_proceed = {
-
-            let new_val = ctx.decrement_join_count(task.node_index, self.expect_count).await?;
-
-            new_val == 0
-
-        };
-
-
-
-        if should_proceed {
-
-            // pending_joins removal is handled by decrement_join_count in store
-
-            if let Some(target) = self.next {
+    async fn execute(&self, _ctx: &Context, _task: &Task, syscall: &mut dyn Syscall) -> Result<()> {
 
-                syscall.jump(target);
+        // The arrival is recorded with the task's commit, which queues the jump only once all branches arrived
 
-            }
+        syscall.arrive(self.expect_count);
 
-        } else {
+        if let Some(target) = self.next {
 
-            syscall.wait();
+            syscall.jump(target);
 
         }
 
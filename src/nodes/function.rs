@@ -2,11 +2,13 @@ use crate::runtime::node::{Node, NodeDefinition};
 use crate::runtime::context::Context;
 use crate::runtime::syscall::Syscall;
 use crate::runtime::task::Task;
+use crate::runtime::blueprint::POLICY_PARAM;
 use crate::actions::FunctionHandler;
 use async_trait::async_trait;
 use serde_json::Value;
 use anyhow::Result;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// 将 FunctionHandler 包装为 Node
 #[derive(Debug)]
@@ -20,16 +22,18 @@ pub struct FunctionNode {
 #[async_trait]
 impl Node for FunctionNode {
     async fn execute(&self, ctx: &Context, _task: &Task, syscall: &mut dyn Syscall) -> Result<()> {
-        // 1. Resolve Variables in Params
+        // 1. Resolve Variables in Params (fetched in one batch)
         let mut resolved_params = self.params.clone();
         if let Some(obj) = resolved_params.as_object_mut() {
-            for (_, v) in obj.iter_mut() {
-                if let Some(s) = v.as_str() {
-                    if s.starts_with("${") && s.ends_with("}") {
-                        let var_name = &s[2..s.len()-1];
-                        if let Some(val) = ctx.get_var(var_name).await {
-                            *v = val;
-                        }
+            let var_names: Vec<String> = obj.values()
+                .filter_map(|v| v.as_str().and_then(var_reference))
+                .map(str::to_string)
+                .collect();
+            if !var_names.is_empty() {
+                let vars = ctx.get_vars(&var_names).await?;
+                for (_, v) in obj.iter_mut() {
+                    if let Some(val) = v.as_str().and_then(var_reference).and_then(|name| vars.get(name)) {
+                        *v = val.clone();
                     }
                 }
             }
@@ -50,6 +54,18 @@ impl Node for FunctionNode {
 
         Ok(())
     }
+
+    fn default_timeout(&self) -> Option<Duration> {
+        self.handler.default_timeout()
+    }
+}
+
+/// 去掉编译器写入的执行策略，处理器只看到节点自己的参数
+fn handler_params(mut params: Value) -> Value {
+    if let Some(obj) = params.as_object_mut() {
+        obj.remove(POLICY_PARAM);
+    }
+    params
 }
 
 /// 对应的 Definition
@@ -63,10 +79,11 @@ impl NodeDefinition for FunctionNodeDefinition {
     }
 
     fn validate(&self, params: &Value) -> Result<()> {
-        self.handler.validate(params)
+        self.handler.validate(&handler_params(params.clone()))
     }
 
     fn prepare(&self, params: Value) -> Result<Box<dyn Node>> {
+        let params = handler_params(params);
         // Extract System Params
         let next = params.get("next").and_then(|v| v.as_u64()).map(|i| i as usize);
         let output = params.get("output").and_then(|v| v.as_str()).map(|s| s.to_string());
@@ -83,3 +100,8 @@ impl NodeDefinition for FunctionNodeDefinition {
         }))
     }
 }
+
+/// The variable name of a whole-value reference like `"${name}"`.
+fn var_reference(s: &str) -> Option<&str> {
+    s.strip_prefix("${")?.strip_suffix('}')
+}
@@ -1,8 +1,15 @@
 use serde::{Serialize, Deserialize};
 use serde_json::Value;
+use crate::runtime::input::InputSpec;
+use std::collections::BTreeMap;
+use sha2::{Digest, Sha256};
 
 pub type NodeIndex = usize;
 
+/// 编译器存放节点执行策略 (retry / timeout / on_error / compensate) 的保留参数，
+/// 与用户的 Function 参数分开，处理器不会收到它
+pub const POLICY_PARAM: &str = "_policy";
+
 /// 编译后的蓝图 (中间表示，可序列化)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Blueprint {
@@ -10,14 +17,70 @@ pub struct Blueprint {
     pub name: String,
     pub nodes: Vec<BlueprintNode>,
     pub start_index: NodeIndex,
+    /// 变量默认值 (有序，保证内容哈希稳定)
+    #[serde(default)]
+    pub variables: BTreeMap<String, Value>,
+    /// 声明的输入参数
+    #[serde(default)]
+    pub inputs: BTreeMap<String, InputSpec>,
+    /// 节点默认超时时间 (毫秒)
+    #[serde(default)]
+    pub default_timeout: Option<u64>,
+    /// 实例整体截止时间 (毫秒，从启动开始计算)
+    #[serde(default)]
+    pub deadline: Option<u64>,
+    /// 内容哈希，实例启动时固定在该版本上，重新注册同 ID 的蓝图不影响运行中的实例
+    #[serde(default)]
+    pub version: String,
+}
+
+impl Blueprint {
+    /// SHA-256 of everything but the version itself, as hex.
+    ///
+    /// The version is persisted and compared across processes, so it must not depend on the
+    /// toolchain: the JSON is canonical because struct fields serialize in declaration order
+    /// and maps (`BTreeMap`, `serde_json::Map`) in key order.
+    pub fn content_hash(&self) -> String {
+        let unversioned = Blueprint { version: String::new(), ..self.clone() };
+        let serialized = serde_json::to_vec(&unversioned).expect("Blueprint is always serializable");
+        Sha256::digest(&serialized).iter().map(|b| format!("{:02x}", b)).collect()
+    }
 }
 
 /// 蓝图节点配置
 /// 这是一个通用的数据容器，用于在该节点被加载时传递给 NodeDefinition::prepare
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlueprintNode {
+    /// DSL 中的节点 ID (融合节点取链首节点的 ID)
+    #[serde(default)]
+    pub id: String,
     /// 节点类型名称 (e.g. "log", "if", "fork")
     pub kind: String, 
     /// 配置参数 (包含编译器计算出的跳转目标索引，如 "next": 1)
     pub params: Value,
 }
+
+impl BlueprintNode {
+    /// 读取一项执行策略 (见 [`POLICY_PARAM`])
+    pub fn policy(&self, name: &str) -> Option<&Value> {
+        self.params.get(POLICY_PARAM).and_then(|policy| policy.get(name))
+    }
+
+    /// 从参数中提取所有后继节点索引 (next / targets / join_target / branches / else_next / body / on_error)
+    pub fn targets(&self) -> Vec<NodeIndex> {
+        let index = |name: &str| self.params.get(name).and_then(Value::as_u64).map(|i| i as usize);
+        let listed = |name: &str| self.params.get(name).and_then(Value::as_array).into_iter().flatten();
+
+        let mut targets = Vec::new();
+        targets.extend(index("next"));
+        targets.extend(listed("targets").filter_map(Value::as_u64).map(|i| i as usize));
+        targets.extend(index("join_target"));
+        targets.extend(listed("branches").filter_map(|b| b.get("target").and_then(Value::as_u64)).map(|i| i as usize));
+        targets.extend(index("else_next"));
+        // Loop/Iteration
+        targets.extend(index("body"));
+        // Error handler
+        targets.extend(self.policy("on_error").and_then(Value::as_u64).map(|i| i as usize));
+        targets
+    }
+}
@@ -1,8 +1,13 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use serde_json::Value;
 use uuid::Uuid;
 use crate::runtime::storage::StateStore;
 use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+
+/// Buffered variable writes, in write order with one entry per variable.
+type WriteBuffer = Vec<(String, Value)>;
 
 /// 运行时上下文 (Runtime Context)
 /// 包含工作流实例的所有动态状态，现在委托给 StateStore
@@ -11,6 +16,10 @@ pub struct Context {
     pub instance_id: Uuid,
     pub workflow_id: String,
     pub store: Arc<dyn StateStore>,
+    /// 实例被取消时触发，长时间运行的 Handler 应配合 `cancelled()` 及时退出
+    pub cancellation: CancellationToken,
+    /// 写缓冲：启用后 `set_var` 不直接写入 StateStore，由 Engine 在任务成功后统一提交
+    writes: Option<Arc<Mutex<WriteBuffer>>>,
 }
 
 impl Context {
@@ -19,10 +28,48 @@ impl Context {
             instance_id,
             workflow_id,
             store,
+            cancellation: CancellationToken::new(),
+            writes: None,
+        }
+    }
+
+    /// Buffers variable writes until `take_writes`. Reads still see the buffered values.
+    pub fn buffered(mut self) -> Self {
+        self.writes = Some(Arc::new(Mutex::new(Vec::new())));
+        self
+    }
+
+    /// Drains the buffered writes, in write order with one entry per variable.
+    pub fn take_writes(&self) -> Vec<(String, Value)> {
+        match &self.writes {
+            Some(writes) => std::mem::take(&mut *writes.lock().unwrap()),
+            None => Vec::new(),
         }
     }
 
+    fn buffered_var(&self, key: &str) -> Option<Value> {
+        let writes = self.writes.as_ref()?.lock().unwrap();
+        writes.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+    }
+
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Resolves once the instance is cancelled.
+    pub async fn cancelled(&self) {
+        self.cancellation.cancelled().await
+    }
+
     pub async fn get_var(&self, key: &str) -> Option<Value> {
+        if let Some(value) = self.buffered_var(key) {
+            return Some(value);
+        }
         match self.store.get_var(self.instance_id, key).await {
             Ok(v) => v,
             Err(e) => {
@@ -34,16 +81,33 @@ impl Context {
     }
 
     pub async fn set_var(&self, key: &str, value: Value) {
+        if let Some(writes) = &self.writes {
+            let mut writes = writes.lock().unwrap();
+            writes.retain(|(k, _)| k != key);
+            writes.push((key.to_string(), value));
+            return;
+        }
         if let Err(e) = self.store.set_var(self.instance_id, key, value).await {
              eprintln!("Error setting var {}: {}", key, e);
         }
     }
     
-    pub async fn get_all_vars(&self) -> Result<std::collections::HashMap<String, Value>> {
-        self.store.get_all_vars(self.instance_id).await
+    /// Fetches several variables at once; missing ones are left out.
+    pub async fn get_vars(&self, keys: &[String]) -> Result<HashMap<String, Value>> {
+        let mut vars = self.store.get_vars(self.instance_id, keys).await?;
+        for key in keys {
+            if let Some(value) = self.buffered_var(key) {
+                vars.insert(key.clone(), value);
+            }
+        }
+        Ok(vars)
     }
-    
-    pub async fn decrement_join_count(&self, node_index: usize, initial_count: usize) -> Result<usize> {
-        self.store.decrement_join_count(self.instance_id, node_index, initial_count).await
+
+    pub async fn get_all_vars(&self) -> Result<HashMap<String, Value>> {
+        let mut vars = self.store.get_all_vars(self.instance_id).await?;
+        if let Some(writes) = &self.writes {
+            vars.extend(writes.lock().unwrap().iter().cloned());
+        }
+        Ok(vars)
     }
 }
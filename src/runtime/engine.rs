@@ -2,38 +2,209 @@ use std::sync::Arc;
 use dashmap::DashMap;
 use uuid::Uuid;
 use anyhow::{Result, anyhow};
-use crate::runtime::blueprint::{Blueprint, NodeIndex};
+use crate::runtime::blueprint::{Blueprint, BlueprintNode, NodeIndex};
 use crate::runtime::context::Context;
 use crate::runtime::task::Task;
 use crate::runtime::node::{Node, NodeDefinition};
+use crate::runtime::registry::NodeRegistry;
 use crate::runtime::syscall::Syscall;
-use crate::runtime::storage::{StateStore, TaskQueue, InMemoryStateStore, InMemoryTaskQueue};
+use crate::runtime::storage::{versioned_blueprint_key, BlueprintStore, DeadLetter, Delivery, JoinArrival, SequentialCommitter, StateStore, TaskCommit, TaskCommitter, TaskQueue, InMemoryStateStore, InMemoryTaskQueue};
+use crate::runtime::instance::{InstanceStatus, InstanceFailure, WORKFLOW_OUTPUT_VAR, ERROR_VAR, unix_millis};
+use crate::runtime::error::{ErrorKind, NodeError};
+use crate::runtime::retry::RetryPolicy;
+use crate::runtime::input::resolve_initial_vars;
+use crate::runtime::worker::{WorkerPool, WorkerStats, TaskOutcome};
 use crate::actions::FunctionHandler;
-use crate::nodes::function::FunctionNodeDefinition;
-use std::collections::HashMap;
-use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use serde_json::{json, Value};
 
 pub struct Engine {
-    // Raw Blueprints (Config)
+    // Raw Blueprints (Config) by `id@version`. Instances started on an older version still
+    // need it after the blueprint was re-registered; it is evicted once they all finished.
     blueprints: DashMap<String, Arc<Blueprint>>,
-    // Instantiated Nodes (JIT Cache)
-    executable_cache: DashMap<String, Arc<Vec<Box<dyn Node>>>>,
+    // Version new instances of each blueprint start on
+    latest_versions: DashMap<String, String>,
+    // Shared blueprints, fetched on demand when not registered locally
+    blueprint_store: Option<Arc<dyn BlueprintStore>>,
+    // Instantiated Nodes (JIT Cache) by `id@version`
+    executable_cache: DashMap<String, Arc<Executable>>,
     
     // Storage Abstractions
     store: Arc<dyn StateStore>,
     task_queue: Arc<dyn TaskQueue>,
+    // Writes a finished task's variables and follow-up tasks
+    committer: Arc<dyn TaskCommitter>,
     
-    // Registry for Node Factories
-    node_registry: HashMap<String, Box<dyn NodeDefinition>>,
+    // Registry for Node Factories, shareable with a validating Compiler
+    node_registry: Arc<NodeRegistry>,
+
+    // Wakes local `await_completion` callers when this engine finishes an instance
+    status_changed: Notify,
+
+    // Cancellation tokens of instances with tasks currently running on this engine
+    in_flight: DashMap<Uuid, InFlight>,
+
+    // Graceful shutdown: stop popping, drain in-flight tasks within the grace period
+    shutdown: CancellationToken,
+    shutdown_grace: Duration,
+}
+
+/// What the workers started together share: a pool, or a single `run_worker` call.
+#[derive(Clone)]
+struct WorkerScope {
+    // Child of the engine's token, so an engine-wide shutdown stops every scope
+    shutdown: CancellationToken,
+    // Delayed retries that must be flushed to the queue before the workers exit
+    retries: TaskTracker,
 }
 
+impl WorkerScope {
+    fn new(engine_shutdown: &CancellationToken) -> Self {
+        Self { shutdown: engine_shutdown.child_token(), retries: TaskTracker::new() }
+    }
+}
+
+use tokio::sync::Notify;
+use tokio::task::AbortHandle;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use dashmap::mapref::entry::Entry;
 use tokio::time::timeout;
 use std::time::Duration;
-use tracing::{info, error, warn};
+use tracing::{debug, info, error, warn};
+
+/// An instantiated blueprint: runtime nodes plus the per-node policies the engine enforces.
+struct Executable {
+    nodes: Vec<Box<dyn Node>>,
+    node_ids: Vec<String>,
+    policies: Vec<NodePolicy>,
+    // Nodes between a Fork and its Join, which run alongside sibling branches
+    in_fork: Vec<bool>,
+    default_timeout: Option<Duration>,
+}
+
+/// Execution policies the compiler carries in the reserved `POLICY_PARAM`.
+struct NodePolicy {
+    retry: Option<RetryPolicy>,
+    timeout: Option<Duration>,
+    on_error: Option<NodeIndex>,
+    compensate: Option<NodeIndex>,
+}
+
+impl NodePolicy {
+    fn from_node(node: &BlueprintNode) -> Result<Self> {
+        let retry = node.policy("retry")
+            .map(|v| serde_json::from_value(v.clone()))
+            .transpose()
+            .map_err(|e| anyhow!("Invalid retry policy: {}", e))?;
+        let timeout = node.policy("timeout")
+            .map(|v| v.as_u64().ok_or_else(|| anyhow!("Invalid timeout: expected milliseconds, got {}", v)))
+            .transpose()?
+            .map(Duration::from_millis);
+        let on_error = node.policy("on_error")
+            .and_then(|v| v.as_u64())
+            .map(|i| i as NodeIndex);
+        let compensate = node.policy("compensate")
+            .and_then(|v| v.as_u64())
+            .map(|i| i as NodeIndex);
+        Ok(Self { retry, timeout, on_error, compensate })
+    }
+}
+
+/// Node timeout used when neither the node, its handler nor the workflow declares one.
+const DEFAULT_NODE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long in-flight tasks may keep running after a shutdown request, by default.
+const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(30);
+
+/// How long a pop already in progress may still complete after a shutdown request,
+/// so a task the queue handed out is re-queued instead of lost. Matches the Redis BRPOP timeout.
+const POP_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Requests a graceful shutdown of the workers of an engine, or of one worker pool.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    token: CancellationToken,
+}
+
+impl ShutdownHandle {
+    /// Workers stop popping new tasks and return once their in-flight task is done,
+    /// or re-queue it when the grace period runs out.
+    pub fn shutdown(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
+
+/// How often an instance with running tasks checks the store for a cancellation issued by
+/// another process.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+struct InFlight {
+    token: CancellationToken,
+    tasks: usize,
+    // Fired by the instance's watcher once the store reports it Cancelled
+    cancelled: CancellationToken,
+    watcher: AbortHandle,
+}
+
+/// Keeps an instance's token registered in `in_flight` while one of its tasks runs.
+struct InFlightGuard<'a> {
+    in_flight: &'a DashMap<Uuid, InFlight>,
+    instance_id: Uuid,
+    token: CancellationToken,
+    cancelled: CancellationToken,
+}
+
+impl InFlightGuard<'_> {
+    /// Resolves when the instance is cancelled, either locally through its token
+    /// or by another process, which the instance's watcher observes in the store.
+    async fn wait_for_cancellation(&self) {
+        tokio::select! {
+            _ = self.token.cancelled() => {}
+            _ = self.cancelled.cancelled() => {
+                self.token.cancel();
+                // Gives a cooperative handler one poll to observe the token, as on a local cancel
+                tokio::task::yield_now().await;
+            }
+        }
+    }
+}
+
+/// Polls the store until the instance is Cancelled, shared by all of its tasks running on
+/// this engine. Aborted once the last of them finished.
+async fn watch_for_cancellation(store: Arc<dyn StateStore>, instance_id: Uuid, cancelled: CancellationToken) {
+    loop {
+        tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+        if let Ok(Some(InstanceStatus::Cancelled)) = store.get_status(instance_id).await {
+            cancelled.cancel();
+            return;
+        }
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        if let Entry::Occupied(mut entry) = self.in_flight.entry(self.instance_id) {
+            entry.get_mut().tasks -= 1;
+            if entry.get().tasks == 0 {
+                entry.remove().watcher.abort();
+            }
+        }
+    }
+}
+
+/// How often `await_completion` re-reads the store, to observe instances finished by remote workers.
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 struct EngineSyscall {
     task: Task,
     pending_tasks: Vec<Task>,
+    join: Option<JoinArrival>,
+    terminated: bool,
 }
 
 impl Syscall for EngineSyscall {
@@ -41,9 +212,13 @@ impl Syscall for EngineSyscall {
         let new_task = Task {
             instance_id: self.task.instance_id,
             workflow_id: self.task.workflow_id.clone(),
+            blueprint_version: self.task.blueprint_version.clone(),
             token_id: self.task.token_id,
             node_index: target,
             flow_id: self.task.flow_id,
+            attempt: 0,
+            deadline: self.task.deadline,
+            compensation: false,
         };
         self.pending_tasks.push(new_task);
     }
@@ -53,9 +228,13 @@ impl Syscall for EngineSyscall {
             let new_task = Task {
                 instance_id: self.task.instance_id,
                 workflow_id: self.task.workflow_id.clone(),
+                blueprint_version: self.task.blueprint_version.clone(),
                 token_id: Uuid::new_v4(),
                 node_index: target,
                 flow_id: self.task.flow_id,
+                attempt: 0,
+                deadline: self.task.deadline,
+                compensation: false,
             };
             self.pending_tasks.push(new_task);
         }
@@ -65,8 +244,22 @@ impl Syscall for EngineSyscall {
         // Do nothing
     }
 
+    fn arrive(&mut self, expect_count: usize) {
+        self.join = Some(JoinArrival {
+            node_index: self.task.node_index,
+            token_id: self.task.token_id,
+            expect_count,
+        });
+    }
+
     fn terminate(&mut self) {
-        // Do nothing
+        self.terminated = true;
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -79,12 +272,20 @@ impl Engine {
     }
 
     pub fn new_with_storage(store: Arc<dyn StateStore>, task_queue: Arc<dyn TaskQueue>) -> Self {
+        let committer = Arc::new(SequentialCommitter::new(store.clone(), task_queue.clone()));
         let mut engine = Self {
             blueprints: DashMap::new(),
+            latest_versions: DashMap::new(),
+            blueprint_store: None,
             executable_cache: DashMap::new(),
             store,
             task_queue,
-            node_registry: HashMap::new(),
+            committer,
+            node_registry: Arc::new(NodeRegistry::new()),
+            status_changed: Notify::new(),
+            in_flight: DashMap::new(),
+            shutdown: CancellationToken::new(),
+            shutdown_grace: DEFAULT_SHUTDOWN_GRACE,
         };
         
         // Register internal FusedNode handler
@@ -93,59 +294,156 @@ impl Engine {
         engine
     }
 
-    pub fn register_blueprint(&self, blueprint: Blueprint) {
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle { token: self.shutdown.clone() }
+    }
+
+    pub fn set_shutdown_grace(&mut self, grace: Duration) {
+        self.shutdown_grace = grace;
+    }
+
+    /// Replaces the default committer, which issues one store/queue call per write, e.g. with
+    /// a `RedisCommitter` that batches them into a single round-trip.
+    pub fn set_committer(&mut self, committer: Arc<dyn TaskCommitter>) {
+        self.committer = committer;
+    }
+
+    /// Lets the engine fetch blueprints it has not registered from a store shared with other processes.
+    pub fn set_blueprint_store(&mut self, store: Arc<dyn BlueprintStore>) {
+        self.blueprint_store = Some(store);
+    }
+
+    /// Registers the blueprint and saves it to the blueprint store, if any, so workers
+    /// elsewhere can run it.
+    pub async fn publish_blueprint(&self, mut blueprint: Blueprint) -> Result<()> {
+        if blueprint.version.is_empty() {
+            blueprint.version = blueprint.content_hash();
+        }
+        if let Some(store) = &self.blueprint_store {
+            store.save_blueprint(&blueprint).await?;
+        }
+        self.register_blueprint(blueprint);
+        Ok(())
+    }
+
+    /// Makes the blueprint the latest version of its id. Instances already running keep
+    /// the version they were started on.
+    pub fn register_blueprint(&self, mut blueprint: Blueprint) {
+        if blueprint.version.is_empty() {
+            blueprint.version = blueprint.content_hash();
+        }
         let id = blueprint.id.clone();
-        self.blueprints.insert(id.clone(), Arc::new(blueprint));
-        self.executable_cache.remove(&id);
+        let version = blueprint.version.clone();
+        self.blueprints.insert(versioned_blueprint_key(&id, &version), Arc::new(blueprint));
+        self.latest_versions.insert(id, version);
     }
 
     pub fn register_node(&mut self, definition: Box<dyn NodeDefinition>) {
-        self.node_registry.insert(definition.name().to_string(), definition);
+        self.node_registry.register(definition);
     }
 
     pub fn register_function(&mut self, handler: Arc<dyn FunctionHandler>) {
-        let def = FunctionNodeDefinition { handler };
-        self.register_node(Box::new(def));
+        self.node_registry.register_function(handler);
+    }
+
+    /// The node definitions of this engine, e.g. for `Compiler::with_registry`.
+    pub fn node_registry(&self) -> Arc<NodeRegistry> {
+        self.node_registry.clone()
     }
 
-    fn prepare_blueprint(&self, blueprint_id: &str) -> Result<Arc<Vec<Box<dyn Node>>>> {
-        if let Some(nodes) = self.executable_cache.get(blueprint_id) {
-            return Ok(nodes.clone());
+    /// Looks the blueprint up locally, then in the blueprint store.
+    /// Without a version, returns the latest one.
+    async fn fetch_blueprint(&self, blueprint_id: &str, version: Option<&str>) -> Result<Arc<Blueprint>> {
+        let local_version = match version {
+            Some(version) => Some(version.to_string()),
+            None => self.latest_versions.get(blueprint_id).map(|v| v.value().clone()),
+        };
+        if let Some(local_version) = local_version
+            && let Some(blueprint) = self.blueprints.get(&versioned_blueprint_key(blueprint_id, &local_version)) {
+            return Ok(blueprint.clone());
+        }
+        if let Some(store) = &self.blueprint_store
+            && let Some(blueprint) = store.get_blueprint(blueprint_id, version).await? {
+            info!(blueprint_id = blueprint_id, version = %blueprint.version, "Fetched blueprint from store");
+            let version = blueprint.version.clone();
+            self.latest_versions.entry(blueprint_id.to_string()).or_insert_with(|| version.clone());
+            let blueprint = self.blueprints.entry(versioned_blueprint_key(blueprint_id, &version))
+                .or_insert_with(|| Arc::new(blueprint))
+                .clone();
+            return Ok(blueprint);
+        }
+        match version {
+            Some(version) => Err(anyhow!("Blueprint not found: {} (version {})", blueprint_id, version)),
+            None => Err(anyhow!("Blueprint not found: {}", blueprint_id)),
         }
+    }
 
-        let blueprint = self.blueprints.get(blueprint_id)
-            .ok_or_else(|| anyhow!("Blueprint not found: {}", blueprint_id))?;
+    async fn load_executable(&self, blueprint_id: &str, version: Option<&str>) -> Result<Arc<Executable>> {
+        if let Some(version) = version
+            && let Some(executable) = self.executable_cache.get(&versioned_blueprint_key(blueprint_id, version)) {
+            return Ok(executable.clone());
+        }
+        let blueprint = self.fetch_blueprint(blueprint_id, version).await?;
+        self.prepare_blueprint(&blueprint)
+    }
+
+    fn prepare_blueprint(&self, blueprint: &Blueprint) -> Result<Arc<Executable>> {
+        let cache_key = versioned_blueprint_key(&blueprint.id, &blueprint.version);
+        if let Some(executable) = self.executable_cache.get(&cache_key) {
+            return Ok(executable.clone());
+        }
 
         let mut nodes = Vec::with_capacity(blueprint.nodes.len());
+        let mut policies = Vec::with_capacity(blueprint.nodes.len());
+        let mut node_ids = Vec::with_capacity(blueprint.nodes.len());
         for bp_node in &blueprint.nodes {
             let def = self.node_registry.get(&bp_node.kind)
                 .ok_or_else(|| anyhow!("Node definition not found: {}", bp_node.kind))?;
             
             let node_instance = def.prepare(bp_node.params.clone())?;
             nodes.push(node_instance);
+            policies.push(NodePolicy::from_node(bp_node)?);
+            node_ids.push(bp_node.id.clone());
         }
 
-        let arc_nodes = Arc::new(nodes);
-        self.executable_cache.insert(blueprint_id.to_string(), arc_nodes.clone());
-        Ok(arc_nodes)
+        let executable = Arc::new(Executable {
+            nodes,
+            node_ids,
+            policies,
+            in_fork: fork_members(blueprint),
+            default_timeout: blueprint.default_timeout.map(Duration::from_millis),
+        });
+        self.executable_cache.insert(cache_key, executable.clone());
+        Ok(executable)
     }
 
+    /// Starts an instance on the latest version of the blueprint. `initial_vars` override the
+    /// blueprint's variable and input defaults; the declared inputs are validated first.
     pub async fn start_workflow(&self, blueprint_id: &str, initial_vars: HashMap<String, Value>) -> Result<Uuid> {
-        let _ = self.prepare_blueprint(blueprint_id)?;
-        let blueprint_meta = self.blueprints.get(blueprint_id).unwrap(); 
+        let blueprint_meta = self.fetch_blueprint(blueprint_id, None).await?;
+        self.prepare_blueprint(&blueprint_meta)?;
+        let initial_vars = resolve_initial_vars(&blueprint_meta.variables, &blueprint_meta.inputs, initial_vars)
+            .map_err(|e| anyhow!("Cannot start {}: {}", blueprint_id, e))?;
 
         let instance_id = Uuid::new_v4();
         
-        // 1. Initialize State
+        // 1. Initialize State, pinned to the current version of the blueprint
         self.store.init_instance(instance_id, initial_vars).await?;
+        self.store.set_blueprint_version(instance_id, &blueprint_meta.version).await?;
+        self.store.track_blueprint_run(instance_id, &versioned_blueprint_key(blueprint_id, &blueprint_meta.version)).await?;
+        self.store.set_status(instance_id, InstanceStatus::Pending).await?;
 
         // 2. Push Initial Task
         let task = Task {
             instance_id,
             workflow_id: blueprint_id.to_string(),
+            blueprint_version: Some(blueprint_meta.version.clone()),
             token_id: Uuid::new_v4(),
             node_index: blueprint_meta.start_index,
             flow_id: Uuid::new_v4(),
+            attempt: 0,
+            deadline: blueprint_meta.deadline.map(|d| unix_millis() + d),
+            compensation: false,
         };
 
         self.task_queue.push(task).await
@@ -154,69 +452,59 @@ impl Engine {
         Ok(instance_id)
     }
 
+    /// Pops and executes tasks until a shutdown is requested through `shutdown_handle`.
     pub async fn run_worker(&self) {
-        info!("Worker started.");
-
-        loop {
-            match self.task_queue.pop().await {
-                Ok(Some(task)) => {
-                    let workflow_id = &task.workflow_id;
-                    
-                    // Create Ephemeral Context
-                    let context = Context::new(
-                        task.instance_id,
-                        workflow_id.clone(),
-                        self.store.clone()
-                    );
-
-                    let nodes = if let Some(n) = self.executable_cache.get(workflow_id) {
-                        n.clone()
-                    } else {
-                        if let Ok(n) = self.prepare_blueprint(workflow_id) {
-                            n
-                        } else {
-                            error!(workflow_id = %workflow_id, "Failed to prepare blueprint");
-                            continue;
-                        }
-                    };
-
-                    if task.node_index >= nodes.len() {
-                        error!(node_index = task.node_index, "Node index out of bounds");
-                        continue;
-                    }
+        self.worker_loop(&WorkerStats::new(0), &WorkerScope::new(&self.shutdown)).await
+    }
+
+    /// Starts `n` workers on the Tokio runtime, so at most `n` tasks of this engine
+    /// execute concurrently in this process. The pool can be shut down on its own,
+    /// without stopping the engine's other workers.
+    pub fn spawn_workers(self: &Arc<Self>, n: usize) -> WorkerPool {
+        assert!(n > 0, "A worker pool needs at least one worker");
+        let scope = WorkerScope::new(&self.shutdown);
+        let mut handles = Vec::with_capacity(n);
+        let mut stats = Vec::with_capacity(n);
+        for worker in 0..n {
+            let worker_stats = Arc::new(WorkerStats::new(worker));
+            let engine = self.clone();
+            let loop_stats = worker_stats.clone();
+            let loop_scope = scope.clone();
+            handles.push(tokio::spawn(async move {
+                engine.worker_loop(&loop_stats, &loop_scope).await;
+            }));
+            stats.push(worker_stats);
+        }
+        WorkerPool::new(handles, stats, ShutdownHandle { token: scope.shutdown })
+    }
+
+    async fn worker_loop(&self, stats: &WorkerStats, scope: &WorkerScope) {
+        info!(worker = stats.worker(), "Worker started.");
 
-                    let node = &nodes[task.node_index];
-                    
-                    let mut syscall = EngineSyscall {
-                        task: task.clone(),
-                        pending_tasks: Vec::new(),
-                    };
-
-                    // Global timeout configuration (hardcoded for now)
-                    let timeout_duration = Duration::from_secs(60);
-
-                    match timeout(timeout_duration, node.execute(&context, &task, &mut syscall)).await {
-                        Ok(Ok(())) => {
-                            // Flush pending tasks
-                            for new_task in syscall.pending_tasks {
-                                if let Err(e) = self.task_queue.push(new_task).await {
-                                    error!("Failed to schedule task: {}", e);
-                                }
-                            }
-                        }
-                        Ok(Err(e)) => {
-                            error!(instance_id = %task.instance_id, node_index = task.node_index, error = ?e, "Task failed");
-                        }
-                        Err(_) => {
-                            error!(instance_id = %task.instance_id, node_index = task.node_index, "Task timed out after {:?}", timeout_duration);
-                        }
+        while !scope.shutdown.is_cancelled() {
+            let pop = self.task_queue.pop();
+            tokio::pin!(pop);
+
+            let popped = tokio::select! {
+                popped = &mut pop => popped,
+                _ = scope.shutdown.cancelled() => {
+                    // Let a pop already in progress finish, so its task is not lost
+                    if let Ok(Ok(Some(delivery))) = timeout(POP_DRAIN_TIMEOUT, pop).await {
+                        self.release(&delivery).await;
                     }
+                    break;
+                }
+            };
+
+            match popped {
+                Ok(Some(delivery)) => {
+                    stats.set_busy(true);
+                    let outcome = self.process_task_with_grace(&delivery, scope).await;
+                    stats.record(outcome);
                 }
                 Ok(None) => {
-                    // Queue closed or empty? If empty and using mpsc, it waits. 
-                    // If pop() returns None it implies channel closed.
-                    warn!("Task queue returned None (closed?), worker stopping.");
-                    break;
+                    // Nothing arrived within the queue's poll interval
+                    debug!("Task queue idle.");
                 }
                 Err(e) => {
                     error!("Error popping from task queue: {}", e);
@@ -224,6 +512,677 @@ impl Engine {
                 }
             }
         }
+
+        // Delayed retries are pushed right away instead of being lost with the process
+        scope.retries.close();
+        scope.retries.wait().await;
+        info!(worker = stats.worker(), "Worker stopped.");
+    }
+
+    /// Runs a delivered task and acks it once its follow-up tasks were pushed. Once a shutdown
+    /// is requested the task gets `shutdown_grace` to finish, after which it is aborted and
+    /// its delivery released, for another worker to pick up.
+    async fn process_task_with_grace(&self, delivery: &Delivery, scope: &WorkerScope) -> TaskOutcome {
+        let task = &delivery.task;
+        let process = self.process_task(task.clone(), &delivery.receipt, scope);
+        tokio::pin!(process);
+
+        let grace_expired = async {
+            scope.shutdown.cancelled().await;
+            info!(instance_id = %task.instance_id, node_index = task.node_index, grace = ?self.shutdown_grace, "Shutdown requested, draining in-flight task");
+            tokio::time::sleep(self.shutdown_grace).await;
+        };
+
+        tokio::select! {
+            outcome = &mut process => {
+                // A scheduled retry acks the delivery itself, once the retry is queued
+                if outcome != TaskOutcome::Retrying
+                    && let Err(e) = self.task_queue.ack(&delivery.receipt).await {
+                    // The lease will expire and the task be delivered again
+                    error!(instance_id = %task.instance_id, node_index = task.node_index, error = ?e, "Failed to ack task");
+                }
+                outcome
+            }
+            _ = grace_expired => {
+                warn!(instance_id = %task.instance_id, node_index = task.node_index, "Grace period expired, re-queueing in-flight task");
+                self.release(delivery).await;
+                TaskOutcome::Skipped
+            }
+        }
+    }
+
+    async fn release(&self, delivery: &Delivery) {
+        if let Err(e) = self.task_queue.nack(&delivery.receipt).await {
+            error!(instance_id = %delivery.task.instance_id, error = ?e, "Failed to release task");
+        }
+    }
+
+    async fn process_task(&self, task: Task, receipt: &str, scope: &WorkerScope) -> TaskOutcome {
+        let workflow_id = &task.workflow_id;
+
+        match self.store.get_status(task.instance_id).await {
+            Ok(Some(status)) if status.is_terminal() => {
+                // Stops sibling branches once the instance has failed or finished
+                debug!(instance_id = %task.instance_id, status = %status, "Dropping task of finished instance");
+                return TaskOutcome::Skipped;
+            }
+            Ok(Some(InstanceStatus::Suspended)) => {
+                self.park_task(task).await;
+                return TaskOutcome::Skipped;
+            }
+            Ok(Some(InstanceStatus::Compensating)) if !task.compensation => {
+                debug!(instance_id = %task.instance_id, "Dropping task of compensating instance");
+                return TaskOutcome::Skipped;
+            }
+            Ok(Some(InstanceStatus::Pending)) => {
+                self.update_status(task.instance_id, InstanceStatus::Running).await;
+            }
+            Ok(_) => {}
+            Err(e) => error!(instance_id = %task.instance_id, "Failed to read instance status: {}", e),
+        }
+        
+        let guard = self.track_in_flight(task.instance_id);
+
+        // Create Ephemeral Context; its writes are committed together once the node succeeded
+        let context = Context::new(
+            task.instance_id,
+            workflow_id.clone(),
+            self.store.clone()
+        ).with_cancellation(guard.token.clone()).buffered();
+
+        let executable = match self.load_executable(workflow_id, task.blueprint_version.as_deref()).await {
+            Ok(e) => e,
+            Err(e) => {
+                error!(workflow_id = %workflow_id, "Failed to prepare blueprint");
+                self.fail_instance(&task, None, e).await;
+                return TaskOutcome::Failed;
+            }
+        };
+
+        if task.node_index >= executable.nodes.len() {
+            let e = anyhow!("Node index {} out of bounds", task.node_index);
+            self.fail_instance(&task, Some(task.node_index), e).await;
+            return TaskOutcome::Failed;
+        }
+
+        let node = &executable.nodes[task.node_index];
+        let policy = &executable.policies[task.node_index];
+
+        // Precedence: DSL node timeout > handler default > workflow default > engine default
+        let node_timeout = policy.timeout
+            .or_else(|| node.default_timeout())
+            .or(executable.default_timeout)
+            .unwrap_or(DEFAULT_NODE_TIMEOUT);
+
+        // Never run past the instance deadline
+        let remaining = task.deadline.map(|d| Duration::from_millis(d.saturating_sub(unix_millis())));
+        let (timeout_duration, timeout_error) = match remaining {
+            Some(remaining) if remaining.is_zero() => {
+                self.fail_instance(&task, Some(task.node_index), NodeError::DeadlineExceeded.into()).await;
+                return TaskOutcome::Failed;
+            }
+            Some(remaining) if remaining < node_timeout => (remaining, NodeError::DeadlineExceeded),
+            _ => (node_timeout, NodeError::Timeout(node_timeout)),
+        };
+        
+        let mut syscall = EngineSyscall {
+            task: task.clone(),
+            pending_tasks: Vec::new(),
+            join: None,
+            terminated: false,
+        };
+
+        let result = tokio::select! {
+            // Polled first, so a cooperative handler can observe the token and wind down
+            biased;
+            result = timeout(timeout_duration, node.execute(&context, &task, &mut syscall)) => match result {
+                Ok(result) => result,
+                Err(_) => Err(timeout_error.into()),
+            },
+            // Otherwise dropping the execution future aborts the handler
+            _ = guard.wait_for_cancellation() => Err(NodeError::Cancelled.into()),
+        };
+
+        if guard.token.is_cancelled() {
+            // Whatever the handler returned, its pending tasks are discarded
+            info!(instance_id = %task.instance_id, node_index = task.node_index, "Task aborted, instance stopped");
+            return TaskOutcome::Skipped;
+        }
+
+        // The instance may have been stopped by another worker while the handler ran
+        if !task.compensation && self.is_stopped(task.instance_id).await {
+            info!(instance_id = %task.instance_id, node_index = task.node_index, "Dropping result of stopped instance");
+            return TaskOutcome::Skipped;
+        }
+
+        if task.compensation {
+            let result = match result {
+                Ok(()) => {
+                    let commit = TaskCommit { instance_id: task.instance_id, vars: context.take_writes(), ..Default::default() };
+                    self.committer.commit(commit).await
+                }
+                Err(e) => Err(e),
+            };
+            let outcome = match result {
+                Ok(()) => TaskOutcome::Succeeded,
+                Err(e) => {
+                    // Best effort: a failed compensation must not block the ones before it
+                    error!(instance_id = %task.instance_id, node_index = task.node_index, error = ?e, "Compensation failed");
+                    TaskOutcome::Failed
+                }
+            };
+            self.advance_compensation(&task).await;
+            return outcome;
+        }
+
+        let result = match result {
+            Ok(()) => {
+                // The completion record goes in with the successors, before they can fail
+                let commit = TaskCommit {
+                    instance_id: task.instance_id,
+                    vars: context.take_writes(),
+                    compensation: policy.compensate,
+                    join: syscall.join,
+                    tasks: syscall.pending_tasks,
+                };
+                self.committer.commit(commit).await
+            }
+            Err(e) => Err(e),
+        };
+
+        let error = match result {
+            Ok(()) => {
+                if syscall.terminated {
+                    self.update_status(task.instance_id, InstanceStatus::Completed).await;
+                }
+                return TaskOutcome::Succeeded;
+            }
+            Err(e) => e,
+        };
+
+        let deadline_exceeded = matches!(error.downcast_ref::<NodeError>(), Some(NodeError::DeadlineExceeded));
+        if !deadline_exceeded && self.schedule_retry(&task, receipt, scope, policy, &error) {
+            return TaskOutcome::Retrying;
+        }
+
+        if !deadline_exceeded && let Some(handler) = policy.on_error {
+            let node_id = &executable.node_ids[task.node_index];
+            if let Err(e) = self.route_to_error_handler(&task, node_id, handler, &error).await {
+                error!(instance_id = %task.instance_id, "Failed to route to error handler: {}", e);
+                self.fail_instance(&task, Some(task.node_index), error).await;
+            }
+            return TaskOutcome::Failed;
+        }
+
+        // Only tasks that used up their retries are kept for replay; errors the node does not
+        // retry fail the instance for good
+        let exhausted = policy.retry.as_ref().is_some_and(|retry| retry.retries(ErrorKind::classify(&error)));
+        if !deadline_exceeded && exhausted {
+            self.dead_letter(&task, &executable.node_ids[task.node_index], &error).await;
+        }
+        self.fail_instance(&task, Some(task.node_index), error).await;
+        TaskOutcome::Failed
+    }
+
+    fn track_in_flight(&self, instance_id: Uuid) -> InFlightGuard<'_> {
+        let mut entry = self.in_flight.entry(instance_id).or_insert_with(|| {
+            let cancelled = CancellationToken::new();
+            let watcher = tokio::spawn(watch_for_cancellation(self.store.clone(), instance_id, cancelled.clone()));
+            InFlight {
+                token: CancellationToken::new(),
+                tasks: 0,
+                cancelled,
+                watcher: watcher.abort_handle(),
+            }
+        });
+        entry.tasks += 1;
+        InFlightGuard {
+            in_flight: &self.in_flight,
+            instance_id,
+            token: entry.token.clone(),
+            cancelled: entry.cancelled.clone(),
+        }
+    }
+
+
+    /// Whether an instance no longer accepts results of regular tasks: it finished,
+    /// or a failure started rolling it back.
+    async fn is_stopped(&self, instance_id: Uuid) -> bool {
+        match self.store.get_status(instance_id).await {
+            Ok(Some(status)) => status.is_terminal() || status == InstanceStatus::Compensating,
+            Ok(None) => false,
+            Err(e) => {
+                error!(instance_id = %instance_id, "Failed to read instance status: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Aborts the tasks of the instance running on this engine. Tasks started afterwards,
+    /// such as compensations, get a fresh token.
+    fn abort_in_flight(&self, instance_id: Uuid) {
+        if let Some(mut in_flight) = self.in_flight.get_mut(&instance_id) {
+            std::mem::replace(&mut in_flight.token, CancellationToken::new()).cancel();
+        }
+    }
+
+    /// Cancels an instance: queued tasks are dropped when popped and running tasks are aborted.
+    /// Returns false if the instance had already finished.
+    pub async fn cancel(&self, instance_id: Uuid, reason: &str) -> Result<bool> {
+        if self.store.get_status(instance_id).await?.is_none() {
+            return Err(anyhow!("Instance not found: {}", instance_id));
+        }
+
+        let applied = self.store.transition_status(instance_id, InstanceStatus::Cancelled).await?;
+        if applied {
+            self.store.record_failure(instance_id, InstanceFailure::cancelled(reason)).await?;
+            // Tasks parked while suspended will never run
+            self.store.take_parked_tasks(instance_id).await?;
+            self.abort_in_flight(instance_id);
+            info!(instance_id = %instance_id, reason = %reason, "Instance cancelled");
+            self.status_changed.notify_waiters();
+            self.finish_blueprint_run(instance_id).await;
+        }
+        Ok(applied)
+    }
+
+    /// Suspends a running instance. Running tasks finish, but every task popped afterwards
+    /// is parked in the store until `resume`. Returns false if the instance is not running.
+    pub async fn suspend(&self, instance_id: Uuid) -> Result<bool> {
+        match self.store.get_status(instance_id).await? {
+            None => return Err(anyhow!("Instance not found: {}", instance_id)),
+            Some(InstanceStatus::Pending | InstanceStatus::Running) => {}
+            Some(_) => return Ok(false),
+        }
+
+        let applied = self.store.transition_status(instance_id, InstanceStatus::Suspended).await?;
+        if applied {
+            info!(instance_id = %instance_id, "Instance suspended");
+        }
+        Ok(applied)
+    }
+
+    /// Resumes a suspended instance and re-enqueues its parked tasks.
+    /// Returns false if the instance is not suspended.
+    pub async fn resume(&self, instance_id: Uuid) -> Result<bool> {
+        match self.store.get_status(instance_id).await? {
+            None => return Err(anyhow!("Instance not found: {}", instance_id)),
+            Some(InstanceStatus::Suspended) => {}
+            Some(_) => return Ok(false),
+        }
+
+        let applied = self.store.transition_status(instance_id, InstanceStatus::Running).await?;
+        if applied {
+            let count = self.requeue_parked_tasks(instance_id).await?;
+            info!(instance_id = %instance_id, tasks = count, "Instance resumed");
+        }
+        Ok(applied)
+    }
+
+    async fn park_task(&self, task: Task) {
+        let instance_id = task.instance_id;
+        debug!(instance_id = %instance_id, node_index = task.node_index, "Parking task of suspended instance");
+        if let Err(e) = self.store.park_task(task).await {
+            error!(instance_id = %instance_id, "Failed to park task: {}", e);
+            return;
+        }
+
+        // A resume may have drained the parked tasks between our status read and the park
+        match self.store.get_status(instance_id).await {
+            Ok(Some(InstanceStatus::Suspended)) => {}
+            Ok(_) => {
+                if let Err(e) = self.requeue_parked_tasks(instance_id).await {
+                    error!(instance_id = %instance_id, "Failed to requeue parked tasks: {}", e);
+                }
+            }
+            Err(e) => error!(instance_id = %instance_id, "Failed to read instance status: {}", e),
+        }
+    }
+
+    async fn requeue_parked_tasks(&self, instance_id: Uuid) -> Result<usize> {
+        let tasks = self.store.take_parked_tasks(instance_id).await?;
+        let count = tasks.len();
+        for task in tasks {
+            self.task_queue.push(task).await?;
+        }
+        Ok(count)
+    }
+
+    /// Catches a node failure: records it in `_error` and continues at the node's error branch.
+    async fn route_to_error_handler(&self, task: &Task, node_id: &str, handler: NodeIndex, error: &anyhow::Error) -> Result<()> {
+        let kind = ErrorKind::classify(error);
+        warn!(instance_id = %task.instance_id, node_id = %node_id, error = %error, "Task failed, jumping to error handler");
+
+        let error_value = json!({
+            "message": error.to_string(),
+            "node_id": node_id,
+            "kind": kind.as_str(),
+        });
+        self.store.set_var(task.instance_id, ERROR_VAR, error_value).await?;
+
+        let handler_task = Task {
+            node_index: handler,
+            attempt: 0,
+            ..task.clone()
+        };
+        self.task_queue.push(handler_task).await
+    }
+
+    /// Re-enqueues a failed task after its backoff delay, then acks its delivery. The delivery
+    /// stays leased during the backoff, so the task is redelivered if this process dies before
+    /// the retry is queued; a backoff longer than the queue's visibility timeout may run it twice.
+    /// Returns false when the node has no retry policy or the policy is exhausted.
+    fn schedule_retry(&self, task: &Task, receipt: &str, scope: &WorkerScope, policy: &NodePolicy, error: &anyhow::Error) -> bool {
+        let Some(retry) = &policy.retry else {
+            return false;
+        };
+
+        let attempt = task.attempt + 1;
+        if !retry.should_retry(attempt, ErrorKind::classify(error)) {
+            return false;
+        }
+
+        let delay = retry.delay_for_attempt(attempt);
+        warn!(instance_id = %task.instance_id, node_index = task.node_index, attempt, delay = ?delay, error = %error, "Task failed, retrying");
+
+        let retry_task = Task { attempt, ..task.clone() };
+        let receipt = receipt.to_string();
+        let queue = self.task_queue.clone();
+        let shutdown = scope.shutdown.clone();
+        scope.retries.spawn(async move {
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.cancelled() => {}
+            }
+            let instance_id = retry_task.instance_id;
+            // On failure the delivery stays leased, so the failed attempt is delivered again
+            let result = async {
+                queue.push(retry_task).await?;
+                queue.ack(&receipt).await
+            };
+            if let Err(e) = result.await {
+                error!(instance_id = %instance_id, error = ?e, "Failed to schedule retry");
+            }
+        });
+        true
+    }
+
+    /// Moves an instance to a new status, waking local waiters when it becomes terminal.
+    async fn update_status(&self, instance_id: Uuid, status: InstanceStatus) {
+        match self.store.transition_status(instance_id, status).await {
+            Ok(true) => {
+                info!(instance_id = %instance_id, status = %status, "Instance status changed");
+                if status.is_terminal() {
+                    self.status_changed.notify_waiters();
+                    self.finish_blueprint_run(instance_id).await;
+                }
+            }
+            Ok(false) => {}
+            Err(e) => error!(instance_id = %instance_id, "Failed to update instance status: {}", e),
+        }
+    }
+
+    /// Stops counting a finished instance on its blueprint version and evicts the versions
+    /// that drained.
+    async fn finish_blueprint_run(&self, instance_id: Uuid) {
+        if let Err(e) = self.store.finish_blueprint_run(instance_id).await {
+            error!(instance_id = %instance_id, "Failed to release blueprint version: {}", e);
+            return;
+        }
+        self.evict_drained_blueprints().await;
+    }
+
+    /// Drops the blueprint versions that are no longer the latest and have no running
+    /// instances left, locally and from the blueprint store. A version replaced while
+    /// nothing ran on it goes when the next instance finishes.
+    async fn evict_drained_blueprints(&self) {
+        let stale: Vec<Arc<Blueprint>> = self.blueprints.iter()
+            .filter(|entry| self.latest_versions.get(&entry.id).is_none_or(|latest| *latest != entry.version))
+            .map(|entry| entry.value().clone())
+            .collect();
+        for blueprint in stale {
+            let key = versioned_blueprint_key(&blueprint.id, &blueprint.version);
+            match self.store.blueprint_runs(&key).await {
+                Ok(0) => {}
+                Ok(_) => continue,
+                Err(e) => {
+                    error!(blueprint = %key, "Failed to count blueprint runs: {}", e);
+                    continue;
+                }
+            }
+            self.blueprints.remove(&key);
+            self.executable_cache.remove(&key);
+            if let Some(store) = &self.blueprint_store
+                && let Err(e) = store.remove_blueprint(&blueprint.id, &blueprint.version).await {
+                error!(blueprint = %key, "Failed to remove blueprint from store: {}", e);
+            }
+            info!(blueprint = %key, "Evicted drained blueprint version");
+        }
+    }
+
+    /// Keeps a task whose retries are exhausted, so it can be replayed once the cause is fixed.
+    async fn dead_letter(&self, task: &Task, node_id: &str, error: &anyhow::Error) {
+        let letter = match DeadLetter::for_task(task, node_id, error) {
+            Ok(letter) => letter,
+            Err(e) => {
+                error!(instance_id = %task.instance_id, "Failed to build dead letter: {}", e);
+                return;
+            }
+        };
+        info!(instance_id = %task.instance_id, node_id = node_id, dead_letter = %letter.id, "Task dead-lettered");
+        if let Err(e) = self.task_queue.dead_letter(letter).await {
+            error!(instance_id = %task.instance_id, "Failed to dead-letter task: {}", e);
+        }
+    }
+
+    pub async fn dead_letters(&self) -> Result<Vec<DeadLetter>> {
+        self.task_queue.dead_letters().await
+    }
+
+    pub async fn get_dead_letter(&self, id: Uuid) -> Result<Option<DeadLetter>> {
+        self.task_queue.get_dead_letter(id).await
+    }
+
+    /// Re-queues a dead-lettered task with a fresh retry budget, reopening its Failed instance
+    /// and clearing the failure. Returns `false` if there is no such dead letter.
+    ///
+    /// Only the dead-lettered task is re-driven, so replay is refused when the instance was
+    /// rolled back by compensations or the task ran inside a Parallel branch, whose sibling
+    /// branches were dropped when the instance failed and would never reach the Join.
+    pub async fn replay_dead_letter(&self, id: Uuid) -> Result<bool> {
+        let Some(letter) = self.task_queue.get_dead_letter(id).await? else {
+            return Ok(false);
+        };
+        let mut task = letter.task()
+            .map_err(|e| anyhow!("Dead letter {} does not hold a valid task: {}", id, e))?;
+        let instance_id = task.instance_id;
+
+        match self.store.get_status(instance_id).await? {
+            Some(InstanceStatus::Failed) => {}
+            Some(status) => return Err(anyhow!("Instance {} is {}, not replaying dead letter {}", instance_id, status, id)),
+            None => return Err(anyhow!("Instance not found: {}", instance_id)),
+        }
+        if self.store.get_failure(instance_id).await?.is_some_and(|f| f.compensated) {
+            return Err(anyhow!("Instance {} was compensated, not replaying dead letter {}", instance_id, id));
+        }
+
+        // Counted before the blueprint is loaded, so its version cannot be evicted once it was found
+        let version = match &task.blueprint_version {
+            Some(version) => Some(version.clone()),
+            None => self.store.get_blueprint_version(instance_id).await?,
+        };
+        if let Some(version) = &version {
+            self.store.track_blueprint_run(instance_id, &versioned_blueprint_key(&task.workflow_id, version)).await?;
+        }
+        let replayed = async {
+            let executable = self.load_executable(&task.workflow_id, version.as_deref()).await?;
+            if executable.in_fork.get(task.node_index).copied().unwrap_or(false) {
+                return Err(anyhow!("Dead letter {} ran inside a Parallel branch, its siblings cannot be replayed", id));
+            }
+
+            // A concurrent replay of the same letter loses here
+            if !self.task_queue.remove_dead_letter(id).await? {
+                return Ok(false);
+            }
+            if !self.store.reopen_failed(instance_id).await? {
+                // Keep the letter for a later attempt
+                self.task_queue.dead_letter(letter).await?;
+                return Err(anyhow!("Instance {} is no longer Failed, not replaying dead letter {}", instance_id, id));
+            }
+            Ok(true)
+        }.await;
+        if !matches!(replayed, Ok(true)) {
+            // Unless a concurrent replay reopened the instance
+            if self.store.get_status(instance_id).await?.is_some_and(|status| status.is_terminal()) {
+                self.finish_blueprint_run(instance_id).await;
+            }
+            return replayed;
+        }
+
+        task.attempt = 0;
+        self.task_queue.push(task).await?;
+        info!(instance_id = %instance_id, dead_letter = %id, "Dead letter replayed");
+        self.status_changed.notify_waiters();
+        Ok(true)
+    }
+
+    pub async fn purge_dead_letters(&self) -> Result<usize> {
+        self.task_queue.purge_dead_letters().await
+    }
+
+    /// Marks the instance as Failed and records the error. Only the first failure is kept;
+    /// running tasks of sibling branches are aborted and queued ones dropped when popped.
+    /// If completed nodes declared compensations, the instance goes through Compensating first.
+    async fn fail_instance(&self, task: &Task, node_index: Option<NodeIndex>, error: anyhow::Error) {
+        let instance_id = task.instance_id;
+        error!(instance_id = %instance_id, node_index = ?node_index, error = ?error, "Task failed");
+
+        let compensation = match self.store.peek_compensation(instance_id).await {
+            Ok(compensation) => compensation,
+            Err(e) => {
+                error!(instance_id = %instance_id, "Failed to read compensation stack: {}", e);
+                None
+            }
+        };
+        let status = if compensation.is_some() { InstanceStatus::Compensating } else { InstanceStatus::Failed };
+
+        match self.store.transition_status(instance_id, status).await {
+            Ok(true) => {
+                self.abort_in_flight(instance_id);
+                let failure = InstanceFailure { compensated: compensation.is_some(), ..InstanceFailure::new(node_index, &error) };
+                if let Err(e) = self.store.record_failure(instance_id, failure).await {
+                    error!(instance_id = %instance_id, "Failed to record instance failure: {}", e);
+                }
+                info!(instance_id = %instance_id, status = %status, "Instance status changed");
+
+                match compensation {
+                    Some(compensation) => self.schedule_compensation(task, compensation).await,
+                    None => {
+                        self.status_changed.notify_waiters();
+                        self.finish_blueprint_run(instance_id).await;
+                    }
+                }
+            }
+            Ok(false) => {}
+            Err(e) => error!(instance_id = %instance_id, "Failed to update instance status: {}", e),
+        }
+    }
+
+    /// Pops the compensation that just ran and schedules the next one,
+    /// or finishes the instance as Failed once the stack is empty.
+    /// The stack lives in the store, so a crashed worker only loses the in-flight task.
+    async fn advance_compensation(&self, task: &Task) {
+        let instance_id = task.instance_id;
+        let next = async {
+            self.store.pop_compensation(instance_id).await?;
+            self.store.peek_compensation(instance_id).await
+        };
+
+        match next.await {
+            Ok(Some(compensation)) => self.schedule_compensation(task, compensation).await,
+            Ok(None) => {
+                info!(instance_id = %instance_id, "Compensation finished");
+                self.update_status(instance_id, InstanceStatus::Failed).await;
+            }
+            Err(e) => error!(instance_id = %instance_id, "Failed to advance compensation: {}", e),
+        }
+    }
+
+    async fn schedule_compensation(&self, task: &Task, compensation: NodeIndex) {
+        let compensation_task = Task {
+            token_id: Uuid::new_v4(),
+            node_index: compensation,
+            attempt: 0,
+            // Rollback must run even when the instance ran out of time
+            deadline: None,
+            compensation: true,
+            ..task.clone()
+        };
+        if let Err(e) = self.task_queue.push(compensation_task).await {
+            error!(instance_id = %task.instance_id, "Failed to schedule compensation: {}", e);
+        }
+    }
+
+    /// Re-schedules the pending compensation of an instance stuck in Compensating,
+    /// e.g. after the worker running it crashed.
+    pub async fn resume_compensation(&self, workflow_id: &str, instance_id: Uuid) -> Result<()> {
+        if self.store.get_status(instance_id).await? != Some(InstanceStatus::Compensating) {
+            return Err(anyhow!("Instance {} is not compensating", instance_id));
+        }
+        let task = Task {
+            instance_id,
+            workflow_id: workflow_id.to_string(),
+            blueprint_version: self.store.get_blueprint_version(instance_id).await?,
+            token_id: Uuid::new_v4(),
+            node_index: 0,
+            flow_id: Uuid::new_v4(),
+            attempt: 0,
+            deadline: None,
+            compensation: true,
+        };
+        match self.store.peek_compensation(instance_id).await? {
+            Some(compensation) => self.schedule_compensation(&task, compensation).await,
+            None => self.update_status(instance_id, InstanceStatus::Failed).await,
+        }
+        Ok(())
+    }
+
+    pub async fn get_status(&self, instance_id: Uuid) -> Result<Option<InstanceStatus>> {
+        self.store.get_status(instance_id).await
+    }
+
+    /// Returns the failure recorded for a Failed instance.
+    pub async fn get_failure(&self, instance_id: Uuid) -> Result<Option<InstanceFailure>> {
+        self.store.get_failure(instance_id).await
+    }
+
+    /// Returns the value written by the `End` node, if any.
+    pub async fn get_output(&self, instance_id: Uuid) -> Result<Option<Value>> {
+        self.store.get_var(instance_id, WORKFLOW_OUTPUT_VAR).await
+    }
+
+    /// Waits until the instance reaches a terminal status and returns it.
+    pub async fn await_completion(&self, instance_id: Uuid, wait_timeout: Duration) -> Result<InstanceStatus> {
+        let wait = async {
+            loop {
+                // Register before reading, so a completion in between is not missed
+                let notified = self.status_changed.notified();
+
+                match self.store.get_status(instance_id).await? {
+                    Some(status) if status.is_terminal() => return Ok(status),
+                    Some(_) => {}
+                    None => return Err(anyhow!("Instance not found: {}", instance_id)),
+                }
+
+                tokio::select! {
+                    _ = notified => {}
+                    _ = tokio::time::sleep(STATUS_POLL_INTERVAL) => {}
+                }
+            }
+        };
+
+        timeout(wait_timeout, wait).await
+            .map_err(|_| anyhow!("Timed out after {:?} waiting for instance {}", wait_timeout, instance_id))?
     }
 
     pub async fn get_instance_var(&self, instance_id: Uuid, key: &str) -> Option<Value> {
@@ -236,3 +1195,24 @@ impl Engine {
         }
     }
 }
+
+/// Marks the nodes between each Fork and its Join (the Join included), following every
+/// successor from the Fork's branch targets until the Join is reached.
+fn fork_members(blueprint: &Blueprint) -> Vec<bool> {
+    let mut members = vec![false; blueprint.nodes.len()];
+    for fork in blueprint.nodes.iter().filter(|n| n.kind == "fork") {
+        let join = fork.params.get("join_target").and_then(Value::as_u64).map(|j| j as usize);
+        let mut visited = HashSet::new();
+        let mut pending: Vec<NodeIndex> = fork.targets().into_iter().filter(|&t| Some(t) != join).collect();
+        while let Some(index) = pending.pop() {
+            if index >= members.len() || !visited.insert(index) {
+                continue;
+            }
+            members[index] = true;
+            if Some(index) != join {
+                pending.extend(blueprint.nodes[index].targets());
+            }
+        }
+    }
+    members
+}
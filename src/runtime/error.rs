@@ -0,0 +1,49 @@
+use serde::{Serialize, Deserialize};
+use std::time::Duration;
+use thiserror::Error;
+
+/// 引擎产生的节点执行错误
+#[derive(Debug, Error)]
+pub enum NodeError {
+    #[error("Node timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("Instance deadline exceeded")]
+    DeadlineExceeded,
+    #[error("Instance cancelled")]
+    Cancelled,
+}
+
+/// 节点错误分类，供重试 / 错误处理策略匹配 (`retry_on`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// 节点执行超时或实例超过截止时间
+    Timeout,
+    /// HTTP 请求失败 (连接错误、状态码错误等)
+    Http,
+    /// 其他未分类错误
+    Other,
+}
+
+impl ErrorKind {
+    /// Walks the error chain and returns the most specific known kind.
+    pub fn classify(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(NodeError::Timeout(_) | NodeError::DeadlineExceeded) = cause.downcast_ref::<NodeError>() {
+                return ErrorKind::Timeout;
+            }
+            if cause.downcast_ref::<reqwest::Error>().is_some() {
+                return ErrorKind::Http;
+            }
+        }
+        ErrorKind::Other
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Http => "http",
+            ErrorKind::Other => "other",
+        }
+    }
+}
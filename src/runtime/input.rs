@@ -0,0 +1,111 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use anyhow::{Result, anyhow};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// 工作流输入参数的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputType {
+    /// 不检查类型
+    #[default]
+    Any,
+    String,
+    /// 整数或浮点数
+    Number,
+    Integer,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl InputType {
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            InputType::Any => true,
+            InputType::String => value.is_string(),
+            InputType::Number => value.is_number(),
+            InputType::Integer => value.is_i64() || value.is_u64(),
+            InputType::Boolean => value.is_boolean(),
+            InputType::Array => value.is_array(),
+            InputType::Object => value.is_object(),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InputType::Any => "any",
+            InputType::String => "string",
+            InputType::Number => "number",
+            InputType::Integer => "integer",
+            InputType::Boolean => "boolean",
+            InputType::Array => "array",
+            InputType::Object => "object",
+        }
+    }
+}
+
+impl fmt::Display for InputType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 工作流声明的输入参数，启动实例时校验
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct InputSpec {
+    #[serde(rename = "type", default)]
+    pub kind: InputType,
+    /// 启动时必须提供 (声明了默认值时可省略)
+    #[serde(default)]
+    pub required: bool,
+    /// 调用方未提供时使用的值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+}
+
+/// Builds the initial variables of an instance: workflow `variables`, then input defaults,
+/// then the caller's values on top. Fails if a required input is missing or any declared
+/// input has the wrong type.
+pub fn resolve_initial_vars(
+    variables: &BTreeMap<String, Value>,
+    inputs: &BTreeMap<String, InputSpec>,
+    provided: HashMap<String, Value>,
+) -> Result<HashMap<String, Value>> {
+    let mut vars: HashMap<String, Value> = variables.iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    for (name, spec) in inputs {
+        if let Some(default) = &spec.default {
+            vars.insert(name.clone(), default.clone());
+        }
+    }
+    vars.extend(provided);
+
+    let mut problems = Vec::new();
+    for (name, spec) in inputs {
+        match vars.get(name) {
+            None if spec.required => problems.push(format!("missing required input `{}`", name)),
+            Some(value) if !spec.kind.matches(value) => {
+                problems.push(format!("input `{}` must be {}, got {}", name, spec.kind, json_type(value)))
+            }
+            _ => {}
+        }
+    }
+    if !problems.is_empty() {
+        return Err(anyhow!("Invalid inputs: {}", problems.join("; ")));
+    }
+    Ok(vars)
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
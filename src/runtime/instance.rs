@@ -0,0 +1,119 @@
+use serde::{Serialize, Deserialize};
+use anyhow::{Result, anyhow};
+use std::fmt;
+use std::str::FromStr;
+
+/// End 节点写入工作流输出的保留变量名
+pub const WORKFLOW_OUTPUT_VAR: &str = "_WORKFLOW_OUTPUT";
+
+/// 错误分支被触发时写入错误信息的保留变量名 (`message`, `node_id`, `kind`)
+pub const ERROR_VAR: &str = "_error";
+
+/// 工作流实例的生命周期状态 (持久化在 StateStore 中)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InstanceStatus {
+    /// 实例已创建，初始任务尚未被 Worker 取走
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+    /// 实例已失败，正在按完成顺序的逆序执行补偿动作，全部完成后进入 Failed
+    Compensating,
+    /// 被运维暂停：Worker 取到的任务会暂存到 StateStore，恢复后重新入队
+    Suspended,
+}
+
+impl InstanceStatus {
+    pub const TERMINAL: [InstanceStatus; 3] = [
+        InstanceStatus::Completed,
+        InstanceStatus::Failed,
+        InstanceStatus::Cancelled,
+    ];
+
+    /// 终态之后实例不会再产生新的任务
+    pub fn is_terminal(&self) -> bool {
+        Self::TERMINAL.contains(self)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InstanceStatus::Pending => "Pending",
+            InstanceStatus::Running => "Running",
+            InstanceStatus::Completed => "Completed",
+            InstanceStatus::Failed => "Failed",
+            InstanceStatus::Cancelled => "Cancelled",
+            InstanceStatus::Compensating => "Compensating",
+            InstanceStatus::Suspended => "Suspended",
+        }
+    }
+}
+
+impl fmt::Display for InstanceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for InstanceStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "Pending" => Ok(InstanceStatus::Pending),
+            "Running" => Ok(InstanceStatus::Running),
+            "Completed" => Ok(InstanceStatus::Completed),
+            "Failed" => Ok(InstanceStatus::Failed),
+            "Cancelled" => Ok(InstanceStatus::Cancelled),
+            "Compensating" => Ok(InstanceStatus::Compensating),
+            "Suspended" => Ok(InstanceStatus::Suspended),
+            _ => Err(anyhow!("Unknown instance status: {}", s)),
+        }
+    }
+}
+
+/// 实例失败记录 (失败节点、错误链、时间戳)
+/// 对 Cancelled 实例记录的是取消原因
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstanceFailure {
+    /// 失败节点在 Blueprint 中的索引 (蓝图加载失败时为空)
+    pub node_index: Option<usize>,
+    /// 顶层错误信息
+    pub error: String,
+    /// 完整错误链 (由外到内)
+    pub causes: Vec<String>,
+    /// Unix 毫秒时间戳
+    pub failed_at: u64,
+    /// 失败时是否执行了补偿 (实例经 Compensating 进入 Failed)
+    #[serde(default)]
+    pub compensated: bool,
+}
+
+impl InstanceFailure {
+    pub fn new(node_index: Option<usize>, error: &anyhow::Error) -> Self {
+        Self {
+            node_index,
+            error: error.to_string(),
+            causes: error.chain().map(|e| e.to_string()).collect(),
+            failed_at: unix_millis(),
+            compensated: false,
+        }
+    }
+
+    pub fn cancelled(reason: &str) -> Self {
+        Self {
+            node_index: None,
+            error: reason.to_string(),
+            causes: vec![reason.to_string()],
+            failed_at: unix_millis(),
+            compensated: false,
+        }
+    }
+}
+
+pub fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
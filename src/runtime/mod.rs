@@ -3,6 +3,14 @@ pub mod blueprint;
 pub mod task;
 pub mod engine;
 pub mod node;
+pub mod registry;
 pub mod syscall;
 pub mod storage;
+pub mod redis_connection;
 pub mod redis_storage;
+pub mod redis_stream;
+pub mod instance;
+pub mod error;
+pub mod retry;
+pub mod input;
+pub mod worker;
@@ -5,12 +5,18 @@ use crate::runtime::syscall::Syscall;
 use crate::runtime::task::Task;
 use anyhow::Result;
 use std::fmt::Debug;
+use std::time::Duration;
 
 /// 运行时节点接口
 #[async_trait]
 pub trait Node: Send + Sync + Debug {
     /// 运行时执行
     async fn execute(&self, ctx: &Context, task: &Task, syscall: &mut dyn Syscall) -> Result<()>;
+
+    /// 节点自身声明的默认超时 (DSL 中的 timeout 优先)
+    fn default_timeout(&self) -> Option<Duration> {
+        None
+    }
 }
 
 /// 节点工厂/定义接口
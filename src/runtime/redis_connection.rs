@@ -0,0 +1,63 @@
+use anyhow::Result;
+use redis::aio::{ConnectionManager, MultiplexedConnection};
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+/// Idle dedicated connections kept for blocking commands; more are opened on demand.
+const MAX_IDLE_BLOCKING: usize = 16;
+
+/// Connections shared by the Redis backend.
+///
+/// Regular commands go through one long-lived multiplexed connection that reconnects on
+/// failure. Blocking commands (`BLMOVE`, `XREADGROUP ... BLOCK`) hold a connection until they
+/// return, which would stall every command multiplexed behind them, so they check out a
+/// dedicated connection from a small pool instead.
+///
+/// Cloning is cheap; clones share the same connections.
+#[derive(Clone)]
+pub struct RedisConnections {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    client: redis::Client,
+    manager: OnceCell<ConnectionManager>,
+    blocking: Mutex<Vec<MultiplexedConnection>>,
+}
+
+impl RedisConnections {
+    /// Connects lazily, on the first command.
+    pub fn new(client: redis::Client) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                client,
+                manager: OnceCell::new(),
+                blocking: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    pub(crate) async fn get(&self) -> Result<ConnectionManager> {
+        let manager = self.inner.manager
+            .get_or_try_init(|| ConnectionManager::new(self.inner.client.clone()))
+            .await?;
+        Ok(manager.clone())
+    }
+
+    /// Checks out a dedicated connection. Hand it back with `checkin` once the command
+    /// succeeded; a connection that failed is dropped instead.
+    pub(crate) async fn checkout(&self) -> Result<MultiplexedConnection> {
+        let idle = self.inner.blocking.lock().unwrap().pop();
+        match idle {
+            Some(conn) => Ok(conn),
+            None => Ok(self.inner.client.get_multiplexed_async_connection().await?),
+        }
+    }
+
+    pub(crate) fn checkin(&self, conn: MultiplexedConnection) {
+        let mut idle = self.inner.blocking.lock().unwrap();
+        if idle.len() < MAX_IDLE_BLOCKING {
+            idle.push(conn);
+        }
+    }
+}
@@ -1,73 +1,547 @@
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 use crate::runtime::task::Task;
-use crate::runtime::storage::{StateStore, TaskQueue};
+use crate::runtime::blueprint::Blueprint;
+use crate::runtime::storage::{versioned_blueprint_key, BlueprintStore, DeadLetter, Delivery, JoinArrival, StateStore, TaskCommit, TaskCommitter, TaskQueue};
+use crate::runtime::redis_stream::{RedisStreamTaskQueue, TASK_FIELD};
+use crate::runtime::instance::{InstanceStatus, InstanceFailure, unix_millis};
+use crate::runtime::redis_connection::RedisConnections;
 use anyhow::Result;
-use redis::AsyncCommands;
+use redis::{AsyncCommands, Direction};
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
 
+/// How long a popped task stays leased before the reaper hands it to another worker.
+pub(crate) const DEFAULT_VISIBILITY_TIMEOUT: Duration = Duration::from_secs(300);
+/// Minimum time between two reaper runs of the same queue instance.
+pub(crate) const REAP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reliable queue: `pop` moves an entry into a processing list (`BLMOVE`) and records a lease
+/// in a sorted set scored by its expiry. Acked entries leave both; entries whose lease expired
+/// are moved back to the queue by the reaper, which `pop` runs every `REAP_INTERVAL`.
+///
+/// Entries are `QueueEntry` envelopes and the receipt of a delivery is the envelope's id, which
+/// also keys the lease and a hash from id to the entry in the processing list. Re-queueing gives
+/// the entry a fresh id, so a late ack from a worker whose lease expired cannot remove the
+/// redelivered copy another worker is holding.
 pub struct RedisTaskQueue {
-    client: redis::Client,
+    connections: RedisConnections,
     queue_key: String,
+    visibility_timeout: Duration,
+    last_reap: Mutex<Option<Instant>>,
+    dead_letters: RedisDeadLetters,
+}
+
+/// A serialized task under an id unique to one delivery of it.
+#[derive(Serialize, Deserialize)]
+struct QueueEntry {
+    id: String,
+    task: String,
 }
 
+impl QueueEntry {
+    fn serialize(task: &Task) -> Result<String> {
+        let entry = QueueEntry { id: Uuid::new_v4().to_string(), task: serde_json::to_string(task)? };
+        Ok(serde_json::to_string(&entry)?)
+    }
+}
+
+/// Lua helper shared by the scripts that re-queue an entry: same task, new delivery id.
+const REQUEUE_ENTRY: &str = r#"
+    local function requeue(queue, entry, id)
+        local envelope = cjson.decode(entry)
+        envelope.id = id
+        redis.call("LPUSH", queue, cjson.encode(envelope))
+    end
+"#;
+
 impl RedisTaskQueue {
     pub fn new(client: redis::Client, queue_key: String) -> Self {
+        Self::with_connections(RedisConnections::new(client), queue_key)
+    }
+
+    pub fn with_connections(connections: RedisConnections, queue_key: String) -> Self {
+        let dead_letters = RedisDeadLetters::new(connections.clone(), format!("{}:dead", queue_key));
         Self {
-            client,
+            connections,
             queue_key,
+            visibility_timeout: DEFAULT_VISIBILITY_TIMEOUT,
+            last_reap: Mutex::new(None),
+            dead_letters,
+        }
+    }
+
+    /// Sets how long a worker may hold a task without acking it. Should exceed the longest node timeout.
+    pub fn with_visibility_timeout(mut self, visibility_timeout: Duration) -> Self {
+        self.visibility_timeout = visibility_timeout;
+        self
+    }
+
+    fn processing_key(&self) -> String {
+        format!("{}:processing", self.queue_key)
+    }
+
+    fn lease_key(&self) -> String {
+        format!("{}:leases", self.queue_key)
+    }
+
+    fn deliveries_key(&self) -> String {
+        format!("{}:deliveries", self.queue_key)
+    }
+
+    /// Re-queues every entry whose lease expired. Entries found in the processing list without
+    /// a lease (the popping worker died before recording it) are given one first.
+    /// Returns the number of re-queued entries.
+    pub async fn reap_expired(&self) -> Result<usize> {
+        let mut conn = self.connections.get().await?;
+        let script = redis::Script::new(&format!("{}{}", REQUEUE_ENTRY, r#"
+            local now = tonumber(ARGV[1])
+            local requeued = 0
+            for i, id in ipairs(redis.call("ZRANGEBYSCORE", KEYS[3], "-inf", now)) do
+                local entry = redis.call("HGET", KEYS[4], id)
+                redis.call("ZREM", KEYS[3], id)
+                redis.call("HDEL", KEYS[4], id)
+                if entry and redis.call("LREM", KEYS[2], 1, entry) > 0 then
+                    requeue(KEYS[1], entry, ARGV[3] .. "-" .. i)
+                    requeued = requeued + 1
+                end
+            end
+            for _, entry in ipairs(redis.call("LRANGE", KEYS[2], 0, -1)) do
+                -- Entries that are not envelopes are being dead-lettered by `pop`
+                local ok, envelope = pcall(cjson.decode, entry)
+                if ok and type(envelope) == "table" and type(envelope.id) == "string"
+                    and not redis.call("ZSCORE", KEYS[3], envelope.id) then
+                    redis.call("HSET", KEYS[4], envelope.id, entry)
+                    redis.call("ZADD", KEYS[3], now + tonumber(ARGV[2]), envelope.id)
+                end
+            end
+            return requeued
+        "#));
+        let requeued: usize = script
+            .key(&self.queue_key)
+            .key(self.processing_key())
+            .key(self.lease_key())
+            .key(self.deliveries_key())
+            .arg(unix_millis())
+            .arg(self.visibility_timeout.as_millis() as u64)
+            .arg(Uuid::new_v4().to_string())
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(requeued)
+    }
+
+    async fn reap_if_due(&self) {
+        {
+            let mut last_reap = self.last_reap.lock().unwrap();
+            if last_reap.is_some_and(|at| at.elapsed() < REAP_INTERVAL) {
+                return;
+            }
+            *last_reap = Some(Instant::now());
+        }
+        match self.reap_expired().await {
+            Ok(0) => {}
+            Ok(n) => warn!(queue = %self.queue_key, requeued = n, "Re-queued tasks with expired leases"),
+            Err(e) => error!(queue = %self.queue_key, error = ?e, "Failed to reap expired leases"),
         }
     }
+
+    /// Dead-letters a payload that never parses, since redelivering it would loop forever.
+    async fn discard(&self, payload: String, error: serde_json::Error) -> Result<()> {
+        warn!(queue = %self.queue_key, error = %error, "Dead-lettering undeserializable task");
+        let letter = DeadLetter::new(payload, format!("Failed to deserialize task: {}", error), None);
+        self.dead_letter(letter).await
+    }
 }
 
 #[async_trait]
 impl TaskQueue for RedisTaskQueue {
     async fn push(&self, task: Task) -> Result<()> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
-        let serialized = serde_json::to_string(&task)?;
-        let _: () = conn.lpush(&self.queue_key, serialized).await?;
+        let mut conn = self.connections.get().await?;
+        let _: () = conn.lpush(&self.queue_key, QueueEntry::serialize(&task)?).await?;
         Ok(())
     }
 
-    async fn pop(&self) -> Result<Option<Task>> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
-        // BRPOP returns (key, value). We use timeout 0 for infinite block?
-        // Or maybe better to use a reasonable timeout to allow shutdown/checking?
-        // Let's use 1 second timeout for now to stay responsive.
-        let result: Option<(String, String)> = conn.brpop(&self.queue_key, 1.0).await?;
-        
-        if let Some((_, task_json)) = result {
-             let task = serde_json::from_str(&task_json)?;
-             Ok(Some(task))
+    async fn pop(&self) -> Result<Option<Delivery>> {
+        self.reap_if_due().await;
+
+        let mut blocking = self.connections.checkout().await?;
+        // Block for at most 1 second to stay responsive
+        let result: Option<String> = blocking
+            .blmove(&self.queue_key, self.processing_key(), Direction::Right, Direction::Left, 1.0)
+            .await?;
+        self.connections.checkin(blocking);
+        let Some(raw) = result else {
+            return Ok(None);
+        };
+
+        let mut conn = self.connections.get().await?;
+        let entry: QueueEntry = match serde_json::from_str(&raw) {
+            Ok(entry) => entry,
+            Err(e) => {
+                self.discard(raw.clone(), e).await?;
+                let _: () = conn.lrem(self.processing_key(), 1, &raw).await?;
+                return Ok(None);
+            }
+        };
+
+        let expires_at = unix_millis() + self.visibility_timeout.as_millis() as u64;
+        let _: () = redis::pipe()
+            .atomic()
+            .hset(self.deliveries_key(), &entry.id, &raw).ignore()
+            .zadd(self.lease_key(), &entry.id, expires_at).ignore()
+            .query_async(&mut conn)
+            .await?;
+
+        match serde_json::from_str(&entry.task) {
+            Ok(task) => Ok(Some(Delivery { task, receipt: entry.id })),
+            Err(e) => {
+                self.discard(entry.task, e).await?;
+                self.ack(&entry.id).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn ack(&self, receipt: &str) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        let script = redis::Script::new(r#"
+            local entry = redis.call("HGET", KEYS[3], ARGV[1])
+            redis.call("ZREM", KEYS[2], ARGV[1])
+            redis.call("HDEL", KEYS[3], ARGV[1])
+            if entry then
+                redis.call("LREM", KEYS[1], 1, entry)
+            end
+            return 0
+        "#);
+        let _: i32 = script
+            .key(self.processing_key())
+            .key(self.lease_key())
+            .key(self.deliveries_key())
+            .arg(receipt)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn nack(&self, receipt: &str) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        // Only re-queue if the delivery is still leased, so a reaped task is not duplicated
+        let script = redis::Script::new(&format!("{}{}", REQUEUE_ENTRY, r#"
+            local entry = redis.call("HGET", KEYS[4], ARGV[1])
+            redis.call("ZREM", KEYS[3], ARGV[1])
+            redis.call("HDEL", KEYS[4], ARGV[1])
+            if entry and redis.call("LREM", KEYS[2], 1, entry) > 0 then
+                requeue(KEYS[1], entry, ARGV[2])
+                return 1
+            end
+            return 0
+        "#));
+        let _: i32 = script
+            .key(&self.queue_key)
+            .key(self.processing_key())
+            .key(self.lease_key())
+            .key(self.deliveries_key())
+            .arg(receipt)
+            .arg(Uuid::new_v4().to_string())
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn dead_letter(&self, letter: DeadLetter) -> Result<()> {
+        self.dead_letters.add(letter).await
+    }
+
+    async fn dead_letters(&self) -> Result<Vec<DeadLetter>> {
+        self.dead_letters.list().await
+    }
+
+    async fn get_dead_letter(&self, id: Uuid) -> Result<Option<DeadLetter>> {
+        self.dead_letters.get(id).await
+    }
+
+    async fn remove_dead_letter(&self, id: Uuid) -> Result<bool> {
+        self.dead_letters.remove(id).await
+    }
+
+    async fn purge_dead_letters(&self) -> Result<usize> {
+        self.dead_letters.purge().await
+    }
+}
+
+/// Dead letters of a Redis queue, kept in a hash of serialized letters by id.
+pub(crate) struct RedisDeadLetters {
+    connections: RedisConnections,
+    key: String,
+}
+
+impl RedisDeadLetters {
+    pub(crate) fn new(connections: RedisConnections, key: String) -> Self {
+        Self { connections, key }
+    }
+
+    pub(crate) async fn add(&self, letter: DeadLetter) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        let serialized = serde_json::to_string(&letter)?;
+        let _: () = conn.hset(&self.key, letter.id.to_string(), serialized).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn list(&self) -> Result<Vec<DeadLetter>> {
+        let mut conn = self.connections.get().await?;
+        let values: Vec<String> = conn.hvals(&self.key).await?;
+        let mut letters = values.iter()
+            .map(|v| serde_json::from_str(v))
+            .collect::<serde_json::Result<Vec<DeadLetter>>>()?;
+        letters.sort_by_key(|l| l.failed_at);
+        Ok(letters)
+    }
+
+    pub(crate) async fn get(&self, id: Uuid) -> Result<Option<DeadLetter>> {
+        let mut conn = self.connections.get().await?;
+        let value: Option<String> = conn.hget(&self.key, id.to_string()).await?;
+        Ok(value.map(|v| serde_json::from_str(&v)).transpose()?)
+    }
+
+    pub(crate) async fn remove(&self, id: Uuid) -> Result<bool> {
+        let mut conn = self.connections.get().await?;
+        let removed: i32 = conn.hdel(&self.key, id.to_string()).await?;
+        Ok(removed > 0)
+    }
+
+    pub(crate) async fn purge(&self) -> Result<usize> {
+        let mut conn = self.connections.get().await?;
+        let (count, _): (usize, i32) = redis::pipe()
+            .atomic()
+            .hlen(&self.key)
+            .del(&self.key)
+            .query_async(&mut conn)
+            .await?;
+        Ok(count)
+    }
+}
+
+fn var_key(instance_id: Uuid) -> String {
+    format!("skript:inst:{}:vars", instance_id)
+}
+
+/// Ids of the instances running on one version of a blueprint.
+fn blueprint_runs_key(versioned_key: &str) -> String {
+    format!("skript:blueprints:runs:{}", versioned_key)
+}
+
+fn compensation_key(instance_id: Uuid) -> String {
+    format!("skript:inst:{}:compensations", instance_id)
+}
+
+/// Sets of the branches waiting at a Join node and of those of its completed rounds.
+fn join_keys(instance_id: Uuid, node_index: usize) -> (String, String) {
+    let arrived = format!("skript:inst:{}:joins:{}", instance_id, node_index);
+    let completed = format!("{}:completed", arrived);
+    (arrived, completed)
+}
+
+/// Records a join arrival and, if it completes the join, queues the tasks given after it.
+/// KEYS: arrived set, completed set, queue. ARGV: token, expect_count, stream field
+/// (empty for a list queue), tasks. Returns 1 if the join completed.
+const JOIN_ARRIVAL_SCRIPT: &str = r#"
+    if redis.call("SISMEMBER", KEYS[2], ARGV[1]) == 1 then
+        return 0
+    end
+    redis.call("SADD", KEYS[1], ARGV[1])
+    if redis.call("SCARD", KEYS[1]) < tonumber(ARGV[2]) then
+        return 0
+    end
+    redis.call("SUNIONSTORE", KEYS[2], KEYS[2], KEYS[1])
+    redis.call("DEL", KEYS[1])
+    for i = 4, #ARGV do
+        if ARGV[3] == "" then
+            redis.call("LPUSH", KEYS[3], ARGV[i])
+        else
+            redis.call("XADD", KEYS[3], "*", ARGV[3], ARGV[i])
+        end
+    end
+    return 1
+"#;
+
+fn serialize_vars(vars: impl IntoIterator<Item = (String, Value)>) -> Result<Vec<(String, String)>> {
+    vars.into_iter()
+        .map(|(k, v)| Ok((k, serde_json::to_string(&v)?)))
+        .collect()
+}
+
+/// Serialized blueprints in one hash by `id@version`, and the latest version of each
+/// blueprint in a second hash by id. Old versions are deleted by the engine once drained,
+/// see [`BlueprintStore::save_blueprint`].
+pub struct RedisBlueprintStore {
+    connections: RedisConnections,
+    key: String,
+    latest_key: String,
+}
+
+impl RedisBlueprintStore {
+    pub fn new(client: redis::Client) -> Self {
+        Self::with_connections(RedisConnections::new(client))
+    }
+
+    pub fn with_connections(connections: RedisConnections) -> Self {
+        Self {
+            connections,
+            key: "skript:blueprints".to_string(),
+            latest_key: "skript:blueprints:latest".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl BlueprintStore for RedisBlueprintStore {
+    async fn save_blueprint(&self, blueprint: &Blueprint) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        let serialized = serde_json::to_string(blueprint)?;
+        let _: () = redis::pipe()
+            .atomic()
+            .hset(&self.key, versioned_blueprint_key(&blueprint.id, &blueprint.version), serialized).ignore()
+            .hset(&self.latest_key, &blueprint.id, &blueprint.version).ignore()
+            .query_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_blueprint(&self, id: &str, version: Option<&str>) -> Result<Option<Blueprint>> {
+        let mut conn = self.connections.get().await?;
+        let version = match version {
+            Some(version) => version.to_string(),
+            None => match conn.hget::<_, _, Option<String>>(&self.latest_key, id).await? {
+                Some(latest) => latest,
+                None => return Ok(None),
+            },
+        };
+        let value: Option<String> = conn.hget(&self.key, versioned_blueprint_key(id, &version)).await?;
+        Ok(value.map(|v| serde_json::from_str(&v)).transpose()?)
+    }
+
+    async fn remove_blueprint(&self, id: &str, version: &str) -> Result<bool> {
+        // Checked in the script, so a concurrent save cannot make the version latest in between
+        let script = redis::Script::new(r#"
+            if redis.call("HGET", KEYS[2], ARGV[1]) == ARGV[2] then
+                return 0
+            end
+            return redis.call("HDEL", KEYS[1], ARGV[3])
+        "#);
+        let mut conn = self.connections.get().await?;
+        let removed: i32 = script.key(&self.key)
+            .key(&self.latest_key)
+            .arg(id)
+            .arg(version)
+            .arg(versioned_blueprint_key(id, version))
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(removed == 1)
+    }
+}
+
+/// Commits a task's variables, compensation record and follow-up tasks in a single
+/// `MULTI` round-trip. The store and queue must live on the same Redis.
+pub struct RedisCommitter {
+    connections: RedisConnections,
+    queue: CommitQueue,
+}
+
+enum CommitQueue {
+    List(String),
+    Stream(String),
+}
+
+impl RedisCommitter {
+    pub fn new(store: &RedisStateStore, queue: &RedisTaskQueue) -> Self {
+        Self { connections: store.connections.clone(), queue: CommitQueue::List(queue.queue_key.clone()) }
+    }
+
+    pub fn for_stream(store: &RedisStateStore, queue: &RedisStreamTaskQueue) -> Self {
+        Self { connections: store.connections.clone(), queue: CommitQueue::Stream(queue.stream_key().to_string()) }
+    }
+}
+
+#[async_trait]
+impl TaskCommitter for RedisCommitter {
+    async fn commit(&self, commit: TaskCommit) -> Result<()> {
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        if !commit.vars.is_empty() {
+            pipe.hset_multiple(var_key(commit.instance_id), &serialize_vars(commit.vars)?).ignore();
+        }
+        if let Some(compensation) = commit.compensation {
+            pipe.lpush(compensation_key(commit.instance_id), compensation).ignore();
+        }
+        let (queue_key, stream_field) = match &self.queue {
+            CommitQueue::List(key) => (key, ""),
+            CommitQueue::Stream(key) => (key, TASK_FIELD),
+        };
+        let tasks = commit.tasks.iter()
+            .map(|task| match &self.queue {
+                CommitQueue::List(_) => QueueEntry::serialize(task),
+                CommitQueue::Stream(_) => Ok(serde_json::to_string(task)?),
+            })
+            .collect::<Result<Vec<String>>>()?;
+        if let Some(join) = &commit.join {
+            // Evaluated inside the transaction, so the arrival and its tasks are applied together
+            let (arrived, completed) = join_keys(commit.instance_id, join.node_index);
+            pipe.cmd("EVAL").arg(JOIN_ARRIVAL_SCRIPT).arg(3)
+                .arg(arrived).arg(completed).arg(queue_key)
+                .arg(join.token_id.to_string()).arg(join.expect_count).arg(stream_field).arg(&tasks)
+                .ignore();
         } else {
-             Ok(None)
+            for task in tasks {
+                match &self.queue {
+                    CommitQueue::List(key) => pipe.lpush(key, task).ignore(),
+                    CommitQueue::Stream(key) => pipe.xadd(key, "*", &[(TASK_FIELD, task)]).ignore(),
+                };
+            }
         }
+        let mut conn = self.connections.get().await?;
+        let _: () = pipe.query_async(&mut conn).await?;
+        Ok(())
     }
 }
 
 pub struct RedisStateStore {
-    client: redis::Client,
+    connections: RedisConnections,
 }
 
 impl RedisStateStore {
     pub fn new(client: redis::Client) -> Self {
-        Self { client }
+        Self::with_connections(RedisConnections::new(client))
+    }
+
+    pub fn with_connections(connections: RedisConnections) -> Self {
+        Self { connections }
     }
 
     fn var_key(&self, instance_id: Uuid) -> String {
-        format!("skript:inst:{}:vars", instance_id)
+        var_key(instance_id)
     }
     
-    fn join_key(&self, instance_id: Uuid) -> String {
-        format!("skript:inst:{}:joins", instance_id)
+    fn meta_key(&self, instance_id: Uuid) -> String {
+        format!("skript:inst:{}:meta", instance_id)
+    }
+
+    fn compensation_key(&self, instance_id: Uuid) -> String {
+        compensation_key(instance_id)
+    }
+
+    fn parked_key(&self, instance_id: Uuid) -> String {
+        format!("skript:inst:{}:parked", instance_id)
     }
 }
 
 #[async_trait]
 impl StateStore for RedisStateStore {
     async fn get_var(&self, instance_id: Uuid, key: &str) -> Result<Option<Value>> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let mut conn = self.connections.get().await?;
         let val_str: Option<String> = conn.hget(self.var_key(instance_id), key).await?;
         
         if let Some(s) = val_str {
@@ -79,14 +553,44 @@ impl StateStore for RedisStateStore {
     }
 
     async fn set_var(&self, instance_id: Uuid, key: &str, value: Value) -> Result<()> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let mut conn = self.connections.get().await?;
         let val_str = serde_json::to_string(&value)?;
         let _: () = conn.hset(self.var_key(instance_id), key, val_str).await?;
         Ok(())
     }
 
+    async fn get_vars(&self, instance_id: Uuid, keys: &[String]) -> Result<HashMap<String, Value>> {
+        if keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let mut conn = self.connections.get().await?;
+        let values: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(self.var_key(instance_id))
+            .arg(keys)
+            .query_async(&mut conn)
+            .await?;
+
+        let mut result = HashMap::new();
+        for (key, value) in keys.iter().zip(values) {
+            if let Some(s) = value {
+                result.insert(key.clone(), serde_json::from_str(&s)?);
+            }
+        }
+        Ok(result)
+    }
+
+    async fn set_vars(&self, instance_id: Uuid, vars: Vec<(String, Value)>) -> Result<()> {
+        if vars.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.connections.get().await?;
+        let items = serialize_vars(vars)?;
+        let _: () = conn.hset_multiple(self.var_key(instance_id), &items).await?;
+        Ok(())
+    }
+
     async fn init_instance(&self, instance_id: Uuid, initial_vars: HashMap<String, Value>) -> Result<()> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let mut conn = self.connections.get().await?;
         let key = self.var_key(instance_id);
         
         // Optim: Use pipeline or just loop? Pipeline is better but hset_multiple might work if we flatten.
@@ -103,7 +607,7 @@ impl StateStore for RedisStateStore {
     }
     
     async fn get_all_vars(&self, instance_id: Uuid) -> Result<HashMap<String, Value>> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let mut conn = self.connections.get().await?;
         let raw_map: HashMap<String, String> = conn.hgetall(self.var_key(instance_id)).await?;
         
         let mut result = HashMap::new();
@@ -115,58 +619,180 @@ impl StateStore for RedisStateStore {
         Ok(result)
     }
 
-    async fn decrement_join_count(&self, instance_id: Uuid, node_index: usize, initial_count: usize) -> Result<usize> {
-        // LUA SCRIPT for atomicity
-        // ARGV[1] = initial_count
-        // KEYS[1] = join_key (Hash)
-        // ARGV[2] = node_index (Field)
-        
+    async fn record_join_arrival(&self, instance_id: Uuid, arrival: &JoinArrival) -> Result<bool> {
+        let mut conn = self.connections.get().await?;
+        let (arrived, completed) = join_keys(instance_id, arrival.node_index);
+        // No tasks: the caller queues them itself if the join completed
+        let completed: i32 = redis::Script::new(JOIN_ARRIVAL_SCRIPT)
+            .key(arrived)
+            .key(completed)
+            .key("")
+            .arg(arrival.token_id.to_string())
+            .arg(arrival.expect_count)
+            .arg("")
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(completed == 1)
+    }
+
+    async fn set_status(&self, instance_id: Uuid, status: InstanceStatus) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        let _: () = conn.hset(self.meta_key(instance_id), "status", status.as_str()).await?;
+        Ok(())
+    }
+
+    async fn get_status(&self, instance_id: Uuid) -> Result<Option<InstanceStatus>> {
+        let mut conn = self.connections.get().await?;
+        let status: Option<String> = conn.hget(self.meta_key(instance_id), "status").await?;
+        status.map(|s| s.parse()).transpose()
+    }
+
+    async fn transition_status(&self, instance_id: Uuid, status: InstanceStatus) -> Result<bool> {
+        // ARGV[1] = new status, ARGV[2..] = terminal statuses
         let script = redis::Script::new(r#"
-            local key = KEYS[1]
-            local field = ARGV[2]
-            local init = tonumber(ARGV[1])
-            
-            -- Check if exists
-            local current = redis.call("HGET", key, field)
-            if current == false then
-                -- Initialize if not exists. 
-                -- Wait, if not exists, we assume it's the first thread arriving?
-                -- But if we just HINCRBY -1, it starts from 0 -> -1?
-                -- We need to initialize to 'initial_count' first?
-                
-                -- Logic:
-                -- If not exists, set to initial_count - 1.
-                -- If exists, decrement.
-                
-                local val = init - 1
-                if val == 0 then
-                    return 0 -- Already done? Should not happen if init >= 1
-                else
-                    redis.call("HSET", key, field, val)
-                    return val
-                end
-            else
-                local val = tonumber(current) - 1
-                if val <= 0 then
-                    redis.call("HDEL", key, field)
+            local current = redis.call("HGET", KEYS[1], "status")
+            if current == ARGV[1] then
+                return 0
+            end
+            for i = 2, #ARGV do
+                if current == ARGV[i] then
                     return 0
-                else
-                    redis.call("HSET", key, field, val)
-                    return val
                 end
             end
+            redis.call("HSET", KEYS[1], "status", ARGV[1])
+            return 1
         "#);
-        
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
-        let key = self.join_key(instance_id);
-        
-        let new_val: usize = script
-            .key(key)
-            .arg(initial_count)
-            .arg(node_index)
+
+        let mut conn = self.connections.get().await?;
+        let mut invocation = script.key(self.meta_key(instance_id));
+        invocation.arg(status.as_str());
+        for terminal in InstanceStatus::TERMINAL {
+            invocation.arg(terminal.as_str());
+        }
+        let applied: i32 = invocation.invoke_async(&mut conn).await?;
+        Ok(applied == 1)
+    }
+
+    async fn set_blueprint_version(&self, instance_id: Uuid, version: &str) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        let _: () = conn.hset(self.meta_key(instance_id), "blueprint_version", version).await?;
+        Ok(())
+    }
+
+    async fn get_blueprint_version(&self, instance_id: Uuid) -> Result<Option<String>> {
+        let mut conn = self.connections.get().await?;
+        Ok(conn.hget(self.meta_key(instance_id), "blueprint_version").await?)
+    }
+
+    async fn track_blueprint_run(&self, instance_id: Uuid, key: &str) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        let _: () = redis::pipe()
+            .atomic()
+            .sadd(blueprint_runs_key(key), instance_id.to_string()).ignore()
+            .hset(self.meta_key(instance_id), "blueprint_run", key).ignore()
+            .query_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn finish_blueprint_run(&self, instance_id: Uuid) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        let Some(key) = conn.hget::<_, _, Option<String>>(self.meta_key(instance_id), "blueprint_run").await? else {
+            return Ok(());
+        };
+        // ARGV[1] = version key the run was read under, ARGV[2] = instance id
+        let script = redis::Script::new(r#"
+            if redis.call("HGET", KEYS[1], "blueprint_run") ~= ARGV[1] then
+                return 0
+            end
+            redis.call("HDEL", KEYS[1], "blueprint_run")
+            redis.call("SREM", KEYS[2], ARGV[2])
+            return 1
+        "#);
+        let _: i32 = script.key(self.meta_key(instance_id))
+            .key(blueprint_runs_key(&key))
+            .arg(&key)
+            .arg(instance_id.to_string())
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn blueprint_runs(&self, key: &str) -> Result<usize> {
+        let mut conn = self.connections.get().await?;
+        Ok(conn.scard(blueprint_runs_key(key)).await?)
+    }
+
+    async fn record_failure(&self, instance_id: Uuid, failure: InstanceFailure) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        let failure_str = serde_json::to_string(&failure)?;
+        let _: () = conn.hset(self.meta_key(instance_id), "failure", failure_str).await?;
+        Ok(())
+    }
+
+    async fn get_failure(&self, instance_id: Uuid) -> Result<Option<InstanceFailure>> {
+        let mut conn = self.connections.get().await?;
+        let failure_str: Option<String> = conn.hget(self.meta_key(instance_id), "failure").await?;
+        failure_str.map(|s| serde_json::from_str(&s).map_err(Into::into)).transpose()
+    }
+
+    async fn reopen_failed(&self, instance_id: Uuid) -> Result<bool> {
+        // ARGV[1] = Failed, ARGV[2] = Running
+        let script = redis::Script::new(r#"
+            if redis.call("HGET", KEYS[1], "status") ~= ARGV[1] then
+                return 0
+            end
+            redis.call("HSET", KEYS[1], "status", ARGV[2])
+            redis.call("HDEL", KEYS[1], "failure")
+            return 1
+        "#);
+
+        let mut conn = self.connections.get().await?;
+        let reopened: i32 = script.key(self.meta_key(instance_id))
+            .arg(InstanceStatus::Failed.as_str())
+            .arg(InstanceStatus::Running.as_str())
             .invoke_async(&mut conn)
             .await?;
-            
-        Ok(new_val)
+        Ok(reopened == 1)
+    }
+
+    async fn push_compensation(&self, instance_id: Uuid, node_index: usize) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        let _: () = conn.lpush(self.compensation_key(instance_id), node_index).await?;
+        Ok(())
+    }
+
+    async fn peek_compensation(&self, instance_id: Uuid) -> Result<Option<usize>> {
+        let mut conn = self.connections.get().await?;
+        let top: Option<usize> = conn.lindex(self.compensation_key(instance_id), 0).await?;
+        Ok(top)
+    }
+
+    async fn pop_compensation(&self, instance_id: Uuid) -> Result<Option<usize>> {
+        let mut conn = self.connections.get().await?;
+        let top: Option<usize> = conn.lpop(self.compensation_key(instance_id), None).await?;
+        Ok(top)
+    }
+
+    async fn park_task(&self, task: Task) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        let serialized = serde_json::to_string(&task)?;
+        let _: () = conn.rpush(self.parked_key(task.instance_id), serialized).await?;
+        Ok(())
+    }
+
+    async fn take_parked_tasks(&self, instance_id: Uuid) -> Result<Vec<Task>> {
+        let mut conn = self.connections.get().await?;
+        let key = self.parked_key(instance_id);
+        // MULTI/EXEC so a concurrent park is either taken now or left for the next call
+        let (tasks, _): (Vec<String>, i32) = redis::pipe()
+            .atomic()
+            .lrange(&key, 0, -1)
+            .del(&key)
+            .query_async(&mut conn)
+            .await?;
+        tasks.iter()
+            .map(|t| serde_json::from_str(t).map_err(Into::into))
+            .collect()
     }
 }
@@ -0,0 +1,278 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use crate::runtime::task::Task;
+use crate::runtime::storage::{DeadLetter, Delivery, TaskQueue};
+use crate::runtime::redis_connection::RedisConnections;
+use crate::runtime::redis_storage::{RedisDeadLetters, DEFAULT_VISIBILITY_TIMEOUT, REAP_INTERVAL};
+use anyhow::Result;
+use redis::AsyncCommands;
+use redis::streams::{StreamAutoClaimOptions, StreamAutoClaimReply, StreamId, StreamReadOptions, StreamReadReply};
+use serde::Serialize;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+
+/// Stream entry field holding the serialized task.
+pub(crate) const TASK_FIELD: &str = "task";
+/// How long `XREADGROUP` blocks before `pop` reports the queue idle.
+const READ_BLOCK_MS: usize = 1000;
+
+/// Task queue on a Redis Stream with a consumer group.
+///
+/// Every delivered entry stays in the consumer's pending entry list (PEL) until it is acked.
+/// Entries idle in some consumer's PEL for longer than the visibility timeout (the consumer
+/// stalled or died) are taken over with `XAUTOCLAIM`, which `pop` runs every `REAP_INTERVAL`.
+///
+/// The receipt of a delivery is its stream entry id.
+pub struct RedisStreamTaskQueue {
+    connections: RedisConnections,
+    stream_key: String,
+    group: String,
+    consumer: String,
+    visibility_timeout: Duration,
+    group_ready: AtomicBool,
+    last_claim: Mutex<Option<Instant>>,
+    dead_letters: RedisDeadLetters,
+}
+
+/// Queue state as reported by `XINFO`.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamQueueInfo {
+    /// Entries in the stream, delivered or not
+    pub length: usize,
+    /// Entries delivered but not acked yet
+    pub pending: usize,
+    /// Entries not delivered to any consumer yet, if Redis can tell
+    pub lag: Option<usize>,
+    pub consumers: Vec<StreamConsumerInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamConsumerInfo {
+    pub name: String,
+    pub pending: usize,
+    /// Milliseconds since the consumer last interacted with the stream
+    pub idle: usize,
+}
+
+impl RedisStreamTaskQueue {
+    /// `consumer` must be unique per process: its PEL holds the tasks that process is running.
+    pub fn new(client: redis::Client, stream_key: String, group: String, consumer: String) -> Self {
+        Self::with_connections(RedisConnections::new(client), stream_key, group, consumer)
+    }
+
+    pub fn with_connections(connections: RedisConnections, stream_key: String, group: String, consumer: String) -> Self {
+        let dead_letters = RedisDeadLetters::new(connections.clone(), format!("{}:dead", stream_key));
+        Self {
+            connections,
+            stream_key,
+            group,
+            consumer,
+            visibility_timeout: DEFAULT_VISIBILITY_TIMEOUT,
+            group_ready: AtomicBool::new(false),
+            last_claim: Mutex::new(None),
+            dead_letters,
+        }
+    }
+
+    /// Sets how long an entry may stay unacked before another consumer claims it.
+    /// Should exceed the longest node timeout.
+    pub fn with_visibility_timeout(mut self, visibility_timeout: Duration) -> Self {
+        self.visibility_timeout = visibility_timeout;
+        self
+    }
+
+    pub(crate) fn stream_key(&self) -> &str {
+        &self.stream_key
+    }
+
+    pub async fn info(&self) -> Result<StreamQueueInfo> {
+        self.ensure_group().await?;
+        let mut conn = self.connections.get().await?;
+        let length: usize = conn.xlen(&self.stream_key).await?;
+        let groups: redis::streams::StreamInfoGroupsReply = conn.xinfo_groups(&self.stream_key).await?;
+        let group = groups.groups.into_iter().find(|g| g.name == self.group);
+        let consumers: redis::streams::StreamInfoConsumersReply = conn.xinfo_consumers(&self.stream_key, &self.group).await?;
+
+        Ok(StreamQueueInfo {
+            length,
+            pending: group.as_ref().map(|g| g.pending).unwrap_or(0),
+            lag: group.and_then(|g| g.lag),
+            consumers: consumers.consumers.into_iter()
+                .map(|c| StreamConsumerInfo { name: c.name, pending: c.pending, idle: c.idle })
+                .collect(),
+        })
+    }
+
+    /// Takes over one entry that stayed unacked for longer than the visibility timeout.
+    pub async fn claim_stalled(&self) -> Result<Option<Delivery>> {
+        self.ensure_group().await?;
+        let mut conn = self.connections.get().await?;
+        let reply: StreamAutoClaimReply = conn.xautoclaim_options(
+            &self.stream_key,
+            &self.group,
+            &self.consumer,
+            self.visibility_timeout.as_millis() as u64,
+            "0-0",
+            StreamAutoClaimOptions::default().count(1),
+        ).await?;
+
+        match reply.claimed.into_iter().next() {
+            Some(entry) => {
+                warn!(stream = %self.stream_key, entry = %entry.id, "Claimed stalled task");
+                self.deliver(entry).await
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn ensure_group(&self) -> Result<()> {
+        if self.group_ready.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        let mut conn = self.connections.get().await?;
+        // Start at the beginning, so tasks pushed before the first worker started are kept
+        let created: redis::RedisResult<()> = conn.xgroup_create_mkstream(&self.stream_key, &self.group, "0").await;
+        match created {
+            Ok(()) => {}
+            Err(e) if e.code() == Some("BUSYGROUP") => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.group_ready.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    fn claim_due(&self) -> bool {
+        let mut last_claim = self.last_claim.lock().unwrap();
+        if last_claim.is_some_and(|at| at.elapsed() < REAP_INTERVAL) {
+            return false;
+        }
+        *last_claim = Some(Instant::now());
+        true
+    }
+
+    async fn deliver(&self, entry: StreamId) -> Result<Option<Delivery>> {
+        let payload: Option<String> = entry.get(TASK_FIELD);
+        let parsed = payload.as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Stream entry has no `{}` field", TASK_FIELD))
+            .and_then(|p| Ok(serde_json::from_str::<Task>(p)?));
+
+        match parsed {
+            Ok(task) => Ok(Some(Delivery { task, receipt: entry.id })),
+            Err(e) => {
+                // Redelivering an entry that never parses would loop forever
+                warn!(stream = %self.stream_key, entry = %entry.id, error = %e, "Dead-lettering undeserializable task");
+                let letter = DeadLetter::new(payload.unwrap_or_default(), format!("Failed to deserialize task: {}", e), None);
+                self.dead_letter(letter).await?;
+                self.ack(&entry.id).await?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl TaskQueue for RedisStreamTaskQueue {
+    async fn push(&self, task: Task) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        let serialized = serde_json::to_string(&task)?;
+        let _: Option<String> = conn.xadd(&self.stream_key, "*", &[(TASK_FIELD, serialized)]).await?;
+        Ok(())
+    }
+
+    async fn pop(&self) -> Result<Option<Delivery>> {
+        self.ensure_group().await?;
+
+        if self.claim_due() {
+            match self.claim_stalled().await {
+                Ok(Some(delivery)) => {
+                    // There may be more stalled entries, look again on the next pop
+                    *self.last_claim.lock().unwrap() = None;
+                    return Ok(Some(delivery));
+                }
+                Ok(None) => {}
+                Err(e) => error!(stream = %self.stream_key, error = ?e, "Failed to claim stalled tasks"),
+            }
+        }
+
+        let mut blocking = self.connections.checkout().await?;
+        let options = StreamReadOptions::default()
+            .group(&self.group, &self.consumer)
+            .count(1)
+            .block(READ_BLOCK_MS);
+        let reply: Option<StreamReadReply> = match blocking.xread_options(&[&self.stream_key], &[">"], &options).await {
+            Ok(reply) => {
+                self.connections.checkin(blocking);
+                reply
+            }
+            Err(e) => {
+                if e.code() == Some("NOGROUP") {
+                    // The stream was deleted under us; recreate the group on the next pop
+                    self.group_ready.store(false, Ordering::Release);
+                }
+                return Err(e.into());
+            }
+        };
+
+        let entry = reply
+            .and_then(|r| r.keys.into_iter().next())
+            .and_then(|k| k.ids.into_iter().next());
+        match entry {
+            Some(entry) => self.deliver(entry).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn ack(&self, receipt: &str) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        let _: () = redis::pipe()
+            .atomic()
+            .xack(&self.stream_key, &self.group, &[receipt]).ignore()
+            .xdel(&self.stream_key, &[receipt]).ignore()
+            .query_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn nack(&self, receipt: &str) -> Result<()> {
+        let mut conn = self.connections.get().await?;
+        // Streams have no way to un-deliver an entry: add it again as a new one
+        let script = redis::Script::new(r#"
+            local entries = redis.call("XRANGE", KEYS[1], ARGV[2], ARGV[2])
+            if #entries == 0 or redis.call("XACK", KEYS[1], ARGV[1], ARGV[2]) == 0 then
+                return 0
+            end
+            redis.call("XDEL", KEYS[1], ARGV[2])
+            redis.call("XADD", KEYS[1], "*", unpack(entries[1][2]))
+            return 1
+        "#);
+        let _: i32 = script
+            .key(&self.stream_key)
+            .arg(&self.group)
+            .arg(receipt)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn dead_letter(&self, letter: DeadLetter) -> Result<()> {
+        self.dead_letters.add(letter).await
+    }
+
+    async fn dead_letters(&self) -> Result<Vec<DeadLetter>> {
+        self.dead_letters.list().await
+    }
+
+    async fn get_dead_letter(&self, id: Uuid) -> Result<Option<DeadLetter>> {
+        self.dead_letters.get(id).await
+    }
+
+    async fn remove_dead_letter(&self, id: Uuid) -> Result<bool> {
+        self.dead_letters.remove(id).await
+    }
+
+    async fn purge_dead_letters(&self) -> Result<usize> {
+        self.dead_letters.purge().await
+    }
+}
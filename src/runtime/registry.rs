@@ -0,0 +1,37 @@
+use crate::runtime::node::NodeDefinition;
+use crate::actions::FunctionHandler;
+use crate::nodes::function::FunctionNodeDefinition;
+use dashmap::DashMap;
+use std::sync::Arc;
+
+/// Node definitions by kind.
+///
+/// The engine prepares blueprints from it, and a compiler given the same registry rejects
+/// unknown kinds and invalid params at compile time instead of on the first worker.
+#[derive(Default)]
+pub struct NodeRegistry {
+    definitions: DashMap<String, Arc<dyn NodeDefinition>>,
+}
+
+impl NodeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the definition under its name, replacing any previous one.
+    pub fn register(&self, definition: Box<dyn NodeDefinition>) {
+        self.definitions.insert(definition.name().to_string(), Arc::from(definition));
+    }
+
+    pub fn register_function(&self, handler: Arc<dyn FunctionHandler>) {
+        self.register(Box::new(FunctionNodeDefinition { handler }));
+    }
+
+    pub fn get(&self, kind: &str) -> Option<Arc<dyn NodeDefinition>> {
+        self.definitions.get(kind).map(|d| d.value().clone())
+    }
+
+    pub fn contains(&self, kind: &str) -> bool {
+        self.definitions.contains_key(kind)
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Serialize, Deserialize};
+use std::time::Duration;
+use crate::runtime::error::ErrorKind;
+
+/// 节点重试策略 (指数退避)
+/// 所有时间单位均为毫秒
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// 总尝试次数 (包含第一次执行)
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// 第一次重试前的等待时间
+    #[serde(default = "default_initial_delay")]
+    pub initial_delay: u64,
+    /// 每次重试后等待时间的放大倍数
+    #[serde(default = "default_backoff_factor")]
+    pub backoff_factor: f64,
+    /// 等待时间上限
+    #[serde(default = "default_max_delay")]
+    pub max_delay: u64,
+    /// 仅对这些错误类型重试，为空表示任意错误
+    #[serde(default)]
+    pub retry_on: Vec<ErrorKind>,
+}
+
+fn default_max_attempts() -> u32 { 3 }
+fn default_initial_delay() -> u64 { 100 }
+fn default_backoff_factor() -> f64 { 2.0 }
+fn default_max_delay() -> u64 { 10_000 }
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            initial_delay: default_initial_delay(),
+            backoff_factor: default_backoff_factor(),
+            max_delay: default_max_delay(),
+            retry_on: Vec::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// `attempt` is the number of attempts already made (1 after the first failure).
+    pub fn should_retry(&self, attempt: u32, kind: ErrorKind) -> bool {
+        attempt < self.max_attempts && self.retries(kind)
+    }
+
+    /// Whether errors of this kind are retried at all.
+    pub fn retries(&self, kind: ErrorKind) -> bool {
+        self.retry_on.is_empty() || self.retry_on.contains(&kind)
+    }
+
+    /// Delay before the next attempt, given the number of attempts already made.
+    pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1) as i32;
+        let delay = self.initial_delay as f64 * self.backoff_factor.max(1.0).powi(exponent);
+        Duration::from_millis(delay.min(self.max_delay as f64) as u64)
+    }
+}
@@ -2,18 +2,73 @@ use async_trait::async_trait;
 use serde_json::Value;
 use uuid::Uuid;
 use crate::runtime::task::Task;
+use crate::runtime::blueprint::Blueprint;
+use crate::runtime::instance::{InstanceStatus, InstanceFailure, unix_millis};
 use anyhow::Result;
+use serde::{Serialize, Deserialize};
 use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use std::collections::HashSet;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::mpsc;
 
 // --- Interfaces ---
 
+/// A popped task, leased to the worker until it is acknowledged.
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    pub task: Task,
+    /// Opaque handle identifying this delivery in `ack`/`nack`
+    pub receipt: String,
+}
+
+/// A task that could not be processed, kept in the dead-letter queue for inspection and replay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: Uuid,
+    /// The task as it was queued. Kept raw, since it may not deserialize.
+    pub payload: String,
+    pub error: String,
+    /// DSL id of the failed node, when known
+    pub node_id: Option<String>,
+    /// Unix milliseconds
+    pub failed_at: u64,
+}
+
+impl DeadLetter {
+    pub fn new(payload: String, error: String, node_id: Option<String>) -> Self {
+        Self { id: Uuid::new_v4(), payload, error, node_id, failed_at: unix_millis() }
+    }
+
+    pub fn for_task(task: &Task, node_id: &str, error: &anyhow::Error) -> Result<Self> {
+        Ok(Self::new(serde_json::to_string(task)?, error.to_string(), Some(node_id.to_string())))
+    }
+
+    pub fn task(&self) -> Result<Task> {
+        Ok(serde_json::from_str(&self.payload)?)
+    }
+}
+
+/// Delivers each task at least once: a popped task stays leased until it is acked,
+/// and a lease that is never acked (e.g. the worker crashed) eventually expires.
 #[async_trait]
 pub trait TaskQueue: Send + Sync {
     async fn push(&self, task: Task) -> Result<()>;
-    async fn pop(&self) -> Result<Option<Task>>;
+    /// Returns `None` when no task arrived within the queue's poll interval.
+    async fn pop(&self) -> Result<Option<Delivery>>;
+    /// Marks a delivery as done. Call only after the task's follow-up tasks were pushed.
+    async fn ack(&self, receipt: &str) -> Result<()>;
+    /// Releases a delivery without processing it, making the task available again.
+    async fn nack(&self, receipt: &str) -> Result<()>;
+
+    async fn dead_letter(&self, letter: DeadLetter) -> Result<()>;
+    /// All dead letters, oldest first.
+    async fn dead_letters(&self) -> Result<Vec<DeadLetter>>;
+    async fn get_dead_letter(&self, id: Uuid) -> Result<Option<DeadLetter>>;
+    /// Returns whether the letter existed.
+    async fn remove_dead_letter(&self, id: Uuid) -> Result<bool>;
+    /// Removes all dead letters, returning how many there were.
+    async fn purge_dead_letters(&self) -> Result<usize>;
 }
 
 #[async_trait]
@@ -21,13 +76,146 @@ pub trait StateStore: Send + Sync {
     async fn get_var(&self, instance_id: Uuid, key: &str) -> Result<Option<Value>>;
     async fn set_var(&self, instance_id: Uuid, key: &str, value: Value) -> Result<()>;
     async fn init_instance(&self, instance_id: Uuid, initial_vars: std::collections::HashMap<String, Value>) -> Result<()>;
+    /// Fetches several variables in one call; missing ones are left out of the map.
+    async fn get_vars(&self, instance_id: Uuid, keys: &[String]) -> Result<std::collections::HashMap<String, Value>> {
+        let mut vars = std::collections::HashMap::new();
+        for key in keys {
+            if let Some(value) = self.get_var(instance_id, key).await? {
+                vars.insert(key.clone(), value);
+            }
+        }
+        Ok(vars)
+    }
+    async fn set_vars(&self, instance_id: Uuid, vars: Vec<(String, Value)>) -> Result<()> {
+        for (key, value) in vars {
+            self.set_var(instance_id, &key, value).await?;
+        }
+        Ok(())
+    }
     /// Used for iterating all variables (e.g. for expression evaluation)
     /// Note: This might be expensive in remote implementations.
     async fn get_all_vars(&self, instance_id: Uuid) -> Result<std::collections::HashMap<String, Value>>;
     
-    /// Atomically decrement a join counter.
-    /// Returns the NEW value after decrement.
-    async fn decrement_join_count(&self, instance_id: Uuid, node_index: usize, initial_count: usize) -> Result<usize>;
+    /// Atomically records a branch arriving at a Join node. Returns true only for the arrival
+    /// that completes the join; repeated arrivals of the same branch are ignored.
+    async fn record_join_arrival(&self, instance_id: Uuid, arrival: &JoinArrival) -> Result<bool>;
+
+    /// Unconditionally overwrite the lifecycle status of an instance.
+    async fn set_status(&self, instance_id: Uuid, status: InstanceStatus) -> Result<()>;
+    async fn get_status(&self, instance_id: Uuid) -> Result<Option<InstanceStatus>>;
+
+    /// Atomically move the instance to `status` unless it already reached a terminal state
+    /// or is already in `status`. Returns whether the transition was applied.
+    async fn transition_status(&self, instance_id: Uuid, status: InstanceStatus) -> Result<bool>;
+
+    /// The blueprint version an instance was started with.
+    async fn set_blueprint_version(&self, instance_id: Uuid, version: &str) -> Result<()>;
+    async fn get_blueprint_version(&self, instance_id: Uuid) -> Result<Option<String>>;
+
+    /// Counts the instance as running on a blueprint version (`id@version`) until
+    /// `finish_blueprint_run`, so the version is not evicted while the instance needs it.
+    async fn track_blueprint_run(&self, instance_id: Uuid, key: &str) -> Result<()>;
+    /// Stops counting the instance on its version. Does nothing if it is not counted.
+    async fn finish_blueprint_run(&self, instance_id: Uuid) -> Result<()>;
+    /// Number of instances counted on a blueprint version.
+    async fn blueprint_runs(&self, key: &str) -> Result<usize>;
+
+    async fn record_failure(&self, instance_id: Uuid, failure: InstanceFailure) -> Result<()>;
+    async fn get_failure(&self, instance_id: Uuid) -> Result<Option<InstanceFailure>>;
+    /// Atomically moves a Failed instance back to Running and clears its failure record.
+    /// Returns false, changing nothing, if the instance is not Failed.
+    async fn reopen_failed(&self, instance_id: Uuid) -> Result<bool>;
+
+    /// Compensation stack: indices of compensation nodes, pushed as their nodes complete.
+    async fn push_compensation(&self, instance_id: Uuid, node_index: usize) -> Result<()>;
+    /// Returns the most recently pushed compensation without removing it.
+    async fn peek_compensation(&self, instance_id: Uuid) -> Result<Option<usize>>;
+    /// Removes the most recently pushed compensation, once it has been executed.
+    async fn pop_compensation(&self, instance_id: Uuid) -> Result<Option<usize>>;
+
+    /// Parks a task of a suspended instance until it is resumed.
+    async fn park_task(&self, task: Task) -> Result<()>;
+    /// Atomically removes and returns all parked tasks of an instance.
+    async fn take_parked_tasks(&self, instance_id: Uuid) -> Result<Vec<Task>>;
+}
+
+/// Compiled blueprints shared between the processes that submit workflows and the workers
+/// that run them.
+#[async_trait]
+pub trait BlueprintStore: Send + Sync {
+    /// Stores the blueprint under its id and version, and makes it the latest version.
+    /// Earlier versions are kept for the instances that may still run on them, until the
+    /// engine removes them once the last of those finished.
+    async fn save_blueprint(&self, blueprint: &Blueprint) -> Result<()>;
+    /// Returns the given version of a blueprint, or the latest one if `version` is `None`.
+    async fn get_blueprint(&self, id: &str, version: Option<&str>) -> Result<Option<Blueprint>>;
+    /// Removes a version of a blueprint. The latest version is kept; returns whether
+    /// the version was removed.
+    async fn remove_blueprint(&self, id: &str, version: &str) -> Result<bool>;
+}
+
+/// Key of one version of a blueprint, e.g. in caches.
+pub fn versioned_blueprint_key(id: &str, version: &str) -> String {
+    format!("{}@{}", id, version)
+}
+
+/// Everything a successfully executed task writes: the variables it set, the completion
+/// record for compensation and its follow-up tasks.
+#[derive(Debug, Default)]
+pub struct TaskCommit {
+    pub instance_id: Uuid,
+    pub vars: Vec<(String, Value)>,
+    /// Compensation node to push on the instance's compensation stack
+    pub compensation: Option<usize>,
+    /// Set by Join tasks: the follow-up tasks are only queued if this arrival completes the join
+    pub join: Option<JoinArrival>,
+    pub tasks: Vec<Task>,
+}
+
+/// A branch, identified by its token, arriving at a Join node that waits for `expect_count` branches.
+#[derive(Debug, Clone)]
+pub struct JoinArrival {
+    pub node_index: usize,
+    pub token_id: Uuid,
+    pub expect_count: usize,
+}
+
+/// Applies a `TaskCommit`. Variables and the compensation record must be stored no later
+/// than the follow-up tasks are queued, so those tasks always observe them.
+#[async_trait]
+pub trait TaskCommitter: Send + Sync {
+    async fn commit(&self, commit: TaskCommit) -> Result<()>;
+}
+
+/// Applies a commit one store/queue call at a time. Works with any storage.
+pub struct SequentialCommitter {
+    store: Arc<dyn StateStore>,
+    queue: Arc<dyn TaskQueue>,
+}
+
+impl SequentialCommitter {
+    pub fn new(store: Arc<dyn StateStore>, queue: Arc<dyn TaskQueue>) -> Self {
+        Self { store, queue }
+    }
+}
+
+#[async_trait]
+impl TaskCommitter for SequentialCommitter {
+    async fn commit(&self, commit: TaskCommit) -> Result<()> {
+        if !commit.vars.is_empty() {
+            self.store.set_vars(commit.instance_id, commit.vars).await?;
+        }
+        if let Some(compensation) = commit.compensation {
+            self.store.push_compensation(commit.instance_id, compensation).await?;
+        }
+        if let Some(join) = &commit.join && !self.store.record_join_arrival(commit.instance_id, join).await? {
+            return Ok(());
+        }
+        for task in commit.tasks {
+            self.queue.push(task).await?;
+        }
+        Ok(())
+    }
 }
 
 // --- In-Memory Implementations ---
@@ -35,6 +223,16 @@ pub trait StateStore: Send + Sync {
 pub struct InMemoryTaskQueue {
     sender: mpsc::UnboundedSender<Task>,
     receiver: tokio::sync::Mutex<mpsc::UnboundedReceiver<Task>>,
+    // Unacknowledged deliveries, by receipt. Leases never expire: the queue dies with the process,
+    // unless it is shared with a new engine through `requeue_leased`.
+    leased: DashMap<String, Task>,
+    dead_letters: DashMap<Uuid, DeadLetter>,
+}
+
+impl Default for InMemoryTaskQueue {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl InMemoryTaskQueue {
@@ -43,8 +241,20 @@ impl InMemoryTaskQueue {
         Self {
             sender: tx,
             receiver: tokio::sync::Mutex::new(rx),
+            leased: DashMap::new(),
+            dead_letters: DashMap::new(),
         }
     }
+
+    /// Re-queues every unacked delivery, as if its lease had expired. Hands the tasks of an
+    /// engine that was dropped to another engine sharing this queue.
+    pub async fn requeue_leased(&self) -> Result<usize> {
+        let receipts: Vec<String> = self.leased.iter().map(|l| l.key().clone()).collect();
+        for receipt in &receipts {
+            self.nack(receipt).await?;
+        }
+        Ok(receipts.len())
+    }
 }
 
 #[async_trait]
@@ -53,17 +263,126 @@ impl TaskQueue for InMemoryTaskQueue {
         self.sender.send(task).map_err(|e| anyhow::anyhow!("Task channel closed: {}", e))
     }
 
-    async fn pop(&self) -> Result<Option<Task>> {
+    async fn pop(&self) -> Result<Option<Delivery>> {
         let mut rx = self.receiver.lock().await;
-        Ok(rx.recv().await)
+        Ok(rx.recv().await.map(|task| {
+            let receipt = Uuid::new_v4().to_string();
+            self.leased.insert(receipt.clone(), task.clone());
+            Delivery { task, receipt }
+        }))
+    }
+
+    async fn ack(&self, receipt: &str) -> Result<()> {
+        self.leased.remove(receipt);
+        Ok(())
+    }
+
+    async fn nack(&self, receipt: &str) -> Result<()> {
+        if let Some((_, task)) = self.leased.remove(receipt) {
+            self.push(task).await?;
+        }
+        Ok(())
+    }
+
+    async fn dead_letter(&self, letter: DeadLetter) -> Result<()> {
+        self.dead_letters.insert(letter.id, letter);
+        Ok(())
+    }
+
+    async fn dead_letters(&self) -> Result<Vec<DeadLetter>> {
+        let mut letters: Vec<_> = self.dead_letters.iter().map(|l| l.value().clone()).collect();
+        letters.sort_by_key(|l| l.failed_at);
+        Ok(letters)
+    }
+
+    async fn get_dead_letter(&self, id: Uuid) -> Result<Option<DeadLetter>> {
+        Ok(self.dead_letters.get(&id).map(|l| l.value().clone()))
+    }
+
+    async fn remove_dead_letter(&self, id: Uuid) -> Result<bool> {
+        Ok(self.dead_letters.remove(&id).is_some())
+    }
+
+    async fn purge_dead_letters(&self) -> Result<usize> {
+        let count = self.dead_letters.len();
+        self.dead_letters.clear();
+        Ok(count)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryBlueprintStore {
+    // Keyed by `versioned_blueprint_key`
+    blueprints: DashMap<String, Blueprint>,
+    latest: DashMap<String, String>,
+}
+
+impl InMemoryBlueprintStore {
+    pub fn new() -> Self {
+        Self::default()
     }
 }
 
+#[async_trait]
+impl BlueprintStore for InMemoryBlueprintStore {
+    async fn save_blueprint(&self, blueprint: &Blueprint) -> Result<()> {
+        let key = versioned_blueprint_key(&blueprint.id, &blueprint.version);
+        self.blueprints.insert(key, blueprint.clone());
+        self.latest.insert(blueprint.id.clone(), blueprint.version.clone());
+        Ok(())
+    }
+
+    async fn get_blueprint(&self, id: &str, version: Option<&str>) -> Result<Option<Blueprint>> {
+        let version = match version {
+            Some(version) => version.to_string(),
+            None => match self.latest.get(id) {
+                Some(latest) => latest.value().clone(),
+                None => return Ok(None),
+            },
+        };
+        Ok(self.blueprints.get(&versioned_blueprint_key(id, &version)).map(|b| b.value().clone()))
+    }
+
+    async fn remove_blueprint(&self, id: &str, version: &str) -> Result<bool> {
+        // Held while removing, so a concurrent save cannot make the version latest in between
+        let latest = self.latest.get(id);
+        if latest.as_ref().is_some_and(|latest| latest.value() == version) {
+            return Ok(false);
+        }
+        Ok(self.blueprints.remove(&versioned_blueprint_key(id, version)).is_some())
+    }
+}
+
+/// Branches waiting at a Join node, and those of its completed rounds (a loop may reach it
+/// again), so a redelivered arrival is not counted towards the next round.
+#[derive(Default)]
+struct JoinArrivals {
+    arrived: HashSet<Uuid>,
+    completed: HashSet<Uuid>,
+}
+
 pub struct InMemoryStateStore {
     // Map<InstanceID, Map<VarKey, Value>>
     vars: DashMap<Uuid, DashMap<String, Value>>,
-    // Map<InstanceID, Map<NodeIndex, AtomicCounter>>
-    joins: DashMap<Uuid, DashMap<usize, Arc<AtomicUsize>>>,
+    // Map<(InstanceID, NodeIndex), Arrivals>
+    joins: DashMap<(Uuid, usize), JoinArrivals>,
+    // Map<InstanceID, Status>
+    statuses: DashMap<Uuid, InstanceStatus>,
+    failures: DashMap<Uuid, InstanceFailure>,
+    blueprint_versions: DashMap<Uuid, String>,
+    // Map<InstanceID, VersionedBlueprintKey> and Map<VersionedBlueprintKey, Set<InstanceID>>
+    blueprint_runs: DashMap<Uuid, String>,
+    runs_by_version: DashMap<String, HashSet<Uuid>>,
+    // Map<InstanceID, Stack<CompensationNodeIndex>>
+    compensations: DashMap<Uuid, Vec<usize>>,
+    // Map<InstanceID, ParkedTasks>
+    parked: DashMap<Uuid, Vec<Task>>,
+}
+
+impl Default for InMemoryStateStore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl InMemoryStateStore {
@@ -71,6 +390,13 @@ impl InMemoryStateStore {
         Self {
             vars: DashMap::new(),
             joins: DashMap::new(),
+            statuses: DashMap::new(),
+            failures: DashMap::new(),
+            blueprint_versions: DashMap::new(),
+            blueprint_runs: DashMap::new(),
+            runs_by_version: DashMap::new(),
+            compensations: DashMap::new(),
+            parked: DashMap::new(),
         }
     }
 }
@@ -87,7 +413,7 @@ impl StateStore for InMemoryStateStore {
 
     async fn set_var(&self, instance_id: Uuid, key: &str, value: Value) -> Result<()> {
         // Ensure instance entry exists
-        let inst_vars = self.vars.entry(instance_id).or_insert_with(DashMap::new);
+        let inst_vars = self.vars.entry(instance_id).or_default();
         inst_vars.insert(key.to_string(), value);
         Ok(())
     }
@@ -113,24 +439,111 @@ impl StateStore for InMemoryStateStore {
         }
     }
 
-    async fn decrement_join_count(&self, instance_id: Uuid, node_index: usize, initial_count: usize) -> Result<usize> {
-        let inst_joins = self.joins.entry(instance_id).or_insert_with(DashMap::new);
-        
-        // 1. Get the Arc and release the map lock immediately by cloning
-        let counter_arc = inst_joins.entry(node_index)
-            .or_insert_with(|| Arc::new(AtomicUsize::new(initial_count)))
-            .value()
-            .clone();
+    async fn record_join_arrival(&self, instance_id: Uuid, arrival: &JoinArrival) -> Result<bool> {
+        // The entry guard holds the shard lock, making the arrival atomic
+        let mut joins = self.joins.entry((instance_id, arrival.node_index)).or_default();
+        if joins.completed.contains(&arrival.token_id) {
+            return Ok(false);
+        }
+        joins.arrived.insert(arrival.token_id);
+        if joins.arrived.len() < arrival.expect_count {
+            return Ok(false);
+        }
+        let arrived = std::mem::take(&mut joins.arrived);
+        joins.completed.extend(arrived);
+        Ok(true)
+    }
+
+    async fn set_status(&self, instance_id: Uuid, status: InstanceStatus) -> Result<()> {
+        self.statuses.insert(instance_id, status);
+        Ok(())
+    }
+
+    async fn get_status(&self, instance_id: Uuid) -> Result<Option<InstanceStatus>> {
+        Ok(self.statuses.get(&instance_id).map(|s| *s.value()))
+    }
 
-        // 2. Operate on the AtomicUsize (no map lock held here)
-        let prev = counter_arc.fetch_sub(1, Ordering::SeqCst);
-        let new_val = prev - 1;
-        
-        // 3. If zero, cleanup (locks map again, which is now safe)
-        if new_val == 0 {
-             inst_joins.remove(&node_index);
+    async fn transition_status(&self, instance_id: Uuid, status: InstanceStatus) -> Result<bool> {
+        // The entry guard holds the shard lock, making check-and-set atomic
+        let mut entry = self.statuses.entry(instance_id).or_insert(InstanceStatus::Pending);
+        if entry.value().is_terminal() || *entry.value() == status {
+            return Ok(false);
         }
-        
-        Ok(new_val)
+        *entry.value_mut() = status;
+        Ok(true)
+    }
+
+    async fn set_blueprint_version(&self, instance_id: Uuid, version: &str) -> Result<()> {
+        self.blueprint_versions.insert(instance_id, version.to_string());
+        Ok(())
+    }
+
+    async fn get_blueprint_version(&self, instance_id: Uuid) -> Result<Option<String>> {
+        Ok(self.blueprint_versions.get(&instance_id).map(|v| v.value().clone()))
+    }
+
+    async fn track_blueprint_run(&self, instance_id: Uuid, key: &str) -> Result<()> {
+        self.runs_by_version.entry(key.to_string()).or_default().insert(instance_id);
+        self.blueprint_runs.insert(instance_id, key.to_string());
+        Ok(())
+    }
+
+    async fn finish_blueprint_run(&self, instance_id: Uuid) -> Result<()> {
+        if let Some((_, key)) = self.blueprint_runs.remove(&instance_id)
+            && let Entry::Occupied(mut runs) = self.runs_by_version.entry(key) {
+            runs.get_mut().remove(&instance_id);
+            if runs.get().is_empty() {
+                runs.remove();
+            }
+        }
+        Ok(())
+    }
+
+    async fn blueprint_runs(&self, key: &str) -> Result<usize> {
+        Ok(self.runs_by_version.get(key).map_or(0, |runs| runs.len()))
+    }
+
+    async fn record_failure(&self, instance_id: Uuid, failure: InstanceFailure) -> Result<()> {
+        self.failures.insert(instance_id, failure);
+        Ok(())
+    }
+
+    async fn get_failure(&self, instance_id: Uuid) -> Result<Option<InstanceFailure>> {
+        Ok(self.failures.get(&instance_id).map(|f| f.value().clone()))
+    }
+
+    async fn reopen_failed(&self, instance_id: Uuid) -> Result<bool> {
+        // Held across the failure removal, so a concurrent failure is not recorded in between
+        let Some(mut status) = self.statuses.get_mut(&instance_id) else {
+            return Ok(false);
+        };
+        if *status != InstanceStatus::Failed {
+            return Ok(false);
+        }
+        *status = InstanceStatus::Running;
+        self.failures.remove(&instance_id);
+        Ok(true)
+    }
+
+    async fn push_compensation(&self, instance_id: Uuid, node_index: usize) -> Result<()> {
+        self.compensations.entry(instance_id).or_default().push(node_index);
+        Ok(())
+    }
+
+    async fn peek_compensation(&self, instance_id: Uuid) -> Result<Option<usize>> {
+        Ok(self.compensations.get(&instance_id).and_then(|stack| stack.last().copied()))
+    }
+
+    async fn pop_compensation(&self, instance_id: Uuid) -> Result<Option<usize>> {
+        Ok(self.compensations.get_mut(&instance_id).and_then(|mut stack| stack.pop()))
+    }
+
+    async fn park_task(&self, task: Task) -> Result<()> {
+        self.parked.entry(task.instance_id).or_default().push(task);
+        Ok(())
+    }
+
+    async fn take_parked_tasks(&self, instance_id: Uuid) -> Result<Vec<Task>> {
+        Ok(self.parked.remove(&instance_id).map(|(_, tasks)| tasks).unwrap_or_default())
     }
 }
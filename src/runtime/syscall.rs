@@ -11,6 +11,10 @@ pub trait Syscall: Send + Sync {
     
     /// 挂起当前任务 (不产生新任务，等待被唤醒或丢弃)
     fn wait(&mut self);
+
+    /// 汇合：当前分支到达等待 `expect_count` 个分支的 Join 节点，
+    /// 后续任务仅在最后一个分支到达时才被调度 (同一分支重复到达只计一次)
+    fn arrive(&mut self, expect_count: usize);
     
     /// 结束当前分支
     fn terminate(&mut self);
@@ -6,6 +6,9 @@ use serde::{Serialize, Deserialize};
 pub struct Task {
     pub instance_id: Uuid,
     pub workflow_id: String,
+    /// 实例启动时固定的蓝图版本 (为空时使用最新版本)
+    #[serde(default)]
+    pub blueprint_version: Option<String>,
     pub token_id: Uuid,
     pub node_index: NodeIndex,
     /// 用于追踪 Fork/Join 的血缘关系
@@ -14,4 +17,13 @@ pub struct Task {
     /// 或者：使用 Token 的 Parent 关系。
     /// 我们暂时保留 flow_id，用于标识“这一批并行任务”。
     pub flow_id: Uuid, 
+    /// 当前节点已失败的次数 (用于重试退避)，跳转到新节点时归零
+    #[serde(default)]
+    pub attempt: u32,
+    /// 实例截止时间 (Unix 毫秒)，由启动时的 Blueprint 配置决定并沿分支传递
+    #[serde(default)]
+    pub deadline: Option<u64>,
+    /// 是否为补偿任务 (实例处于 Compensating 状态时只执行补偿任务)
+    #[serde(default)]
+    pub compensation: bool,
 }
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tracing::error;
+use crate::runtime::engine::ShutdownHandle;
+
+/// What happened to a popped task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TaskOutcome {
+    Succeeded,
+    /// The node failed; it may still be caught by an error branch.
+    Failed,
+    /// The node failed and a retry is scheduled. The delivery is acked once the retry is queued.
+    Retrying,
+    /// Dropped, parked, aborted or re-queued without running to completion.
+    Skipped,
+}
+
+/// Counters of a single worker, updated as it processes tasks.
+#[derive(Debug, Default)]
+pub struct WorkerStats {
+    worker: usize,
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    retried: AtomicU64,
+    skipped: AtomicU64,
+    busy: AtomicBool,
+}
+
+impl WorkerStats {
+    pub(crate) fn new(worker: usize) -> Self {
+        Self { worker, ..Default::default() }
+    }
+
+    pub(crate) fn worker(&self) -> usize {
+        self.worker
+    }
+
+    pub(crate) fn set_busy(&self, busy: bool) {
+        self.busy.store(busy, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record(&self, outcome: TaskOutcome) {
+        let counter = match outcome {
+            TaskOutcome::Succeeded => &self.succeeded,
+            TaskOutcome::Failed => &self.failed,
+            TaskOutcome::Retrying => &self.retried,
+            TaskOutcome::Skipped => &self.skipped,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.set_busy(false);
+    }
+
+    pub fn snapshot(&self) -> WorkerStatsSnapshot {
+        WorkerStatsSnapshot {
+            worker: self.worker,
+            succeeded: self.succeeded.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            retried: self.retried.load(Ordering::Relaxed),
+            skipped: self.skipped.load(Ordering::Relaxed),
+            busy: self.busy.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Point-in-time copy of `WorkerStats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct WorkerStatsSnapshot {
+    pub worker: usize,
+    pub succeeded: u64,
+    pub failed: u64,
+    /// Failed attempts that were scheduled for a retry
+    pub retried: u64,
+    pub skipped: u64,
+    /// Whether the worker is executing a task right now
+    pub busy: bool,
+}
+
+impl WorkerStatsSnapshot {
+    pub fn processed(&self) -> u64 {
+        self.succeeded + self.failed + self.retried + self.skipped
+    }
+}
+
+/// Handle to the workers started by `Engine::spawn_workers`.
+pub struct WorkerPool {
+    handles: Vec<JoinHandle<()>>,
+    stats: Vec<Arc<WorkerStats>>,
+    shutdown: ShutdownHandle,
+}
+
+impl WorkerPool {
+    pub(crate) fn new(handles: Vec<JoinHandle<()>>, stats: Vec<Arc<WorkerStats>>, shutdown: ShutdownHandle) -> Self {
+        Self { handles, stats, shutdown }
+    }
+
+    pub fn size(&self) -> usize {
+        self.handles.len()
+    }
+
+    pub fn stats(&self) -> Vec<WorkerStatsSnapshot> {
+        self.stats.iter().map(|s| s.snapshot()).collect()
+    }
+
+    /// Sum of the stats of all workers (`worker` is the pool size, `busy` is true if any worker is).
+    pub fn total_stats(&self) -> WorkerStatsSnapshot {
+        self.stats().into_iter().fold(
+            WorkerStatsSnapshot { worker: self.size(), ..Default::default() },
+            |total, s| WorkerStatsSnapshot {
+                worker: total.worker,
+                succeeded: total.succeeded + s.succeeded,
+                failed: total.failed + s.failed,
+                retried: total.retried + s.retried,
+                skipped: total.skipped + s.skipped,
+                busy: total.busy || s.busy,
+            },
+        )
+    }
+
+    /// Requests a graceful shutdown of this pool's workers. Other pools of the engine keep running.
+    pub fn shutdown(&self) {
+        self.shutdown.shutdown();
+    }
+
+    /// Waits until every worker has returned.
+    pub async fn join(self) {
+        for handle in self.handles {
+            if let Err(e) = handle.await
+                && e.is_panic() {
+                error!("Worker panicked: {}", e);
+            }
+        }
+    }
+
+    pub async fn shutdown_and_join(self) {
+        self.shutdown();
+        self.join().await;
+    }
+
+    /// Stops all workers immediately, without draining in-flight tasks.
+    pub fn abort(&self) {
+        for handle in &self.handles {
+            handle.abort();
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use skript::dsl::builder::WorkflowBuilder;
+use skript::compiler::core::Compiler;
+use skript::runtime::engine::Engine;
+use skript::runtime::instance::InstanceStatus;
+use skript::runtime::storage::{versioned_blueprint_key, BlueprintStore, InMemoryBlueprintStore, InMemoryStateStore, InMemoryTaskQueue, StateStore, TaskQueue};
+use skript::actions::builtin::AssignAction;
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn engine(store: &Arc<InMemoryStateStore>, queue: &Arc<InMemoryTaskQueue>, blueprints: &Arc<InMemoryBlueprintStore>) -> Engine {
+    let store: Arc<dyn StateStore> = store.clone();
+    let queue: Arc<dyn TaskQueue> = queue.clone();
+    let mut engine = Engine::new_with_storage(store, queue);
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_function(Arc::new(AssignAction));
+    engine.set_blueprint_store(blueprints.clone());
+    engine
+}
+
+#[tokio::test]
+async fn test_worker_fetches_published_blueprint() {
+    let store = Arc::new(InMemoryStateStore::new());
+    let queue = Arc::new(InMemoryTaskQueue::new());
+    let blueprints = Arc::new(InMemoryBlueprintStore::new());
+
+    let workflow = WorkflowBuilder::new("shared-flow")
+        .start("start")
+        .function("set", "assign").param("value", 42).output("answer").build()
+        .end("end", "answer")
+        .connect("start", "set")
+        .connect("set", "end")
+        .build();
+    let blueprint = Compiler::new().compile(workflow).expect("Compilation failed");
+
+    // The submitter publishes; the worker never registers the blueprint itself
+    let submitter = engine(&store, &queue, &blueprints);
+    submitter.publish_blueprint(blueprint).await.unwrap();
+    assert!(blueprints.get_blueprint("shared-flow", None).await.unwrap().is_some());
+
+    let worker = Arc::new(engine(&store, &queue, &blueprints));
+    let workers = worker.spawn_workers(1);
+
+    let instance_id = submitter.start_workflow("shared-flow", HashMap::new()).await.unwrap();
+    let status = worker.await_completion(instance_id, Duration::from_secs(5)).await.unwrap();
+    workers.abort();
+
+    assert_eq!(status, InstanceStatus::Completed);
+    assert_eq!(worker.get_output(instance_id).await.unwrap(), Some(json!(42)));
+}
+
+#[tokio::test]
+async fn test_unknown_blueprint_is_rejected() {
+    let store = Arc::new(InMemoryStateStore::new());
+    let queue = Arc::new(InMemoryTaskQueue::new());
+    let blueprints = Arc::new(InMemoryBlueprintStore::new());
+
+    let engine = engine(&store, &queue, &blueprints);
+    let err = engine.start_workflow("missing", HashMap::new()).await.unwrap_err();
+    assert_eq!(err.to_string(), "Blueprint not found: missing");
+}
+
+#[tokio::test]
+async fn test_replaced_version_is_evicted_once_drained() {
+    let store = Arc::new(InMemoryStateStore::new());
+    let queue = Arc::new(InMemoryTaskQueue::new());
+    let blueprints = Arc::new(InMemoryBlueprintStore::new());
+    let compile = |value: i64| {
+        let workflow = WorkflowBuilder::new("drained-flow")
+            .start("start")
+            .function("set", "assign").param("value", value).output("answer").build()
+            .end("end", "answer")
+            .connect("start", "set")
+            .connect("set", "end")
+            .build();
+        Compiler::new().compile(workflow).expect("Compilation failed")
+    };
+
+    let engine = Arc::new(engine(&store, &queue, &blueprints));
+    let v1 = compile(1);
+    let v1_version = v1.version.clone();
+    let v1_key = versioned_blueprint_key("drained-flow", &v1_version);
+    engine.publish_blueprint(v1).await.unwrap();
+    let cancelled = engine.start_workflow("drained-flow", HashMap::new()).await.unwrap();
+    let running = engine.start_workflow("drained-flow", HashMap::new()).await.unwrap();
+    assert_eq!(store.blueprint_runs(&v1_key).await.unwrap(), 2);
+
+    let v2 = compile(2);
+    let v2_version = v2.version.clone();
+    engine.publish_blueprint(v2).await.unwrap();
+
+    // One instance still runs on v1, so it is kept
+    engine.cancel(cancelled, "not needed").await.unwrap();
+    assert_eq!(store.blueprint_runs(&v1_key).await.unwrap(), 1);
+    assert!(blueprints.get_blueprint("drained-flow", Some(&v1_version)).await.unwrap().is_some());
+
+    let workers = engine.spawn_workers(1);
+    let status = engine.await_completion(running, Duration::from_secs(5)).await.unwrap();
+    assert_eq!(status, InstanceStatus::Completed);
+    assert_eq!(engine.get_output(running).await.unwrap(), Some(json!(1)));
+
+    // The last v1 instance finished: v1 is gone, the latest version stays
+    assert_eq!(store.blueprint_runs(&v1_key).await.unwrap(), 0);
+    assert!(blueprints.get_blueprint("drained-flow", Some(&v1_version)).await.unwrap().is_none());
+    assert!(!blueprints.remove_blueprint("drained-flow", &v2_version).await.unwrap());
+    let latest = engine.start_workflow("drained-flow", HashMap::new()).await.unwrap();
+    engine.await_completion(latest, Duration::from_secs(5)).await.unwrap();
+    assert_eq!(engine.get_output(latest).await.unwrap(), Some(json!(2)));
+    assert!(blueprints.get_blueprint("drained-flow", Some(&v2_version)).await.unwrap().is_some());
+
+    workers.abort();
+}
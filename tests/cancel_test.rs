@@ -0,0 +1,233 @@
+use skript::dsl::builder::WorkflowBuilder;
+use skript::dsl::{Node, NodeType};
+use skript::compiler::core::Compiler;
+use skript::compiler::loader::load_workflow_from_yaml;
+use skript::runtime::engine::Engine;
+use skript::runtime::context::Context;
+use skript::runtime::instance::InstanceStatus;
+use skript::runtime::storage::{InMemoryStateStore, InMemoryTaskQueue};
+use skript::actions::FunctionHandler;
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use skript::nodes::flow::{ForkDefinition, JoinDefinition, LoopDefinition};
+use async_trait::async_trait;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Blocks until the instance is cancelled, recording that it observed the cancellation.
+#[derive(Debug)]
+struct BlockingAction {
+    observed_cancel: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl FunctionHandler for BlockingAction {
+    fn name(&self) -> &str { "block" }
+    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    async fn execute(&self, _params: Value, ctx: &Context) -> Result<Value> {
+        tokio::select! {
+            _ = ctx.cancelled() => {
+                self.observed_cancel.store(true, Ordering::SeqCst);
+                Err(anyhow!("Cancelled"))
+            }
+            _ = tokio::time::sleep(Duration::from_secs(10)) => Ok(json!("done")),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TickAction {
+    ticks: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl FunctionHandler for TickAction {
+    fn name(&self) -> &str { "tick" }
+    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    async fn execute(&self, _params: Value, _ctx: &Context) -> Result<Value> {
+        self.ticks.fetch_add(1, Ordering::SeqCst);
+        Ok(Value::Null)
+    }
+}
+
+#[tokio::test]
+async fn test_cancel_aborts_in_flight_handler() {
+    let workflow = WorkflowBuilder::new("cancel-test")
+        .start("start")
+        .function("call", "block").output("result").build()
+        .end("end", "result")
+        .connect("start", "call")
+        .connect("call", "end")
+        .build();
+
+    let mut compiler = Compiler::new();
+    let blueprint = compiler.compile(workflow).expect("Compilation failed");
+
+    let observed_cancel = Arc::new(AtomicBool::new(false));
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_function(Arc::new(BlockingAction { observed_cancel: observed_cancel.clone() }));
+    engine.register_blueprint(blueprint);
+    let engine = Arc::new(engine);
+
+    let instance_id = engine.start_workflow("cancel-test", HashMap::new())
+        .await
+        .expect("Failed to start workflow");
+
+    let worker_engine = engine.clone();
+    let worker = tokio::spawn(async move {
+        worker_engine.run_worker().await;
+    });
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(engine.cancel(instance_id, "Operator request").await.unwrap());
+
+    let status = engine.await_completion(instance_id, Duration::from_secs(1))
+        .await
+        .expect("Instance did not reach a terminal state");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    worker.abort();
+
+    assert_eq!(status, InstanceStatus::Cancelled);
+    assert!(observed_cancel.load(Ordering::SeqCst));
+    assert_eq!(engine.get_failure(instance_id).await.unwrap().unwrap().error, "Operator request");
+    assert_eq!(engine.get_output(instance_id).await.unwrap(), None);
+
+    // Cancelling a finished instance is a no-op
+    assert!(!engine.cancel(instance_id, "Again").await.unwrap());
+}
+
+#[tokio::test]
+async fn test_cancel_stops_runaway_loop() {
+    let yaml_content = r#"
+id: "runaway"
+nodes:
+  - id: "start"
+    type: "Start"
+  - id: "forever"
+    type: "Loop"
+    condition: "true"
+  - id: "tick"
+    type: "Function"
+    name: "tick"
+  - id: "end"
+    type: "End"
+edges:
+  - source: "start"
+    target: "forever"
+  - source: "forever"
+    target: "tick"
+    branch_type: "body"
+  - source: "tick"
+    target: "forever"
+  - source: "forever"
+    target: "end"
+"#;
+
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let file_path = temp_dir.path().join("runaway.yaml");
+    fs::write(&file_path, yaml_content).expect("Failed to write temp file");
+
+    let workflow = load_workflow_from_yaml(&file_path.to_string_lossy()).expect("Failed to load workflow");
+    let mut compiler = Compiler::new();
+    let blueprint = compiler.compile(workflow).expect("Compilation failed");
+
+    let ticks = Arc::new(AtomicUsize::new(0));
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_node(Box::new(LoopDefinition));
+    engine.register_function(Arc::new(TickAction { ticks: ticks.clone() }));
+    engine.register_blueprint(blueprint);
+    let engine = Arc::new(engine);
+
+    let instance_id = engine.start_workflow("runaway", HashMap::new())
+        .await
+        .expect("Failed to start workflow");
+
+    let worker_engine = engine.clone();
+    let worker = tokio::spawn(async move {
+        worker_engine.run_worker().await;
+    });
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(engine.cancel(instance_id, "Runaway loop").await.unwrap());
+    assert_eq!(engine.get_status(instance_id).await.unwrap(), Some(InstanceStatus::Cancelled));
+
+    // At most the in-flight task finishes after the cancellation
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let after_cancel = ticks.load(Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    worker.abort();
+
+    assert!(after_cancel > 0);
+    assert_eq!(ticks.load(Ordering::SeqCst), after_cancel);
+}
+
+#[tokio::test]
+async fn test_cancel_from_another_engine_aborts_every_running_branch() {
+    let block = |id: &str| Node::new(id, NodeType::Function { name: "block".to_string(), params: HashMap::new(), output: None });
+    let workflow = WorkflowBuilder::new("remote-cancel-test")
+        .start("start")
+        .parallel("p1", vec![vec![block("left")], vec![block("right")]])
+        .end("end", "")
+        .connect("start", "p1")
+        .connect("p1", "end")
+        .build();
+    let blueprint = Compiler::new().compile(workflow).expect("Compilation failed");
+
+    let store = Arc::new(InMemoryStateStore::new());
+    let queue = Arc::new(InMemoryTaskQueue::new());
+    let observed = Arc::new(AtomicUsize::new(0));
+    let mut worker = Engine::new_with_storage(store.clone(), queue.clone());
+    worker.register_node(Box::new(StartDefinition));
+    worker.register_node(Box::new(EndDefinition));
+    worker.register_node(Box::new(ForkDefinition));
+    worker.register_node(Box::new(JoinDefinition));
+    worker.register_function(Arc::new(CountingBlockAction { observed: observed.clone() }));
+    worker.register_blueprint(blueprint);
+    let worker = Arc::new(worker);
+    let workers = worker.spawn_workers(2);
+
+    let instance_id = worker.start_workflow("remote-cancel-test", HashMap::new()).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Only the store tells the worker engine about this cancellation
+    let operator = Engine::new_with_storage(store, queue);
+    assert!(operator.cancel(instance_id, "Operator request").await.unwrap());
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while observed.load(Ordering::SeqCst) < 2 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }).await.expect("Running branches were not aborted");
+    workers.abort();
+
+    assert_eq!(worker.get_status(instance_id).await.unwrap(), Some(InstanceStatus::Cancelled));
+}
+
+/// Blocks until the instance is cancelled, counting the calls that observed it.
+#[derive(Debug)]
+struct CountingBlockAction {
+    observed: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl FunctionHandler for CountingBlockAction {
+    fn name(&self) -> &str { "block" }
+    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    async fn execute(&self, _params: Value, ctx: &Context) -> Result<Value> {
+        tokio::select! {
+            _ = ctx.cancelled() => {
+                self.observed.fetch_add(1, Ordering::SeqCst);
+                Err(anyhow!("Cancelled"))
+            }
+            _ = tokio::time::sleep(Duration::from_secs(10)) => Ok(json!("done")),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use skript::runtime::blueprint::Blueprint;
+use std::process::{Command, Output};
+
+fn skript(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_skript"))
+        .args(args)
+        .output()
+        .expect("Failed to run skript")
+}
+
+#[test]
+fn test_check_reports_errors_and_exit_code() {
+    let ok = skript(&["check", "dsl_examples/assign_node.yaml", "dsl_examples/if_node.yaml"]);
+    assert!(ok.status.success(), "{}", String::from_utf8_lossy(&ok.stderr));
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let broken = dir.path().join("broken.yaml");
+    std::fs::write(&broken, "id: \"broken\"\nnodes:\n  - id: \"start\"\n    type: \"Start\"\n    next: \"missing\"\n")
+        .expect("Failed to write temp file");
+
+    let failed = skript(&["check", "--message-format", "json", "dsl_examples/assign_node.yaml", &broken.to_string_lossy()]);
+    assert!(!failed.status.success());
+    let stderr = String::from_utf8_lossy(&failed.stderr);
+    let diagnostic: serde_json::Value = stderr.lines()
+        .find_map(|line| serde_json::from_str(line).ok())
+        .expect("Expected a JSON diagnostic");
+    assert_eq!(diagnostic["code"], "unknown-target");
+    assert_eq!(diagnostic["span"]["line"], 5);
+    assert!(stderr.contains("1 of 2 workflows failed to compile"));
+}
+
+#[test]
+fn test_check_accepts_builtin_http() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let workflow = dir.path().join("http.yaml");
+    std::fs::write(&workflow, r#"id: "http-flow"
+nodes:
+  - id: "start"
+    type: "Start"
+    next: "fetch"
+  - id: "fetch"
+    type: "Function"
+    name: "http"
+    params: { url: "https://example.com", method: "GET" }
+    output: "response"
+    next: "end"
+  - id: "end"
+    type: "End"
+"#).expect("Failed to write temp file");
+
+    let output = skript(&["check", &workflow.to_string_lossy()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn test_compile_writes_blueprint() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let fused_path = dir.path().join("fused.json");
+    let output = skript(&["compile", "-f", "dsl_examples/assign_node.yaml", "-o", &fused_path.to_string_lossy()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let fused: Blueprint = serde_json::from_str(&std::fs::read_to_string(&fused_path).unwrap()).unwrap();
+
+    let output = skript(&["compile", "-f", "dsl_examples/assign_node.yaml", "--no-fusion"]);
+    assert!(output.status.success());
+    let unfused: Blueprint = serde_json::from_slice(&output.stdout).expect("Expected blueprint JSON on stdout");
+
+    assert_eq!(fused.id, "demo-assignment");
+    assert_eq!(fused.version, fused.content_hash());
+    assert!(fused.nodes.len() < unfused.nodes.len());
+    assert!(unfused.nodes.iter().all(|n| n.kind != "fused"));
+}
+
+#[test]
+#[ignore] // Ignored by default, run explicitly if redis is available
+fn test_dlq_replay_of_unknown_id_fails() {
+    let output = skript(&["dlq", "replay", &uuid::Uuid::new_v4().to_string()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 of 1 dead letters failed to replay"));
+}
@@ -0,0 +1,159 @@
+use skript::dsl::builder::WorkflowBuilder;
+use skript::compiler::core::{Compiler, CompilerConfig};
+use skript::runtime::engine::Engine;
+use skript::runtime::context::Context;
+use skript::runtime::instance::{InstanceStatus, WORKFLOW_OUTPUT_VAR};
+use skript::runtime::storage::{InMemoryStateStore, InMemoryTaskQueue, JoinArrival, SequentialCommitter, StateStore, TaskCommit, TaskCommitter, TaskQueue};
+use skript::runtime::task::Task;
+use skript::actions::FunctionHandler;
+use skript::actions::builtin::AssignAction;
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use async_trait::async_trait;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Records every commit before delegating to the sequential committer.
+struct RecordingCommitter {
+    inner: SequentialCommitter,
+    commits: Mutex<Vec<(Vec<String>, usize)>>,
+}
+
+#[async_trait]
+impl TaskCommitter for RecordingCommitter {
+    async fn commit(&self, commit: TaskCommit) -> Result<()> {
+        let vars = commit.vars.iter().map(|(k, _)| k.clone()).collect();
+        self.commits.lock().unwrap().push((vars, commit.tasks.len()));
+        self.inner.commit(commit).await
+    }
+}
+
+/// Writes a variable, then fails.
+#[derive(Debug)]
+struct WriteThenFail;
+
+#[async_trait]
+impl FunctionHandler for WriteThenFail {
+    fn name(&self) -> &str { "write_then_fail" }
+    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    async fn execute(&self, _params: Value, ctx: &Context) -> Result<Value> {
+        ctx.set_var("partial", json!(true)).await;
+        assert_eq!(ctx.get_var("partial").await, Some(json!(true)));
+        Err(anyhow!("Boom"))
+    }
+}
+
+async fn run(builder: WorkflowBuilder) -> (InstanceStatus, Arc<InMemoryStateStore>, Arc<RecordingCommitter>, uuid::Uuid) {
+    let workflow = builder.build();
+    let workflow_id = workflow.id.clone();
+    let blueprint = Compiler::new_with_config(CompilerConfig { enable_fusion: false, ..Default::default() })
+        .compile(workflow)
+        .expect("Compilation failed");
+
+    let store = Arc::new(InMemoryStateStore::new());
+    let queue = Arc::new(InMemoryTaskQueue::new());
+    let committer = Arc::new(RecordingCommitter {
+        inner: SequentialCommitter::new(store.clone(), queue.clone()),
+        commits: Mutex::new(Vec::new()),
+    });
+
+    let mut engine = Engine::new_with_storage(store.clone(), queue);
+    engine.set_committer(committer.clone());
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_function(Arc::new(AssignAction));
+    engine.register_function(Arc::new(WriteThenFail));
+    engine.register_blueprint(blueprint);
+    let engine = Arc::new(engine);
+
+    let workers = engine.spawn_workers(1);
+    let instance_id = engine.start_workflow(&workflow_id, HashMap::new()).await.unwrap();
+    let status = engine.await_completion(instance_id, Duration::from_secs(5)).await.unwrap();
+    workers.abort();
+    (status, store, committer, instance_id)
+}
+
+#[tokio::test]
+async fn test_each_task_commits_once() {
+    let builder = WorkflowBuilder::new("commit-once")
+        .start("start")
+        .function("set_a", "assign").param("value", 1).output("a").build()
+        .function("set_b", "assign").param("expression", "b = a + 1").build()
+        .end("end", "b")
+        .connect("start", "set_a")
+        .connect("set_a", "set_b")
+        .connect("set_b", "end");
+
+    let (status, store, committer, instance_id) = run(builder).await;
+    assert_eq!(status, InstanceStatus::Completed);
+    assert_eq!(store.get_var(instance_id, "b").await.unwrap(), Some(json!(2)));
+
+    let commits = committer.commits.lock().unwrap().clone();
+    assert_eq!(commits, vec![
+        (vec![], 1),
+        (vec!["a".to_string()], 1),
+        (vec!["b".to_string()], 1),
+        (vec![WORKFLOW_OUTPUT_VAR.to_string()], 0),
+    ]);
+}
+
+#[tokio::test]
+async fn test_failed_task_writes_are_discarded() {
+    let builder = WorkflowBuilder::new("discard-writes")
+        .start("start")
+        .function("fail", "write_then_fail").build()
+        .end("end", "partial")
+        .connect("start", "fail")
+        .connect("fail", "end");
+
+    let (status, store, committer, instance_id) = run(builder).await;
+    assert_eq!(status, InstanceStatus::Failed);
+    assert_eq!(store.get_var(instance_id, "partial").await.unwrap(), None);
+    assert_eq!(committer.commits.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_join_arrivals_are_counted_once_per_branch() {
+    let store = Arc::new(InMemoryStateStore::new());
+    let queue = Arc::new(InMemoryTaskQueue::new());
+    let committer = SequentialCommitter::new(store, queue.clone());
+    let instance_id = Uuid::new_v4();
+    let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+
+    // A Join task of `branch` jumping to node 3, as committed after each of its runs
+    let arrive = |branch: Uuid| TaskCommit {
+        instance_id,
+        join: Some(JoinArrival { node_index: 2, token_id: branch, expect_count: 2 }),
+        tasks: vec![Task {
+            instance_id,
+            workflow_id: "join-once".to_string(),
+            blueprint_version: None,
+            token_id: branch,
+            node_index: 3,
+            flow_id: Uuid::nil(),
+            attempt: 0,
+            deadline: None,
+            compensation: false,
+        }],
+        ..Default::default()
+    };
+
+    // The first branch runs twice: the join must still wait for the second one
+    committer.commit(arrive(first)).await.unwrap();
+    committer.commit(arrive(first)).await.unwrap();
+    assert!(tokio::time::timeout(Duration::from_millis(50), queue.pop()).await.is_err());
+
+    committer.commit(arrive(second)).await.unwrap();
+    let next = queue.pop().await.unwrap().expect("Join did not fire");
+    assert_eq!(next.task.node_index, 3);
+
+    // A late redelivery after the join fired neither fires it again nor counts towards a next round
+    committer.commit(arrive(second)).await.unwrap();
+    committer.commit(arrive(Uuid::new_v4())).await.unwrap();
+    assert!(tokio::time::timeout(Duration::from_millis(50), queue.pop()).await.is_err());
+    committer.commit(arrive(Uuid::new_v4())).await.unwrap();
+    assert!(queue.pop().await.unwrap().is_some());
+}
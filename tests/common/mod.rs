@@ -0,0 +1,74 @@
+//! Mock handlers and engine setup shared by the integration tests.
+// Each test crate compiles this module and uses only part of it
+#![allow(dead_code)]
+
+use skript::runtime::blueprint::Blueprint;
+use skript::runtime::engine::Engine;
+use skript::runtime::context::Context;
+use skript::actions::FunctionHandler;
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use skript::nodes::flow::{ForkDefinition, JoinDefinition};
+use async_trait::async_trait;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Fails every call with `Downstream call failed`, caused by `connection refused`.
+#[derive(Debug, Default)]
+pub struct BrokenAction {
+    pub calls: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl FunctionHandler for BrokenAction {
+    fn name(&self) -> &str { "broken" }
+    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    async fn execute(&self, _params: Value, _ctx: &Context) -> Result<Value> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Err(anyhow!("connection refused").context("Downstream call failed"))
+    }
+}
+
+/// Sleeps for `delay` on every call, then writes `slow_write`. Optionally declares a
+/// handler-level timeout.
+#[derive(Debug)]
+pub struct SlowAction {
+    pub calls: Arc<AtomicUsize>,
+    pub delay: Duration,
+    pub handler_timeout: Option<Duration>,
+}
+
+impl SlowAction {
+    pub fn new(delay: Duration) -> Self {
+        Self { calls: Arc::new(AtomicUsize::new(0)), delay, handler_timeout: None }
+    }
+}
+
+#[async_trait]
+impl FunctionHandler for SlowAction {
+    fn name(&self) -> &str { "slow" }
+    fn default_timeout(&self) -> Option<Duration> { self.handler_timeout }
+    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    async fn execute(&self, _params: Value, ctx: &Context) -> Result<Value> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(self.delay).await;
+        ctx.set_var("slow_write", json!(true)).await;
+        Ok(json!("done"))
+    }
+}
+
+/// An engine with the Start, End and Parallel nodes, the given functions and the blueprint.
+pub fn engine(blueprint: Blueprint, functions: Vec<Arc<dyn FunctionHandler>>) -> Arc<Engine> {
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_node(Box::new(ForkDefinition));
+    engine.register_node(Box::new(JoinDefinition));
+    for function in functions {
+        engine.register_function(function);
+    }
+    engine.register_blueprint(blueprint);
+    Arc::new(engine)
+}
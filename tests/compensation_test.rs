@@ -0,0 +1,135 @@
+use skript::dsl::builder::WorkflowBuilder;
+use skript::dsl::{Node, NodeType, Compensation};
+use skript::compiler::core::Compiler;
+use skript::runtime::engine::Engine;
+use skript::runtime::context::Context;
+use skript::runtime::instance::InstanceStatus;
+use skript::runtime::retry::RetryPolicy;
+use skript::actions::FunctionHandler;
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use async_trait::async_trait;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Appends its `step` param to a shared journal, failing when `fail` is set.
+#[derive(Debug)]
+struct JournalAction {
+    name: String,
+    journal: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl FunctionHandler for JournalAction {
+    fn name(&self) -> &str { &self.name }
+    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    async fn execute(&self, params: Value, _ctx: &Context) -> Result<Value> {
+        let step = params["step"].as_str().unwrap_or_default().to_string();
+        if params["fail"].as_bool().unwrap_or(false) {
+            return Err(anyhow!("{} failed", step));
+        }
+        self.journal.lock().unwrap().push(step);
+        Ok(Value::Null)
+    }
+}
+
+fn step(name: &str) -> HashMap<String, Value> {
+    HashMap::from([("step".to_string(), json!(name))])
+}
+
+#[tokio::test]
+async fn test_failure_runs_compensations_in_reverse_order() {
+    // reserve -> charge -> ship (fails)
+    let workflow = WorkflowBuilder::new("saga-test")
+        .start("start")
+        .function("reserve", "do").param("step", "reserve").compensate("undo", step("release")).build()
+        .function("charge", "do").param("step", "charge").compensate("undo", step("refund")).build()
+        .function("ship", "do").param("step", "ship").param("fail", true)
+            .retry(RetryPolicy { max_attempts: 2, initial_delay: 10, ..Default::default() })
+            .compensate("undo", step("recall")).build()
+        .end("end", "")
+        .connect("start", "reserve")
+        .connect("reserve", "charge")
+        .connect("charge", "ship")
+        .connect("ship", "end")
+        .build();
+
+    let mut compiler = Compiler::new();
+    let blueprint = compiler.compile(workflow).expect("Compilation failed");
+
+    let journal = Arc::new(Mutex::new(Vec::new()));
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_function(Arc::new(JournalAction { name: "do".to_string(), journal: journal.clone() }));
+    engine.register_function(Arc::new(JournalAction { name: "undo".to_string(), journal: journal.clone() }));
+    engine.register_blueprint(blueprint);
+    let engine = Arc::new(engine);
+
+    let instance_id = engine.start_workflow("saga-test", HashMap::new())
+        .await
+        .expect("Failed to start workflow");
+
+    let worker_engine = engine.clone();
+    let worker = tokio::spawn(async move {
+        worker_engine.run_worker().await;
+    });
+
+    let status = engine.await_completion(instance_id, Duration::from_secs(5))
+        .await
+        .expect("Instance did not reach a terminal state");
+    worker.abort();
+
+    assert_eq!(status, InstanceStatus::Failed);
+    // The failed node itself is not compensated
+    assert_eq!(*journal.lock().unwrap(), vec!["reserve", "charge", "refund", "release"]);
+
+    let failure = engine.get_failure(instance_id).await.unwrap().expect("Failure not recorded");
+    assert_eq!(failure.error, "ship failed");
+    assert!(failure.compensated);
+
+    // Replaying "ship" would run it on top of rolled-back state
+    let letters = engine.dead_letters().await.unwrap();
+    assert_eq!(letters.len(), 1);
+    assert!(engine.replay_dead_letter(letters[0].id).await.is_err());
+    assert_eq!(engine.get_status(instance_id).await.unwrap(), Some(InstanceStatus::Failed));
+}
+
+#[test]
+fn test_compensation_compiles_to_extra_node() {
+    let workflow = WorkflowBuilder::new("saga-compile-test")
+        .start("start")
+        .function("reserve", "do").param("step", "reserve").compensate("undo", step("release")).build()
+        .end("end", "")
+        .connect("start", "reserve")
+        .connect("reserve", "end")
+        .build();
+
+    let mut compiler = Compiler::new();
+    let blueprint = compiler.compile(workflow).expect("Compilation failed");
+
+    let reserve = blueprint.nodes.iter().find(|n| n.id == "reserve").unwrap();
+    let index = reserve.policy("compensate").and_then(|v| v.as_u64()).expect("compensate index missing") as usize;
+    let compensation = &blueprint.nodes[index];
+    assert_eq!(compensation.id, "reserve.compensate");
+    assert_eq!(compensation.kind, "undo");
+    assert_eq!(compensation.params, json!({ "step": "release" }));
+}
+
+#[test]
+fn test_compensation_requires_function_node() {
+    let mut workflow = WorkflowBuilder::new("saga-invalid-test")
+        .start("start")
+        .end("end", "")
+        .connect("start", "end")
+        .build();
+    workflow.nodes.push(Node {
+        compensate: Some(Compensation { name: "undo".to_string(), params: HashMap::new() }),
+        ..Node::new("assign", NodeType::Assign { assignments: Vec::new(), expression: None })
+    });
+
+    let mut compiler = Compiler::new();
+    assert!(compiler.compile(workflow).is_err());
+}
@@ -0,0 +1,192 @@
+use skript::dsl::builder::WorkflowBuilder;
+use skript::dsl::{Node, NodeType};
+use skript::compiler::core::Compiler;
+use skript::runtime::engine::Engine;
+use skript::runtime::context::Context;
+use skript::runtime::instance::InstanceStatus;
+use skript::runtime::retry::RetryPolicy;
+use skript::runtime::error::ErrorKind;
+use skript::runtime::storage::{InMemoryStateStore, InMemoryTaskQueue, StateStore};
+use skript::actions::FunctionHandler;
+use skript::actions::builtin::AssignAction;
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use skript::nodes::flow::{ForkDefinition, JoinDefinition};
+use async_trait::async_trait;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Fails until the downstream service is marked healthy.
+#[derive(Debug, Default)]
+struct DownstreamAction {
+    healthy: AtomicBool,
+    calls: AtomicUsize,
+}
+
+#[async_trait]
+impl FunctionHandler for DownstreamAction {
+    fn name(&self) -> &str { "downstream" }
+    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    async fn execute(&self, _params: Value, _ctx: &Context) -> Result<Value> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if self.healthy.load(Ordering::SeqCst) {
+            Ok(json!("ok"))
+        } else {
+            Err(anyhow!("Service unavailable"))
+        }
+    }
+}
+
+fn engine(action: Arc<DownstreamAction>, store: Arc<InMemoryStateStore>) -> Arc<Engine> {
+    let retry = RetryPolicy { max_attempts: 2, initial_delay: 10, ..Default::default() };
+    engine_with_retry(action, store, Some(retry))
+}
+
+fn engine_with_retry(action: Arc<DownstreamAction>, store: Arc<InMemoryStateStore>, retry: Option<RetryPolicy>) -> Arc<Engine> {
+    let mut workflow = WorkflowBuilder::new("dlq-test")
+        .start("start")
+        .function("call", "downstream").output("result").build()
+        .end("end", "result")
+        .connect("start", "call")
+        .connect("call", "end")
+        .build();
+    workflow.nodes.iter_mut().find(|n| n.id == "call").unwrap().retry = retry;
+    let blueprint = Compiler::new().compile(workflow).expect("Compilation failed");
+
+    let mut engine = Engine::new_with_storage(store, Arc::new(InMemoryTaskQueue::new()));
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_function(action);
+    engine.register_blueprint(blueprint);
+    Arc::new(engine)
+}
+
+#[tokio::test]
+async fn test_exhausted_task_is_dead_lettered_and_replayed() {
+    let action = Arc::new(DownstreamAction::default());
+    let engine = engine(action.clone(), Arc::new(InMemoryStateStore::new()));
+    let workers = engine.spawn_workers(1);
+
+    let instance_id = engine.start_workflow("dlq-test", HashMap::new()).await.unwrap();
+    let status = engine.await_completion(instance_id, Duration::from_secs(5)).await.unwrap();
+    assert_eq!(status, InstanceStatus::Failed);
+    assert_eq!(action.calls.load(Ordering::SeqCst), 2);
+
+    let letters = engine.dead_letters().await.unwrap();
+    assert_eq!(letters.len(), 1);
+    assert_eq!(letters[0].node_id.as_deref(), Some("call"));
+    assert_eq!(letters[0].error, "Service unavailable");
+    assert_eq!(letters[0].task().unwrap().instance_id, instance_id);
+
+    // Fix the downstream service and re-drive the failed work
+    action.healthy.store(true, Ordering::SeqCst);
+    assert!(engine.replay_dead_letter(letters[0].id).await.unwrap());
+    assert!(!engine.replay_dead_letter(letters[0].id).await.unwrap());
+    assert!(engine.get_failure(instance_id).await.unwrap().is_none());
+
+    let status = engine.await_completion(instance_id, Duration::from_secs(5)).await.unwrap();
+    assert_eq!(status, InstanceStatus::Completed);
+    assert_eq!(engine.get_output(instance_id).await.unwrap(), Some(json!("ok")));
+    assert!(engine.dead_letters().await.unwrap().is_empty());
+
+    workers.abort();
+}
+
+#[tokio::test]
+async fn test_only_exhausted_retries_are_dead_lettered() {
+    // No retry policy, and a policy that does not retry this kind of error
+    let policies = [None, Some(RetryPolicy { max_attempts: 3, initial_delay: 10, retry_on: vec![ErrorKind::Timeout], ..Default::default() })];
+    for retry in policies {
+        let action = Arc::new(DownstreamAction::default());
+        let engine = engine_with_retry(action.clone(), Arc::new(InMemoryStateStore::new()), retry);
+        let workers = engine.spawn_workers(1);
+
+        let instance_id = engine.start_workflow("dlq-test", HashMap::new()).await.unwrap();
+        let status = engine.await_completion(instance_id, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(status, InstanceStatus::Failed);
+        assert_eq!(action.calls.load(Ordering::SeqCst), 1);
+        assert!(engine.dead_letters().await.unwrap().is_empty());
+
+        workers.abort();
+    }
+}
+
+#[tokio::test]
+async fn test_replay_refuses_finished_instances_and_purge() {
+    let action = Arc::new(DownstreamAction::default());
+    let store = Arc::new(InMemoryStateStore::new());
+    let engine = engine(action.clone(), store.clone());
+    let workers = engine.spawn_workers(1);
+
+    let mut instances = Vec::new();
+    for _ in 0..2 {
+        let id = engine.start_workflow("dlq-test", HashMap::new()).await.unwrap();
+        engine.await_completion(id, Duration::from_secs(5)).await.unwrap();
+        instances.push(id);
+    }
+    let letters = engine.dead_letters().await.unwrap();
+    assert_eq!(letters.len(), 2);
+
+    // Only failed instances are reopened
+    let cancelled = letters.iter().find(|l| l.task().unwrap().instance_id == instances[0]).unwrap();
+    store.set_status(instances[0], InstanceStatus::Cancelled).await.unwrap();
+    assert!(engine.replay_dead_letter(cancelled.id).await.is_err());
+    assert_eq!(engine.dead_letters().await.unwrap().len(), 2);
+
+    assert!(!engine.replay_dead_letter(Uuid::new_v4()).await.unwrap());
+    assert_eq!(engine.purge_dead_letters().await.unwrap(), 2);
+    assert!(engine.dead_letters().await.unwrap().is_empty());
+
+    workers.abort();
+}
+
+#[tokio::test]
+async fn test_replay_refuses_task_inside_parallel_branch() {
+    // Start -> Parallel [downstream] [assign] -> End
+    let function_node = |id: &str, name: &str| Node {
+        retry: Some(RetryPolicy { max_attempts: 2, initial_delay: 10, ..Default::default() }),
+        ..Node::new(id, NodeType::Function { name: name.to_string(), params: HashMap::new(), output: None })
+    };
+    let workflow = WorkflowBuilder::new("dlq-parallel-test")
+        .start("start")
+        .parallel("p1", vec![vec![function_node("call", "downstream")], vec![function_node("other", "assign")]])
+        .end("end", "")
+        .connect("start", "p1")
+        .connect("p1", "end")
+        .build();
+    let blueprint = Compiler::new().compile(workflow).expect("Compilation failed");
+
+    let action = Arc::new(DownstreamAction::default());
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_node(Box::new(ForkDefinition));
+    engine.register_node(Box::new(JoinDefinition));
+    engine.register_function(action.clone());
+    engine.register_function(Arc::new(AssignAction));
+    engine.register_blueprint(blueprint);
+    let engine = Arc::new(engine);
+    let workers = engine.spawn_workers(1);
+
+    let instance_id = engine.start_workflow("dlq-parallel-test", HashMap::new()).await.unwrap();
+    let status = engine.await_completion(instance_id, Duration::from_secs(5)).await.unwrap();
+    assert_eq!(status, InstanceStatus::Failed);
+
+    // The sibling branch may have been dropped, so the Join could never fire
+    action.healthy.store(true, Ordering::SeqCst);
+    let letters = engine.dead_letters().await.unwrap();
+    assert_eq!(letters.len(), 1);
+    let error = engine.replay_dead_letter(letters[0].id).await.unwrap_err();
+    assert!(error.to_string().contains("Parallel branch"), "{}", error);
+
+    // Nothing changed: the letter is kept and the instance stays Failed
+    assert_eq!(engine.dead_letters().await.unwrap().len(), 1);
+    assert_eq!(engine.get_status(instance_id).await.unwrap(), Some(InstanceStatus::Failed));
+    assert!(engine.get_failure(instance_id).await.unwrap().is_some());
+
+    workers.abort();
+}
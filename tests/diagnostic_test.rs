@@ -0,0 +1,98 @@
+use skript::compiler::core::Compiler;
+use skript::compiler::diagnostic::{Diagnostic, Severity};
+use skript::compiler::loader;
+use std::fs;
+
+fn write_workflow(dir: &tempfile::TempDir, yaml: &str) -> String {
+    let file_path = dir.path().join("workflow.yaml");
+    fs::write(&file_path, yaml).expect("Failed to write temp file");
+    file_path.to_string_lossy().to_string()
+}
+
+#[test]
+fn test_unknown_target_points_at_reference() {
+    let yaml = r#"id: "broken-flow"
+nodes:
+  - id: "start"
+    type: "Start"
+  - id: "end"
+    type: "End"
+edges:
+  - source: "start"
+    target: "finish"
+"#;
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = write_workflow(&dir, yaml);
+
+    let (workflow, sources) = loader::load_workflow_with_source_map(&path).expect("Failed to load workflow");
+    let mut compiler = Compiler::new();
+    let err = compiler.compile(workflow).expect_err("Unknown target should fail");
+
+    let mut diagnostic = Diagnostic::from_error(&err);
+    assert_eq!(compiler.diagnostics(), std::slice::from_ref(&diagnostic));
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(diagnostic.code, "unknown-target");
+    assert_eq!(diagnostic.node_id.as_deref(), Some("finish"));
+
+    sources.locate(&mut diagnostic);
+    let span = diagnostic.span.clone().expect("Diagnostic should be located");
+    assert_eq!((span.line, span.column, span.length), (9, 13, 8));
+
+    let rendered = diagnostic.render(Some(&sources));
+    assert!(rendered.starts_with("error[unknown-target]: "));
+    assert!(rendered.contains(&format!("--> {}:9:13", path)));
+    assert!(rendered.ends_with("9 |     target: \"finish\"\n  |             ^^^^^^^^"), "{}", rendered);
+
+    let json = serde_json::to_value(&diagnostic).unwrap();
+    assert_eq!(json["severity"], "error");
+    assert_eq!(json["node_id"], "finish");
+    assert_eq!(json["span"]["line"], 9);
+}
+
+#[test]
+fn test_yaml_errors_carry_location() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = write_workflow(&dir, "id: \"bad\"\nnodes:\n  - id: \"start\"\n    type: [unclosed\n");
+
+    let err = loader::load_workflow_with_source_map(&path).expect_err("Syntax error should fail");
+    let diagnostic = Diagnostic::from_error(&err);
+    assert_eq!(diagnostic.code, "yaml-syntax");
+    let span = diagnostic.span.expect("Syntax errors should be located");
+    assert_eq!(span.file, path);
+    assert!(span.line >= 4);
+
+    let path = write_workflow(&dir, "id: \"bad\"\nnodes:\n  - id: \"start\"\n    type: \"Teleport\"\nedges: []\n");
+    let err = loader::load_workflow_with_source_map(&path).expect_err("Unknown node type should fail");
+    let diagnostic = Diagnostic::from_error(&err);
+    assert_eq!(diagnostic.code, "invalid-workflow");
+    assert!(diagnostic.span.is_some());
+}
+
+#[test]
+fn test_lint_findings_are_warnings() {
+    let yaml = r#"id: "lint-flow"
+nodes:
+  - id: "start"
+    type: "Start"
+    next: "end"
+  - id: "orphan"
+    type: "Function"
+    name: "log"
+    next: "end"
+  - id: "end"
+    type: "End"
+"#;
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = write_workflow(&dir, yaml);
+
+    let (workflow, sources) = loader::load_workflow_with_source_map(&path).expect("Failed to load workflow");
+    let mut compiler = Compiler::new();
+    compiler.compile(workflow).expect("Lint warnings should not fail compilation");
+
+    let mut diagnostics = compiler.diagnostics().to_vec();
+    assert_eq!(diagnostics.len(), 1);
+    sources.locate(&mut diagnostics[0]);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].code, "unreachable");
+    assert_eq!(diagnostics[0].span.as_ref().map(|s| s.line), Some(6));
+}
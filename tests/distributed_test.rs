@@ -14,7 +14,7 @@ use serde_json::Value;
 fn build_worker_binary() {
     println!("Building distributed_worker example...");
     let status = Command::new("cargo")
-        .args(&["build", "--example", "distributed_worker"]) 
+        .args(["build", "--example", "distributed_worker"]) 
         .status()
         .expect("Failed to build worker example");
     assert!(status.success(), "Build failed");
@@ -74,7 +74,6 @@ async fn test_multi_process_execution() {
     let workflow_def = load_workflow_from_yaml(workflow_path).unwrap();
     let mut compiler = Compiler::new();
     let blueprint = compiler.compile(workflow_def).unwrap();
-    let start_index = blueprint.start_index;
     let workflow_id = blueprint.id.clone();
 
     let store = Arc::new(RedisStateStore::new(client.clone()));
@@ -113,11 +112,9 @@ async fn test_multi_process_execution() {
         let info1 = engine.get_instance_var(instance_id, "worker_info_1").await;
         let info2 = engine.get_instance_var(instance_id, "worker_info_2").await;
         
-        if info1.is_some() && info2.is_some() {
+        if let (Some(v1), Some(v2)) = (info1, info2) {
             println!("Execution finished!");
             
-            let v1 = info1.unwrap();
-            let v2 = info2.unwrap();
             println!("Worker 1 Info: {:?}", v1);
             println!("Worker 2 Info: {:?}", v2);
             
@@ -160,6 +157,8 @@ async fn test_multi_process_execution() {
     // 6. Cleanup
     let _ = worker1.kill();
     let _ = worker2.kill();
+    let _ = worker1.wait();
+    let _ = worker2.wait();
 
     assert!(success, "Workflow execution timed out or failed");
 }
@@ -1,6 +1,9 @@
 use skript::dsl::builder::WorkflowBuilder;
 use skript::compiler::core::Compiler;
 use skript::runtime::engine::Engine;
+use skript::runtime::context::Context;
+use skript::runtime::instance::InstanceStatus;
+use skript::actions::FunctionHandler;
 use skript::actions::builtin::{LogAction, AssignAction};
 use skript::nodes::common::{StartDefinition, EndDefinition};
 use skript::nodes::flow::{IfDefinition, ForkDefinition, JoinDefinition};
@@ -9,7 +12,8 @@ use skript::dsl::NodeType;
 use std::collections::HashMap;
 use std::time::Duration;
 use std::sync::Arc;
-use serde_json::json;
+use serde_json::{json, Value};
+use async_trait::async_trait;
 
 #[tokio::test]
 async fn test_engine_linear_execution() {
@@ -122,25 +126,19 @@ async fn test_engine_if_branching() {
 #[tokio::test]
 async fn test_engine_parallel_join() {
     let branch1 = vec![
-        Node { 
-            id: "B1".to_string(), 
-            kind: NodeType::Function { 
-                name: "assign".to_string(), 
-                params: HashMap::from([("value".to_string(), json!(true))]), 
-                output: Some("b1".to_string()) 
-            } 
-        }
+        Node::new("B1", NodeType::Function { 
+            name: "assign".to_string(), 
+            params: HashMap::from([("value".to_string(), json!(true))]), 
+            output: Some("b1".to_string()) 
+        })
     ];
     
     let branch2 = vec![
-        Node { 
-            id: "B2".to_string(), 
-            kind: NodeType::Function { 
-                name: "assign".to_string(), 
-                params: HashMap::from([("value".to_string(), json!(true))]), 
-                output: Some("b2".to_string()) 
-            } 
-        }
+        Node::new("B2", NodeType::Function { 
+            name: "assign".to_string(), 
+            params: HashMap::from([("value".to_string(), json!(true))]), 
+            output: Some("b2".to_string()) 
+        })
     ];
 
     let workflow = WorkflowBuilder::new("engine-test-parallel")
@@ -185,3 +183,61 @@ async fn test_engine_parallel_join() {
     assert_eq!(b2, Some(json!(true)), "Branch 2 should execute");
     assert_eq!(status, Some(json!("done")), "Flow should pass join and reach end");
 }
+
+/// Returns the params it was called with.
+#[derive(Debug)]
+struct EchoAction;
+
+#[async_trait]
+impl FunctionHandler for EchoAction {
+    fn name(&self) -> &str { "echo" }
+    fn validate(&self, params: &Value) -> anyhow::Result<()> {
+        anyhow::ensure!(params.get("_policy").is_none(), "Policies leaked into the params");
+        Ok(())
+    }
+    async fn execute(&self, params: Value, _ctx: &Context) -> anyhow::Result<Value> {
+        Ok(params)
+    }
+}
+
+#[tokio::test]
+async fn test_params_named_like_policies_reach_the_handler() {
+    let workflow = WorkflowBuilder::new("policy-names-test")
+        .start("start")
+        .function("call", "echo")
+            .param("timeout", "5s")
+            .param("retry", "never")
+            .param("on_error", 0)
+            .param("compensate", 0)
+            .timeout(5000)
+            .output("result")
+            .build()
+        .end("end", "result")
+        .connect("start", "call")
+        .connect("call", "end")
+        .build();
+
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_function(Arc::new(EchoAction));
+    let blueprint = Compiler::new().with_registry(engine.node_registry()).compile(workflow).expect("Compilation failed");
+    let call = blueprint.nodes.iter().find(|n| n.id == "call").unwrap();
+    assert_eq!(call.policy("timeout"), Some(&json!(5000)));
+    assert_eq!(call.policy("on_error"), None);
+    engine.register_blueprint(blueprint);
+    let engine = Arc::new(engine);
+
+    let workers = engine.spawn_workers(1);
+    let instance_id = engine.start_workflow("policy-names-test", HashMap::new()).await.unwrap();
+    let status = engine.await_completion(instance_id, Duration::from_secs(5)).await.unwrap();
+    workers.abort();
+
+    assert_eq!(status, InstanceStatus::Completed);
+    let output = engine.get_output(instance_id).await.unwrap().unwrap();
+    assert_eq!(output["timeout"], json!("5s"));
+    assert_eq!(output["retry"], json!("never"));
+    assert_eq!(output["on_error"], json!(0));
+    assert_eq!(output["compensate"], json!(0));
+    assert!(output.get("_policy").is_none());
+}
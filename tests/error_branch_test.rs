@@ -0,0 +1,108 @@
+mod common;
+
+use common::BrokenAction;
+use skript::dsl::builder::WorkflowBuilder;
+use skript::compiler::core::Compiler;
+use skript::runtime::engine::Engine;
+use skript::runtime::instance::{InstanceStatus, ERROR_VAR};
+use skript::runtime::retry::RetryPolicy;
+use skript::actions::builtin::AssignAction;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+async fn run(builder: WorkflowBuilder) -> (Arc<Engine>, uuid::Uuid, InstanceStatus, usize) {
+    let workflow = builder.build();
+    let blueprint_id = workflow.id.clone();
+
+    let mut compiler = Compiler::new();
+    let blueprint = compiler.compile(workflow).expect("Compilation failed");
+
+    let broken = BrokenAction::default();
+    let calls = broken.calls.clone();
+    let engine = common::engine(blueprint, vec![Arc::new(AssignAction), Arc::new(broken)]);
+
+    let instance_id = engine.start_workflow(&blueprint_id, HashMap::new())
+        .await
+        .expect("Failed to start workflow");
+
+    let worker_engine = engine.clone();
+    let worker = tokio::spawn(async move {
+        worker_engine.run_worker().await;
+    });
+
+    let status = engine.await_completion(instance_id, Duration::from_secs(5))
+        .await
+        .expect("Instance did not reach a terminal state");
+    worker.abort();
+
+    (engine, instance_id, status, calls.load(Ordering::SeqCst))
+}
+
+#[tokio::test]
+async fn test_error_edge_catches_failure() {
+    let builder = WorkflowBuilder::new("catch-test")
+        .start("start")
+        .function("call", "broken").output("result").build()
+        .function("fallback", "assign").param("value", "fallback").output("result").build()
+        .end("end", "result")
+        .connect("start", "call")
+        .connect("call", "end")
+        .connect_error("call", "fallback")
+        .connect("fallback", "end");
+
+    let (engine, instance_id, status, calls) = run(builder).await;
+
+    assert_eq!(status, InstanceStatus::Completed);
+    assert_eq!(calls, 1);
+    assert_eq!(engine.get_output(instance_id).await.unwrap(), Some(json!("fallback")));
+
+    let error = engine.get_instance_var(instance_id, ERROR_VAR).await.expect("_error not written");
+    assert_eq!(error["message"], "Downstream call failed");
+    assert_eq!(error["node_id"], "call");
+    assert_eq!(error["kind"], "other");
+}
+
+#[tokio::test]
+async fn test_error_edge_runs_after_retries() {
+    let retry = RetryPolicy { max_attempts: 3, initial_delay: 10, ..Default::default() };
+    let builder = WorkflowBuilder::new("catch-retry-test")
+        .start("start")
+        .function("call", "broken").output("result").retry(retry).build()
+        .function("fallback", "assign").param("value", "fallback").output("result").build()
+        .end("end", "result")
+        .connect("start", "call")
+        .connect("call", "end")
+        .connect_error("call", "fallback")
+        .connect("fallback", "end");
+
+    let (_, _, status, calls) = run(builder).await;
+
+    assert_eq!(status, InstanceStatus::Completed);
+    assert_eq!(calls, 3);
+}
+
+#[test]
+fn test_error_edge_is_not_next() {
+    // The error edge is declared first, it must still not be taken as `next`
+    let workflow = WorkflowBuilder::new("catch-compile-test")
+        .start("start")
+        .function("call", "broken").build()
+        .function("fallback", "assign").param("value", 1).build()
+        .end("end", "")
+        .connect("start", "call")
+        .connect_error("call", "fallback")
+        .connect("call", "end")
+        .connect("fallback", "end")
+        .build();
+
+    let mut compiler = Compiler::new();
+    let blueprint = compiler.compile(workflow).expect("Compilation failed");
+
+    let index_of = |id: &str| blueprint.nodes.iter().position(|n| n.id == id).unwrap();
+    let call = &blueprint.nodes[index_of("call")];
+    assert_eq!(call.params["next"], json!(index_of("end")));
+    assert_eq!(call.policy("on_error"), Some(&json!(index_of("fallback"))));
+}
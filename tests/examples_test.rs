@@ -101,6 +101,11 @@ async fn test_example_complex_flow() {
     run_example("complex_flow.yaml").await;
 }
 
+#[tokio::test]
+async fn test_example_error_handling() {
+    run_example("error_handling.yaml").await;
+}
+
 #[tokio::test]
 async fn test_example_function_node() {
     run_example("function_node.yaml").await;
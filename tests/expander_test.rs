@@ -8,12 +8,12 @@ fn test_expand_parallel_node() {
     // 1. Build Workflow with Parallel Node
     // Start -> Parallel(p1) [Branch1: A, Branch2: B->C] -> End
     let branch1 = vec![
-        Node { id: "A".to_string(), kind: NodeType::Function { name: "log".to_string(), params: Default::default(), output: None } }
+        Node::new("A", NodeType::Function { name: "log".to_string(), params: Default::default(), output: None })
     ];
     
     let branch2 = vec![
-        Node { id: "B".to_string(), kind: NodeType::Function { name: "log".to_string(), params: Default::default(), output: None } },
-        Node { id: "C".to_string(), kind: NodeType::Function { name: "log".to_string(), params: Default::default(), output: None } }
+        Node::new("B", NodeType::Function { name: "log".to_string(), params: Default::default(), output: None }),
+        Node::new("C", NodeType::Function { name: "log".to_string(), params: Default::default(), output: None })
     ];
 
     let workflow = WorkflowBuilder::new("parallel-expand-test")
@@ -59,4 +59,176 @@ fn test_expand_parallel_node() {
     // 4. Branch Tails -> Join: A -> p1_join, C -> p1_join
     assert!(expanded_workflow.edges.iter().any(|e| e.source == "A" && e.target == "p1_join"));
     assert!(expanded_workflow.edges.iter().any(|e| e.source == "C" && e.target == "p1_join"));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_expand_node_shorthand() {
+    let workflow: skript::dsl::Workflow = serde_yaml::from_str(r#"
+id: "shorthand-test"
+nodes:
+  - id: "start"
+    type: "Start"
+    next: "check"
+  - id: "check"
+    type: "If"
+    branches:
+      - condition: "${ready} == true"
+    else: "items"
+  - id: "items"
+    type: "Iteration"
+    collection: "${list}"
+    item_var: "item"
+    body: "work"
+    next: "end"
+  - id: "work"
+    type: "Function"
+    name: "log"
+    next: "items"
+    on_error: "end"
+  - id: "end"
+    type: "End"
+edges:
+  - source: "check"
+    target: "items"
+    branch_index: 0
+  # Same jump as the shorthand: not duplicated
+  - source: "start"
+    target: "check"
+"#).unwrap();
+
+    let expanded = Expander::new().expand(workflow).expect("Expansion failed");
+    let edge = |source: &str, target: &str, branch_type: Option<&str>| {
+        expanded.edges.iter()
+            .filter(|e| e.source == source && e.target == target && e.branch_type.as_deref() == branch_type)
+            .count()
+    };
+
+    assert_eq!(edge("start", "check", None), 1);
+    assert_eq!(edge("check", "items", Some("else")), 1);
+    assert_eq!(edge("items", "work", Some("body")), 1);
+    assert_eq!(edge("items", "end", None), 1);
+    assert_eq!(edge("work", "items", None), 1);
+    assert_eq!(edge("work", "end", Some("error")), 1);
+    assert!(expanded.nodes.iter().all(|n| n.next.is_none() && n.else_next.is_none() && n.body.is_none() && n.on_error.is_none()));
+}
+
+#[test]
+fn test_shorthand_conflicting_with_edge_is_rejected() {
+    let mut workflow = WorkflowBuilder::new("conflict-test")
+        .start("start")
+        .end("a", "")
+        .end("b", "")
+        .connect("start", "a")
+        .build();
+    workflow.nodes[0].next = Some("b".to_string());
+
+    let err = Expander::new().expand(workflow).unwrap_err();
+    assert_eq!(err.to_string(), "Node start declares `next: b` but an edge routes it to a");
+}
+
+#[test]
+fn test_yaml_examples_compile_connected() {
+    use skript::compiler::core::Compiler;
+    use skript::compiler::loader::load_workflow_from_yaml;
+
+    for file in ["dsl_examples/simple_parallel.yaml", "dsl_examples/complex_flow.yaml"] {
+        let workflow = load_workflow_from_yaml(file).unwrap();
+        let blueprint = Compiler::new().compile(workflow).unwrap();
+        let start = &blueprint.nodes[blueprint.start_index];
+        assert!(start.params["next"].is_u64(), "{} start node is not connected", file);
+    }
+}
+
+#[test]
+fn test_branch_subgraph_converges_at_join() {
+    let workflow: skript::dsl::Workflow = serde_yaml::from_str(r#"
+id: "branch-graph-test"
+nodes:
+  - id: "start"
+    type: "Start"
+    next: "par"
+  - id: "par"
+    type: "Parallel"
+    next: "end"
+    branches:
+      - nodes:
+          - id: "check"
+            type: "If"
+            else: "small"
+          - id: "large"
+            type: "Function"
+            name: "log"
+            next: "audit"
+          - id: "small"
+            type: "Function"
+            name: "log"
+          - id: "audit"
+            type: "Function"
+            name: "log"
+        edges:
+          - source: "check"
+            target: "large"
+            condition: "${amount} > 100"
+      - nodes:
+          - id: "notify"
+            type: "Function"
+            name: "log"
+  - id: "end"
+    type: "End"
+"#).unwrap();
+
+    let expanded = Expander::new().expand(workflow).expect("Expansion failed");
+    let targets = |source: &str| -> Vec<&str> {
+        expanded.edges.iter().filter(|e| e.source == source).map(|e| e.target.as_str()).collect()
+    };
+
+    assert_eq!(targets("check"), vec!["large", "small"]);
+    assert_eq!(targets("large"), vec!["audit"]);
+    assert_eq!(targets("small"), vec!["par_join"]);
+    assert_eq!(targets("audit"), vec!["par_join"]);
+    assert_eq!(targets("notify"), vec!["par_join"]);
+    assert_eq!(targets("par_join"), vec!["end"]);
+
+    let blueprint = skript::compiler::core::Compiler::new().compile(expanded).expect("Compilation failed");
+    let check = blueprint.nodes.iter().find(|n| n.id == "check").unwrap();
+    assert_eq!(check.params["branches"].as_array().unwrap().len(), 1);
+    assert!(check.params["else_next"].is_u64());
+}
+
+#[test]
+fn test_expand_nested_parallel() {
+    let log = |id: &str| Node::new(id, NodeType::Function { name: "log".to_string(), params: Default::default(), output: None });
+    let inner = Node::new("inner", NodeType::Parallel {
+        branches: vec![
+            skript::dsl::Branch { nodes: vec![log("a")], edges: Vec::new() },
+            skript::dsl::Branch { nodes: vec![log("b")], edges: Vec::new() },
+        ],
+    });
+
+    let workflow = WorkflowBuilder::new("nested-parallel-test")
+        .start("start")
+        .parallel("outer", vec![vec![inner, log("c")], vec![log("d")]])
+        .end("end", "")
+        .connect("start", "outer")
+        .connect("outer", "end")
+        .build();
+
+    let expanded = Expander::new().expand(workflow).expect("Expansion failed");
+    assert!(expanded.nodes.iter().all(|n| !matches!(n.kind, NodeType::Parallel { .. })));
+
+    let fork = expanded.nodes.iter().find(|n| n.id == "outer_fork").expect("Fork node not found");
+    let NodeType::Fork { branch_start_ids, .. } = &fork.kind else {
+        panic!("outer_fork is not a Fork node");
+    };
+    assert_eq!(branch_start_ids, &vec!["inner_fork".to_string(), "d".to_string()]);
+
+    let has_edge = |source: &str, target: &str| expanded.edges.iter().any(|e| e.source == source && e.target == target);
+    assert!(has_edge("a", "inner_join"));
+    assert!(has_edge("b", "inner_join"));
+    assert!(has_edge("inner_join", "c"));
+    assert!(has_edge("c", "outer_join"));
+    assert!(has_edge("d", "outer_join"));
+    assert!(!has_edge("inner_join", "outer_join"));
+
+    skript::compiler::core::Compiler::new().compile(expanded).expect("Compilation failed");
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::{BrokenAction, SlowAction};
+use skript::dsl::builder::WorkflowBuilder;
+use skript::dsl::{Node, NodeType};
+use skript::compiler::core::{Compiler, CompilerConfig};
+use skript::runtime::instance::InstanceStatus;
+use skript::actions::builtin::AssignAction;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn function_node(id: &str, name: &str, params: HashMap<String, Value>, output: Option<&str>) -> Node {
+    Node::new(id, NodeType::Function {
+        name: name.to_string(),
+        params,
+        output: output.map(|s| s.to_string()),
+    })
+}
+
+#[tokio::test]
+async fn test_failed_branch_fails_instance_and_stops_siblings() {
+    // Start -> Parallel [broken] [assign -> assign] -> End
+    let failing_branch = vec![function_node("fail", "broken", HashMap::new(), None)];
+    let healthy_branch = vec![
+        function_node("first", "assign", HashMap::from([("value".to_string(), json!(1))]), Some("first_done")),
+        function_node("second", "assign", HashMap::from([("value".to_string(), json!(2))]), Some("second_done")),
+    ];
+
+    let workflow = WorkflowBuilder::new("failure-test")
+        .start("start")
+        .parallel("p1", vec![failing_branch, healthy_branch])
+        .end("end", "")
+        .connect("start", "p1")
+        .connect("p1", "end")
+        .build();
+
+    // Keep the healthy branch unfused so it needs more than one task
+    let mut compiler = Compiler::new_with_config(CompilerConfig { enable_fusion: false, ..Default::default() });
+    let blueprint = compiler.compile(workflow).expect("Compilation failed");
+    let failing_index = blueprint.nodes.iter().position(|n| n.kind == "broken").unwrap();
+
+    let engine = common::engine(blueprint, vec![Arc::new(AssignAction), Arc::new(BrokenAction::default())]);
+
+    let instance_id = engine.start_workflow("failure-test", HashMap::new())
+        .await
+        .expect("Failed to start workflow");
+
+    let worker_engine = engine.clone();
+    let worker = tokio::spawn(async move {
+        worker_engine.run_worker().await;
+    });
+
+    let status = engine.await_completion(instance_id, Duration::from_secs(5))
+        .await
+        .expect("Instance did not reach a terminal state");
+    assert_eq!(status, InstanceStatus::Failed);
+
+    let failure = engine.get_failure(instance_id).await.unwrap().expect("Failure not recorded");
+    assert_eq!(failure.node_index, Some(failing_index));
+    assert_eq!(failure.error, "Downstream call failed");
+    assert_eq!(failure.causes, vec!["Downstream call failed", "connection refused"]);
+    assert!(failure.failed_at > 0);
+
+    // Give the worker time to drain the remaining sibling tasks
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    worker.abort();
+
+    assert_eq!(engine.get_instance_var(instance_id, "second_done").await, None, "Sibling branch should be stopped");
+    assert_eq!(engine.get_status(instance_id).await.unwrap(), Some(InstanceStatus::Failed));
+}
+
+#[tokio::test]
+async fn test_failure_aborts_running_sibling() {
+    // Start -> Parallel [slow -> assign] [broken] -> End, with both branches running at once
+    let slow_branch = vec![
+        function_node("slow", "slow", HashMap::new(), Some("slow_done")),
+        function_node("after_slow", "assign", HashMap::from([("value".to_string(), json!(1))]), Some("after_slow_done")),
+    ];
+    let failing_branch = vec![function_node("fail", "broken", HashMap::new(), None)];
+
+    let workflow = WorkflowBuilder::new("abort-sibling-test")
+        .start("start")
+        .parallel("p1", vec![slow_branch, failing_branch])
+        .end("end", "")
+        .connect("start", "p1")
+        .connect("p1", "end")
+        .build();
+    let mut compiler = Compiler::new_with_config(CompilerConfig { enable_fusion: false, ..Default::default() });
+    let blueprint = compiler.compile(workflow).expect("Compilation failed");
+
+    let slow = SlowAction::new(Duration::from_millis(300));
+    let engine = common::engine(blueprint, vec![Arc::new(AssignAction), Arc::new(BrokenAction::default()), Arc::new(slow)]);
+    let workers = engine.spawn_workers(2);
+
+    let instance_id = engine.start_workflow("abort-sibling-test", HashMap::new())
+        .await
+        .expect("Failed to start workflow");
+    let status = engine.await_completion(instance_id, Duration::from_secs(5))
+        .await
+        .expect("Instance did not reach a terminal state");
+    assert_eq!(status, InstanceStatus::Failed);
+
+    // Long enough for the slow handler to have finished had it not been aborted
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    workers.abort();
+
+    for var in ["slow_write", "slow_done", "after_slow_done"] {
+        assert_eq!(engine.get_instance_var(instance_id, var).await, None, "{} written after the instance failed", var);
+    }
+}
@@ -0,0 +1,90 @@
+use skript::compiler::core::Compiler;
+use skript::compiler::expander::Expander;
+use skript::compiler::graph::{Graph, Shape};
+use skript::compiler::loader::load_workflow_from_yaml;
+use skript::dsl::builder::WorkflowBuilder;
+use skript::dsl::{Node, NodeType};
+
+fn labelled_edges(graph: &Graph) -> Vec<(String, String, Option<String>)> {
+    graph.edges.iter().map(|e| (e.source.clone(), e.target.clone(), e.label.clone())).collect()
+}
+
+fn edge(source: &str, target: &str, label: Option<&str>) -> (String, String, Option<String>) {
+    (source.to_string(), target.to_string(), label.map(str::to_string))
+}
+
+#[test]
+fn test_parallel_stages() {
+    let workflow = load_workflow_from_yaml("dsl_examples/simple_parallel.yaml").expect("Failed to load workflow");
+
+    let dsl = Graph::from_workflow(&workflow);
+    assert_eq!(dsl.clusters.len(), 1);
+    assert_eq!(dsl.clusters[0].clusters[1].nodes, vec!["task_b".to_string()]);
+    assert!(labelled_edges(&dsl).contains(&edge("do_parallel_tasks", "task_a", Some("branch 0"))));
+    let dot = dsl.to_dot();
+    assert!(dot.contains("subgraph cluster_1 {"));
+    assert!(dot.contains("\"do_parallel_tasks\" [label=\"do_parallel_tasks\\nparallel\", shape=trapezium];"));
+
+    let expanded = Graph::from_workflow(&Expander::new().expand(workflow).unwrap());
+    assert!(expanded.clusters.is_empty());
+    let edges = labelled_edges(&expanded);
+    assert!(edges.contains(&edge("do_parallel_tasks_fork", "task_b", Some("branch 1"))));
+    assert!(edges.contains(&edge("task_b", "do_parallel_tasks_join", None)));
+    assert!(edges.contains(&edge("do_parallel_tasks_join", "final_log", None)));
+    assert!(expanded.edges.iter().any(|e| e.label.as_deref() == Some("join") && e.dashed));
+    assert_eq!(expanded.nodes.iter().find(|n| n.id == "do_parallel_tasks_join").map(|n| n.shape), Some(Shape::Join));
+}
+
+#[test]
+fn test_condition_and_loop_labels() {
+    let mut workflow = WorkflowBuilder::new("labelled-flow")
+        .start("start")
+        .if_node("check")
+        .function("work", "log").build()
+        .end("end", "")
+        .connect("start", "check")
+        .connect_if("check", "repeat", "${n} > 0")
+        .connect_else("check", "end")
+        .connect("work", "repeat")
+        .build();
+    workflow.nodes.push(Node {
+        body: Some("work".to_string()),
+        next: Some("end".to_string()),
+        ..Node::new("repeat", NodeType::Loop { condition: "${n} > 0".to_string() })
+    });
+
+    let dsl = Graph::from_workflow(&workflow);
+    let edges = labelled_edges(&dsl);
+    assert!(edges.contains(&edge("check", "repeat", Some("${n} > 0"))));
+    assert!(edges.contains(&edge("check", "end", Some("else"))));
+    assert!(edges.contains(&edge("repeat", "work", Some("body"))));
+
+    let blueprint = Compiler::new().compile(workflow).expect("Compilation failed");
+    let compiled = Graph::from_blueprint(&blueprint);
+    assert_eq!(labelled_edges(&compiled).iter().filter(|e| e.2.is_some()).count(), 3);
+
+    let mermaid = compiled.to_mermaid();
+    assert!(mermaid.starts_with("flowchart TD\n"));
+    assert!(mermaid.contains("-->|\"${n} #gt; 0\"|"));
+    assert!(mermaid.contains("{\"check\"}"));
+}
+
+#[test]
+fn test_blueprint_shows_fused_nodes() {
+    let workflow = WorkflowBuilder::new("fused-flow")
+        .start("start")
+        .function("first", "log").build()
+        .function("second", "assign").build()
+        .end("end", "")
+        .connect("start", "first")
+        .connect("first", "second")
+        .connect("second", "end")
+        .build();
+    let blueprint = Compiler::new().compile(workflow).expect("Compilation failed");
+
+    let graph = Graph::from_blueprint(&blueprint);
+    let fused = graph.nodes.iter().find(|n| n.shape == Shape::Fused).expect("Expected a fused node");
+    assert_eq!(fused.label, "first\nfused: log → assign");
+    assert_eq!(labelled_edges(&graph), vec![edge("start", "first", None), edge("first", "end", None)]);
+    assert!(graph.to_mermaid().contains("[[\"first<br/>fused: log → assign\"]]"));
+}
@@ -0,0 +1,89 @@
+use skript::dsl::Workflow;
+use skript::dsl::builder::WorkflowBuilder;
+use skript::compiler::core::Compiler;
+use skript::runtime::engine::Engine;
+use skript::runtime::input::{InputSpec, InputType};
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn run(workflow: Workflow, vars: HashMap<String, Value>) -> anyhow::Result<Option<Value>> {
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    let engine = Arc::new(engine);
+    engine.register_blueprint(Compiler::new().compile(workflow)?);
+
+    let instance_id = engine.start_workflow("inputs-flow", vars).await?;
+    let workers = engine.spawn_workers(1);
+    engine.await_completion(instance_id, Duration::from_secs(5)).await?;
+    workers.abort();
+    engine.get_output(instance_id).await
+}
+
+fn echo(builder: WorkflowBuilder, var: &str) -> Workflow {
+    builder
+        .start("start")
+        .end("end", var)
+        .connect("start", "end")
+        .build()
+}
+
+#[tokio::test]
+async fn test_variable_defaults_are_overridden_by_caller() {
+    let workflow = || echo(WorkflowBuilder::new("inputs-flow").var("greeting", "hello"), "greeting");
+
+    let output = run(workflow(), HashMap::new()).await.unwrap();
+    assert_eq!(output, Some(json!("hello")));
+
+    let vars = HashMap::from([("greeting".to_string(), json!("bonjour"))]);
+    let output = run(workflow(), vars).await.unwrap();
+    assert_eq!(output, Some(json!("bonjour")));
+}
+
+#[tokio::test]
+async fn test_declared_inputs_are_validated() {
+    let workflow = || echo(
+        WorkflowBuilder::new("inputs-flow")
+            .input("count", InputSpec { kind: InputType::Integer, required: true, default: None })
+            .input("label", InputSpec { kind: InputType::String, required: false, default: Some(json!("none")) }),
+        "label",
+    );
+
+    let err = run(workflow(), HashMap::new()).await.unwrap_err();
+    assert_eq!(err.to_string(), "Cannot start inputs-flow: Invalid inputs: missing required input `count`");
+
+    let vars = HashMap::from([
+        ("count".to_string(), json!("three")),
+        ("label".to_string(), json!(3)),
+    ]);
+    let err = run(workflow(), vars).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Cannot start inputs-flow: Invalid inputs: input `count` must be integer, got string; input `label` must be string, got number"
+    );
+
+    let vars = HashMap::from([("count".to_string(), json!(3))]);
+    let output = run(workflow(), vars).await.unwrap();
+    assert_eq!(output, Some(json!("none")));
+}
+
+#[test]
+fn test_inputs_parse_from_yaml() {
+    let workflow: Workflow = serde_yaml::from_str(r#"
+id: "inputs-flow"
+inputs:
+  user_id:
+    type: string
+    required: true
+  retries:
+    type: integer
+    default: 3
+nodes: []
+"#).unwrap();
+
+    assert_eq!(workflow.inputs["user_id"], InputSpec { kind: InputType::String, required: true, default: None });
+    assert_eq!(workflow.inputs["retries"], InputSpec { kind: InputType::Integer, required: false, default: Some(json!(3)) });
+}
@@ -0,0 +1,73 @@
+use skript::dsl::builder::WorkflowBuilder;
+use skript::compiler::core::Compiler;
+use skript::runtime::engine::Engine;
+use skript::runtime::instance::InstanceStatus;
+use skript::actions::builtin::AssignAction;
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use serde_json::json;
+
+fn setup_engine() -> Arc<Engine> {
+    let workflow = WorkflowBuilder::new("lifecycle-test")
+        .start("start")
+        .function("compute", "assign")
+            .param("value", 42)
+            .output("answer")
+            .build()
+        .end("end", "answer")
+        .connect("start", "compute")
+        .connect("compute", "end")
+        .build();
+
+    let mut compiler = Compiler::new();
+    let blueprint = compiler.compile(workflow).expect("Compilation failed");
+
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_function(Arc::new(AssignAction));
+    engine.register_blueprint(blueprint);
+
+    Arc::new(engine)
+}
+
+#[tokio::test]
+async fn test_instance_completes_with_output() {
+    let engine = setup_engine();
+
+    let instance_id = engine.start_workflow("lifecycle-test", HashMap::new())
+        .await
+        .expect("Failed to start workflow");
+
+    // No worker has picked up the initial task yet
+    let status = engine.get_status(instance_id).await.unwrap();
+    assert_eq!(status, Some(InstanceStatus::Pending));
+
+    let worker_engine = engine.clone();
+    let worker = tokio::spawn(async move {
+        worker_engine.run_worker().await;
+    });
+
+    let status = engine.await_completion(instance_id, Duration::from_secs(5))
+        .await
+        .expect("Instance did not complete");
+    worker.abort();
+
+    assert_eq!(status, InstanceStatus::Completed);
+    assert_eq!(engine.get_output(instance_id).await.unwrap(), Some(json!(42)));
+}
+
+#[tokio::test]
+async fn test_await_completion_times_out() {
+    let engine = setup_engine();
+
+    let instance_id = engine.start_workflow("lifecycle-test", HashMap::new())
+        .await
+        .expect("Failed to start workflow");
+
+    // Without a worker the instance never leaves Pending
+    let result = engine.await_completion(instance_id, Duration::from_millis(100)).await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,77 @@
+use skript::dsl::builder::WorkflowBuilder;
+use skript::dsl::{Node, NodeType, Workflow};
+use skript::compiler::core::{Compiler, CompilerConfig};
+use skript::compiler::lint::{Lint, LintLevel};
+
+fn lints(workflow: Workflow) -> Vec<(Lint, String)> {
+    let mut compiler = Compiler::new();
+    compiler.compile(workflow).expect("Compilation failed");
+    compiler.lint_findings().iter().map(|f| (f.lint, f.node_id.clone())).collect()
+}
+
+fn dangling_workflow() -> Workflow {
+    WorkflowBuilder::new("dangling-flow")
+        .start("start")
+        .function("a", "log").build()
+        .function("b", "log").build()
+        .function("orphan", "log").build()
+        .end("end", "")
+        .connect("start", "a")
+        .connect("a", "end")
+        .connect("a", "b")
+        .build()
+}
+
+#[test]
+fn test_connected_workflow_has_no_findings() {
+    let workflow = WorkflowBuilder::new("clean-flow")
+        .start("start")
+        .function("a", "log").build()
+        .end("end", "")
+        .connect("start", "a")
+        .connect("a", "end")
+        .build();
+    assert!(lints(workflow).is_empty());
+}
+
+#[test]
+fn test_graph_findings() {
+    let findings = lints(dangling_workflow());
+    assert_eq!(findings, vec![
+        (Lint::IgnoredEdges, "a".to_string()),
+        (Lint::Unreachable, "b".to_string()),
+        (Lint::Unreachable, "orphan".to_string()),
+    ]);
+
+    let mut workflow = WorkflowBuilder::new("branch-flow")
+        .start("start")
+        .if_node("check")
+        .function("dead_end", "log").build()
+        .end("end", "")
+        .connect("start", "check")
+        .connect_if("check", "loop", "${ready} == true")
+        .connect_if("check", "dead_end", "${ready} == false")
+        .build();
+    workflow.nodes.push(Node {
+        next: Some("end".to_string()),
+        ..Node::new("loop", NodeType::Loop { condition: "${ready} == true".to_string() })
+    });
+    let findings = lints(workflow);
+    assert_eq!(findings, vec![
+        (Lint::IfWithoutFallback, "check".to_string()),
+        (Lint::NoPathToEnd, "dead_end".to_string()),
+        (Lint::MissingBody, "loop".to_string()),
+    ]);
+}
+
+#[test]
+fn test_lint_levels() {
+    let config = CompilerConfig { lint_level: LintLevel::Deny, ..Default::default() };
+    let err = Compiler::new_with_config(config).compile(dangling_workflow()).unwrap_err();
+    assert!(err.to_string().starts_with("Workflow dangling-flow failed lint checks: [ignored_edges] Node a follows 1 of its 2 outgoing edges"), "{}", err);
+
+    let config = CompilerConfig { lint_level: LintLevel::Allow, ..Default::default() };
+    let mut compiler = Compiler::new_with_config(config);
+    compiler.compile(dangling_workflow()).unwrap();
+    assert!(compiler.lint_findings().is_empty());
+}
@@ -1,11 +1,6 @@
 use skript::dsl::builder::WorkflowBuilder;
 use skript::compiler::core::Compiler;
 use skript::runtime::engine::Engine;
-use skript::actions::builtin::{LogAction, AssignAction};
-use serde_json::json;
-use std::sync::Arc;
-use std::time::Instant;
-use uuid::Uuid;
 
 #[test]
 fn test_optimizer_fusion() {
@@ -101,9 +96,9 @@ async fn test_fusion_runtime_execution() {
         .build();
         
     let mut compiler = Compiler::new();
-    let blueprint = compiler.compile(workflow).unwrap();
+    let _blueprint = compiler.compile(workflow).unwrap();
     
-    let engine = Engine::new();
+    let _engine = Engine::new();
     // Register handlers
     // Note: The Engine needs to know how to execute "fused" nodes.
     // We need to register the FusedNode definition in the Engine?
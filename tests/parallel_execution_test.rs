@@ -44,15 +44,13 @@ async fn test_parallel_execution_performance() -> Result<()> {
     fn create_sleep_branch(id_suffix: &str) -> Branch {
         Branch {
             nodes: vec![
-                Node {
-                    id: format!("sleep_{}", id_suffix),
-                    kind: NodeType::Function { 
-                        name: "sleep".to_string(), 
-                        params: HashMap::new(), 
-                        output: None 
-                    }
-                }
-            ]
+                Node::new(format!("sleep_{}", id_suffix), NodeType::Function { 
+                    name: "sleep".to_string(), 
+                    params: HashMap::new(), 
+                    output: None 
+                })
+            ],
+            edges: Vec::new(),
         }
     }
 
@@ -61,38 +59,33 @@ async fn test_parallel_execution_performance() -> Result<()> {
         name: "Parallel Performance Test".to_string(),
         variables: HashMap::new(),
         nodes: vec![
-            Node { id: "start".to_string(), kind: NodeType::Start },
-            Node { 
-                id: "par".to_string(), 
-                kind: NodeType::Parallel { 
-                    branches: vec![
-                        create_sleep_branch("1"), 
-                        create_sleep_branch("2"), 
-                        create_sleep_branch("3"),
-                        create_sleep_branch("4"),
-                    ] 
-                } 
-            },
+            Node::new("start", NodeType::Start),
+            Node::new("par", NodeType::Parallel { 
+                branches: vec![
+                    create_sleep_branch("1"), 
+                    create_sleep_branch("2"), 
+                    create_sleep_branch("3"),
+                    create_sleep_branch("4"),
+                ] 
+            }),
             // Add a flag setting node to know when we are done
-            Node {
-                 id: "set_done".to_string(),
-                 kind: NodeType::Assign {
-                     assignments: vec![
-                         HashMap::from([
-                             ("key".to_string(), json!("finished")),
-                             ("value".to_string(), json!(true))
-                         ])
-                     ],
-                     expression: None
-                 }
-            },
-            Node { id: "end".to_string(), kind: NodeType::End { output: "finished".to_string() } }
+            Node::new("set_done", NodeType::Assign {
+                 assignments: vec![
+                     HashMap::from([
+                         ("key".to_string(), json!("finished")),
+                         ("value".to_string(), json!(true))
+                     ])
+                 ],
+                 expression: None
+             }),
+            Node::new("end", NodeType::End { output: "finished".to_string() })
         ],
         edges: vec![
             Edge { source: "start".to_string(), target: "par".to_string(), condition: None, branch_type: None, branch_index: None },
             Edge { source: "par".to_string(), target: "set_done".to_string(), condition: None, branch_type: None, branch_index: None },
             Edge { source: "set_done".to_string(), target: "end".to_string(), condition: None, branch_type: None, branch_index: None },
-        ]
+        ],
+        ..Default::default()
     };
 
     // 3. Compile & Register
@@ -128,11 +121,10 @@ async fn test_parallel_execution_performance() -> Result<()> {
     // Parallel execution (300ms + overhead) should pass easily.
     for _ in 0..10 { 
         tokio::time::sleep(Duration::from_millis(100)).await;
-        if let Some(val) = engine.get_instance_var(instance_id, "finished").await {
-             if val == json!(true) {
-                 finished = true;
-                 break;
-             }
+        if let Some(val) = engine.get_instance_var(instance_id, "finished").await
+            && val == json!(true) {
+            finished = true;
+            break;
         }
     }
     
@@ -148,3 +140,71 @@ async fn test_parallel_execution_performance() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_nested_parallel_with_branching_completes() -> Result<()> {
+    use skript::nodes::flow::IfDefinition;
+    use skript::runtime::instance::InstanceStatus;
+
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_node(Box::new(ForkDefinition));
+    engine.register_node(Box::new(JoinDefinition));
+    engine.register_node(Box::new(IfDefinition));
+    engine.register_function(Arc::new(AssignAction));
+
+    let workflow: Workflow = serde_yaml::from_str(r#"
+id: "nested-parallel-flow"
+nodes:
+  - id: "start"
+    type: "Start"
+    next: "outer"
+  - id: "outer"
+    type: "Parallel"
+    next: "done"
+    branches:
+      - nodes:
+          - id: "inner"
+            type: "Parallel"
+            branches:
+              - nodes:
+                  - { id: "set_a", type: "Assign", assignments: [{ key: "a", value: 1 }] }
+              - nodes:
+                  - { id: "set_b", type: "Assign", assignments: [{ key: "b", value: 2 }] }
+          - { id: "after_inner", type: "Assign", assignments: [{ key: "inner_done", value: true }] }
+      - nodes:
+          - id: "check"
+            type: "If"
+            else: "small"
+          - { id: "large", type: "Assign", assignments: [{ key: "size", value: "large" }] }
+          - { id: "small", type: "Assign", assignments: [{ key: "size", value: "small" }] }
+        edges:
+          - source: "check"
+            target: "large"
+            condition: "${amount} > 100"
+  - id: "done"
+    type: "Assign"
+    assignments: [{ key: "finished", value: true }]
+    next: "end"
+  - id: "end"
+    type: "End"
+    output: "size"
+"#)?;
+
+    let blueprint = Compiler::new().compile(workflow)?;
+    engine.register_blueprint(blueprint);
+    let engine = Arc::new(engine);
+    let workers = engine.spawn_workers(4);
+
+    let instance_id = engine.start_workflow("nested-parallel-flow", HashMap::from([("amount".to_string(), json!(500))])).await?;
+    let status = engine.await_completion(instance_id, Duration::from_secs(5)).await;
+    workers.abort();
+
+    assert_eq!(status?, InstanceStatus::Completed);
+    assert_eq!(engine.get_output(instance_id).await?, Some(json!("large")));
+    for (var, value) in [("a", json!(1)), ("b", json!(2)), ("inner_done", json!(true)), ("finished", json!(true))] {
+        assert_eq!(engine.get_instance_var(instance_id, var).await, Some(value), "{}", var);
+    }
+    Ok(())
+}
@@ -1,5 +1,7 @@
 use skript::runtime::engine::Engine;
-use skript::runtime::redis_storage::{RedisStateStore, RedisTaskQueue};
+use skript::runtime::redis_storage::{RedisBlueprintStore, RedisCommitter, RedisStateStore, RedisTaskQueue};
+use skript::runtime::storage::{versioned_blueprint_key, BlueprintStore, JoinArrival, StateStore, TaskCommit, TaskCommitter, TaskQueue};
+use skript::runtime::task::Task;
 use skript::actions::builtin::AssignAction;
 use skript::nodes::common::{StartDefinition, EndDefinition};
 use skript::nodes::flow::{IfDefinition, ForkDefinition, JoinDefinition};
@@ -10,6 +12,7 @@ use std::collections::HashMap;
 use std::time::Duration;
 use serde_json::json;
 use redis::Client;
+use uuid::Uuid;
 
 // Helper to get redis client from env config provided by user
 fn get_redis_client() -> Client {
@@ -125,3 +128,80 @@ async fn test_redis_distributed_execution() {
     let output = engine.get_instance_var(instance_id, "_WORKFLOW_OUTPUT").await;
     assert_eq!(output, Some(json!(11)));
 }
+
+#[tokio::test]
+#[ignore] // Requires Redis
+async fn test_redis_blueprint_versions_drain() {
+    let client = get_redis_client();
+    let mut conn = client.get_multiplexed_async_connection().await.expect("Failed to connect to Redis");
+    let _: () = redis::cmd("FLUSHDB").query_async(&mut conn).await.expect("Failed to flush db");
+
+    let store = RedisStateStore::new(client.clone());
+    let blueprints = RedisBlueprintStore::new(client.clone());
+    let compile = |value: i64| Compiler::new().compile(
+        WorkflowBuilder::new("redis-drain-flow")
+        .start("start")
+        .function("set", "assign").param("value", value).output("a").build()
+        .end("end", "a")
+        .connect("start", "set").connect("set", "end")
+        .build()
+    ).unwrap();
+    let v1 = compile(1);
+    let v2 = compile(2);
+    let v1_key = versioned_blueprint_key(&v1.id, &v1.version);
+
+    let instance_id = Uuid::new_v4();
+    store.track_blueprint_run(instance_id, &v1_key).await.unwrap();
+    store.track_blueprint_run(instance_id, &v1_key).await.unwrap();
+    assert_eq!(store.blueprint_runs(&v1_key).await.unwrap(), 1);
+    store.finish_blueprint_run(instance_id).await.unwrap();
+    store.finish_blueprint_run(instance_id).await.unwrap();
+    assert_eq!(store.blueprint_runs(&v1_key).await.unwrap(), 0);
+
+    blueprints.save_blueprint(&v1).await.unwrap();
+    assert!(!blueprints.remove_blueprint(&v1.id, &v1.version).await.unwrap(), "The latest version is kept");
+    blueprints.save_blueprint(&v2).await.unwrap();
+    assert!(blueprints.remove_blueprint(&v1.id, &v1.version).await.unwrap());
+    assert!(blueprints.get_blueprint(&v1.id, Some(&v1.version)).await.unwrap().is_none());
+    assert_eq!(blueprints.get_blueprint(&v2.id, None).await.unwrap().unwrap().version, v2.version);
+}
+
+#[tokio::test]
+#[ignore] // Requires Redis
+async fn test_redis_join_arrivals_are_counted_once_per_branch() {
+    let client = get_redis_client();
+    let store = RedisStateStore::new(client.clone());
+    let queue = RedisTaskQueue::new(client, format!("skript:test:join:{}", Uuid::new_v4()));
+    let committer = RedisCommitter::new(&store, &queue);
+    let instance_id = Uuid::new_v4();
+    let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let arrive = |branch: Uuid| TaskCommit {
+        instance_id,
+        join: Some(JoinArrival { node_index: 2, token_id: branch, expect_count: 2 }),
+        tasks: vec![Task {
+            instance_id,
+            workflow_id: "redis-join-once".to_string(),
+            blueprint_version: None,
+            token_id: branch,
+            node_index: 3,
+            flow_id: Uuid::nil(),
+            attempt: 0,
+            deadline: None,
+            compensation: false,
+        }],
+        ..Default::default()
+    };
+
+    committer.commit(arrive(first)).await.unwrap();
+    committer.commit(arrive(first)).await.unwrap();
+    assert!(queue.pop().await.unwrap().is_none(), "A repeated arrival fired the join");
+
+    committer.commit(arrive(second)).await.unwrap();
+    let next = queue.pop().await.unwrap().expect("Join did not fire");
+    assert_eq!(next.task.node_index, 3);
+    queue.ack(&next.receipt).await.unwrap();
+
+    committer.commit(arrive(second)).await.unwrap();
+    assert!(queue.pop().await.unwrap().is_none(), "A late arrival fired the join again");
+}
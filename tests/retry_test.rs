@@ -0,0 +1,204 @@
+use skript::dsl::builder::WorkflowBuilder;
+use skript::compiler::core::Compiler;
+use skript::compiler::loader::load_workflow_from_yaml;
+use skript::runtime::engine::Engine;
+use skript::runtime::context::Context;
+use skript::runtime::instance::InstanceStatus;
+use skript::runtime::error::ErrorKind;
+use skript::runtime::retry::RetryPolicy;
+use skript::runtime::storage::{InMemoryStateStore, InMemoryTaskQueue, StateStore, TaskQueue};
+use skript::runtime::blueprint::Blueprint;
+use skript::actions::FunctionHandler;
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use async_trait::async_trait;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Fails until it has been called `failures + 1` times.
+#[derive(Debug)]
+struct FlakyAction {
+    calls: Arc<AtomicUsize>,
+    failures: usize,
+}
+
+#[async_trait]
+impl FunctionHandler for FlakyAction {
+    fn name(&self) -> &str { "flaky" }
+    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    async fn execute(&self, _params: Value, _ctx: &Context) -> Result<Value> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        if call < self.failures {
+            return Err(anyhow!("Service unavailable"));
+        }
+        Ok(json!("ok"))
+    }
+}
+
+fn blueprint(retry: RetryPolicy) -> Blueprint {
+    let workflow = WorkflowBuilder::new("retry-test")
+        .start("start")
+        .function("call", "flaky")
+            .output("result")
+            .retry(retry)
+            .build()
+        .end("end", "result")
+        .connect("start", "call")
+        .connect("call", "end")
+        .build();
+
+    let mut compiler = Compiler::new();
+    compiler.compile(workflow).expect("Compilation failed")
+}
+
+fn engine(store: Arc<dyn StateStore>, queue: Arc<dyn TaskQueue>, retry: RetryPolicy, calls: &Arc<AtomicUsize>, failures: usize) -> Arc<Engine> {
+    let mut engine = Engine::new_with_storage(store, queue);
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_function(Arc::new(FlakyAction { calls: calls.clone(), failures }));
+    engine.register_blueprint(blueprint(retry));
+    Arc::new(engine)
+}
+
+async fn run_flaky(retry: RetryPolicy, failures: usize) -> (InstanceStatus, usize) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let engine = engine(Arc::new(InMemoryStateStore::new()), Arc::new(InMemoryTaskQueue::new()), retry, &calls, failures);
+
+    let instance_id = engine.start_workflow("retry-test", HashMap::new())
+        .await
+        .expect("Failed to start workflow");
+
+    let worker_engine = engine.clone();
+    let worker = tokio::spawn(async move {
+        worker_engine.run_worker().await;
+    });
+
+    let status = engine.await_completion(instance_id, Duration::from_secs(5))
+        .await
+        .expect("Instance did not reach a terminal state");
+    worker.abort();
+
+    (status, calls.load(Ordering::SeqCst))
+}
+
+#[tokio::test]
+async fn test_retry_recovers_from_transient_failures() {
+    let retry = RetryPolicy { max_attempts: 3, initial_delay: 10, ..Default::default() };
+    let (status, calls) = run_flaky(retry, 2).await;
+
+    assert_eq!(status, InstanceStatus::Completed);
+    assert_eq!(calls, 3);
+}
+
+#[tokio::test]
+async fn test_retry_gives_up_after_max_attempts() {
+    let retry = RetryPolicy { max_attempts: 2, initial_delay: 10, ..Default::default() };
+    let (status, calls) = run_flaky(retry, 5).await;
+
+    assert_eq!(status, InstanceStatus::Failed);
+    assert_eq!(calls, 2);
+}
+
+#[tokio::test]
+async fn test_retry_skips_unmatched_error_kinds() {
+    let retry = RetryPolicy { retry_on: vec![ErrorKind::Http], initial_delay: 10, ..Default::default() };
+    let (status, calls) = run_flaky(retry, 1).await;
+
+    assert_eq!(status, InstanceStatus::Failed);
+    assert_eq!(calls, 1);
+}
+
+/// Kills the process running the backoff (by dropping its runtime), then lets a second
+/// engine on the same storage pick the task up once its lease is released.
+#[test]
+fn test_retry_survives_crash_during_backoff() {
+    let store = Arc::new(InMemoryStateStore::new());
+    let queue = Arc::new(InMemoryTaskQueue::new());
+    let calls = Arc::new(AtomicUsize::new(0));
+    let retry = RetryPolicy { max_attempts: 3, initial_delay: 60_000, ..Default::default() };
+
+    let crashed = tokio::runtime::Runtime::new().unwrap();
+    let first = engine(store.clone(), queue.clone(), retry.clone(), &calls, 1);
+    let instance_id = crashed.block_on(async {
+        let instance_id = first.start_workflow("retry-test", HashMap::new())
+            .await
+            .expect("Failed to start workflow");
+        first.spawn_workers(1);
+        while calls.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        instance_id
+    });
+    crashed.shutdown_background();
+    drop(first);
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        assert_eq!(queue.requeue_leased().await.unwrap(), 1, "The failed attempt should still be leased");
+
+        let second = engine(store, queue, retry, &calls, 1);
+        let workers = second.spawn_workers(1);
+        let status = second.await_completion(instance_id, Duration::from_secs(5))
+            .await
+            .expect("Instance did not reach a terminal state");
+        workers.abort();
+
+        assert_eq!(status, InstanceStatus::Completed);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    });
+}
+
+#[test]
+fn test_backoff_delay_is_capped() {
+    let retry = RetryPolicy { initial_delay: 100, backoff_factor: 3.0, max_delay: 500, ..Default::default() };
+
+    assert_eq!(retry.delay_for_attempt(1), Duration::from_millis(100));
+    assert_eq!(retry.delay_for_attempt(2), Duration::from_millis(300));
+    assert_eq!(retry.delay_for_attempt(3), Duration::from_millis(500));
+}
+
+#[test]
+fn test_retry_block_reaches_blueprint_params() {
+    let yaml_content = r#"
+id: "retry-yaml"
+nodes:
+  - id: "start"
+    type: "Start"
+  - id: "call"
+    type: "Function"
+    name: "http"
+    params:
+      url: "https://api.example.com"
+    retry:
+      max_attempts: 5
+      initial_delay: 200
+      retry_on: ["http"]
+  - id: "end"
+    type: "End"
+edges:
+  - source: "start"
+    target: "call"
+  - source: "call"
+    target: "end"
+"#;
+
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let file_path = temp_dir.path().join("retry.yaml");
+    fs::write(&file_path, yaml_content).expect("Failed to write temp file");
+
+    let workflow = load_workflow_from_yaml(&file_path.to_string_lossy()).expect("Failed to load workflow");
+    let mut compiler = Compiler::new();
+    let blueprint = compiler.compile(workflow).expect("Compilation failed");
+
+    let call = blueprint.nodes.iter().find(|n| n.kind == "http").unwrap();
+    let retry: RetryPolicy = serde_json::from_value(call.policy("retry").unwrap().clone()).unwrap();
+    assert_eq!(retry.max_attempts, 5);
+    assert_eq!(retry.initial_delay, 200);
+    assert_eq!(retry.backoff_factor, 2.0);
+    assert_eq!(retry.retry_on, vec![ErrorKind::Http]);
+}
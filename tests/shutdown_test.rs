@@ -0,0 +1,111 @@
+use skript::dsl::builder::WorkflowBuilder;
+use skript::compiler::core::Compiler;
+use skript::runtime::engine::Engine;
+use skript::runtime::context::Context;
+use skript::runtime::instance::InstanceStatus;
+use skript::runtime::storage::{InMemoryStateStore, InMemoryTaskQueue, StateStore, TaskQueue};
+use skript::runtime::blueprint::Blueprint;
+use skript::actions::FunctionHandler;
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use async_trait::async_trait;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Sleeps for `first_delay` on the first call and returns immediately afterwards.
+#[derive(Debug)]
+struct SlowOnceAction {
+    calls: Arc<AtomicUsize>,
+    first_delay: Duration,
+}
+
+#[async_trait]
+impl FunctionHandler for SlowOnceAction {
+    fn name(&self) -> &str { "slow_once" }
+    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    async fn execute(&self, _params: Value, _ctx: &Context) -> Result<Value> {
+        if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            tokio::time::sleep(self.first_delay).await;
+        }
+        Ok(json!("done"))
+    }
+}
+
+fn blueprint() -> Blueprint {
+    let workflow = WorkflowBuilder::new("shutdown-test")
+        .start("start")
+        .function("call", "slow_once").output("result").build()
+        .end("end", "result")
+        .connect("start", "call")
+        .connect("call", "end")
+        .build();
+    Compiler::new().compile(workflow).expect("Compilation failed")
+}
+
+fn engine(store: Arc<dyn StateStore>, queue: Arc<dyn TaskQueue>, calls: &Arc<AtomicUsize>, first_delay: Duration, grace: Duration) -> Arc<Engine> {
+    let mut engine = Engine::new_with_storage(store, queue);
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_function(Arc::new(SlowOnceAction { calls: calls.clone(), first_delay }));
+    engine.register_blueprint(blueprint());
+    engine.set_shutdown_grace(grace);
+    Arc::new(engine)
+}
+
+/// Shuts the first worker down while it runs the slow node, then lets a second engine
+/// on the same storage finish the instance. Returns the number of handler calls.
+async fn shutdown_mid_task(first_delay: Duration, grace: Duration) -> usize {
+    let store: Arc<dyn StateStore> = Arc::new(InMemoryStateStore::new());
+    let queue: Arc<dyn TaskQueue> = Arc::new(InMemoryTaskQueue::new());
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let first = engine(store.clone(), queue.clone(), &calls, first_delay, grace);
+    let instance_id = first.start_workflow("shutdown-test", HashMap::new())
+        .await
+        .expect("Failed to start workflow");
+
+    let worker_engine = first.clone();
+    let worker = tokio::spawn(async move {
+        worker_engine.run_worker().await;
+    });
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    first.shutdown_handle().shutdown();
+    tokio::time::timeout(Duration::from_secs(3), worker)
+        .await
+        .expect("Worker did not stop after shutdown")
+        .unwrap();
+    assert_ne!(first.get_status(instance_id).await.unwrap(), Some(InstanceStatus::Completed));
+
+    let second = engine(store, queue, &calls, Duration::ZERO, grace);
+    let worker_engine = second.clone();
+    let worker = tokio::spawn(async move {
+        worker_engine.run_worker().await;
+    });
+
+    let status = second.await_completion(instance_id, Duration::from_secs(5))
+        .await
+        .expect("Instance did not reach a terminal state");
+    worker.abort();
+
+    assert_eq!(status, InstanceStatus::Completed);
+    assert_eq!(second.get_output(instance_id).await.unwrap(), Some(json!("done")));
+    calls.load(Ordering::SeqCst)
+}
+
+#[tokio::test]
+async fn test_shutdown_drains_in_flight_task() {
+    // The node finishes within the grace period, the second engine only runs `end`
+    let calls = shutdown_mid_task(Duration::from_millis(200), Duration::from_secs(2)).await;
+    assert_eq!(calls, 1);
+}
+
+#[tokio::test]
+async fn test_shutdown_requeues_task_after_grace() {
+    // The node outlives the grace period, so it is aborted and runs again on the second engine
+    let calls = shutdown_mid_task(Duration::from_secs(10), Duration::from_millis(50)).await;
+    assert_eq!(calls, 2);
+}
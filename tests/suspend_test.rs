@@ -0,0 +1,103 @@
+use skript::dsl::builder::WorkflowBuilder;
+use skript::compiler::core::{Compiler, CompilerConfig};
+use skript::runtime::engine::Engine;
+use skript::runtime::context::Context;
+use skript::runtime::instance::InstanceStatus;
+use skript::actions::FunctionHandler;
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use async_trait::async_trait;
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{mpsc, Semaphore};
+
+/// Counts calls. Each call reports that it started, then waits for a permit of `gate`,
+/// so the test can act while a step is running.
+#[derive(Debug)]
+struct StepAction {
+    steps: Arc<AtomicUsize>,
+    started: mpsc::UnboundedSender<()>,
+    gate: Arc<Semaphore>,
+}
+
+#[async_trait]
+impl FunctionHandler for StepAction {
+    fn name(&self) -> &str { "step" }
+    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    async fn execute(&self, _params: Value, _ctx: &Context) -> Result<Value> {
+        let _ = self.started.send(());
+        self.gate.acquire().await?.forget();
+        Ok(Value::from(self.steps.fetch_add(1, Ordering::SeqCst) + 1))
+    }
+}
+
+#[tokio::test]
+async fn test_suspend_parks_tasks_until_resume() {
+    let workflow = WorkflowBuilder::new("suspend-test")
+        .start("start")
+        .function("one", "step").build()
+        .function("two", "step").build()
+        .function("three", "step").output("result").build()
+        .end("end", "result")
+        .connect("start", "one")
+        .connect("one", "two")
+        .connect("two", "three")
+        .connect("three", "end")
+        .build();
+
+    let mut compiler = Compiler::new_with_config(CompilerConfig { enable_fusion: false, ..Default::default() });
+    let blueprint = compiler.compile(workflow).expect("Compilation failed");
+
+    let steps = Arc::new(AtomicUsize::new(0));
+    let (started_tx, mut started) = mpsc::unbounded_channel();
+    let gate = Arc::new(Semaphore::new(0));
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_function(Arc::new(StepAction { steps: steps.clone(), started: started_tx, gate: gate.clone() }));
+    engine.register_blueprint(blueprint);
+    let engine = Arc::new(engine);
+
+    let instance_id = engine.start_workflow("suspend-test", HashMap::new())
+        .await
+        .expect("Failed to start workflow");
+
+    let worker_engine = engine.clone();
+    let worker = tokio::spawn(async move {
+        worker_engine.run_worker().await;
+    });
+
+    // Suspend while the first step is running
+    tokio::time::timeout(Duration::from_secs(5), started.recv()).await
+        .expect("The first step did not start");
+    assert!(engine.suspend(instance_id).await.unwrap());
+    assert!(!engine.suspend(instance_id).await.unwrap());
+
+    // The running step finishes; the next one is parked instead of started
+    gate.add_permits(1);
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while steps.load(Ordering::SeqCst) < 1 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }).await.expect("The running step did not finish");
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(steps.load(Ordering::SeqCst), 1, "The running step finishes, the next one is parked");
+    assert!(started.try_recv().is_err(), "No step may start while suspended");
+    assert_eq!(engine.get_status(instance_id).await.unwrap(), Some(InstanceStatus::Suspended));
+
+    assert!(engine.resume(instance_id).await.unwrap());
+    assert!(!engine.resume(instance_id).await.unwrap());
+    gate.add_permits(2);
+
+    let status = engine.await_completion(instance_id, Duration::from_secs(5))
+        .await
+        .expect("Instance did not reach a terminal state");
+    worker.abort();
+
+    assert_eq!(status, InstanceStatus::Completed);
+    assert_eq!(steps.load(Ordering::SeqCst), 3);
+    assert_eq!(engine.get_output(instance_id).await.unwrap(), Some(Value::from(3)));
+}
@@ -0,0 +1,118 @@
+use skript::runtime::task::Task;
+use skript::runtime::storage::{InMemoryTaskQueue, TaskQueue};
+use skript::runtime::redis_storage::RedisTaskQueue;
+use skript::runtime::redis_stream::RedisStreamTaskQueue;
+use std::time::Duration;
+use uuid::Uuid;
+
+fn task(node_index: usize) -> Task {
+    Task {
+        instance_id: Uuid::new_v4(),
+        workflow_id: "queue-test".to_string(),
+        blueprint_version: None,
+        token_id: Uuid::new_v4(),
+        node_index,
+        flow_id: Uuid::new_v4(),
+        attempt: 0,
+        deadline: None,
+        compensation: false,
+    }
+}
+
+#[tokio::test]
+async fn test_in_memory_nack_redelivers() {
+    let queue = InMemoryTaskQueue::new();
+    queue.push(task(1)).await.unwrap();
+
+    let first = queue.pop().await.unwrap().unwrap();
+    queue.nack(&first.receipt).await.unwrap();
+
+    let second = queue.pop().await.unwrap().unwrap();
+    assert_eq!(second.task.token_id, first.task.token_id);
+    assert_ne!(second.receipt, first.receipt);
+
+    // Acked tasks are gone for good, and a stale receipt is ignored
+    queue.ack(&second.receipt).await.unwrap();
+    queue.nack(&second.receipt).await.unwrap();
+    assert!(tokio::time::timeout(Duration::from_millis(100), queue.pop()).await.is_err());
+}
+
+#[tokio::test]
+#[ignore] // Requires a local Redis
+async fn test_redis_expired_lease_is_redelivered() {
+    let client = redis::Client::open("redis://:difyai123456@localhost:6379/6").expect("Invalid Redis URL");
+    let queue_key = format!("skript:test:lease:{}", Uuid::new_v4());
+    let queue = RedisTaskQueue::new(client, queue_key)
+        .with_visibility_timeout(Duration::from_millis(100));
+
+    queue.push(task(1)).await.unwrap();
+    let crashed = queue.pop().await.unwrap().expect("Task was not delivered");
+
+    // Never acked: the lease must expire before the task is handed out again
+    assert_eq!(queue.reap_expired().await.unwrap(), 0);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(queue.reap_expired().await.unwrap(), 1);
+
+    let redelivered = queue.pop().await.unwrap().expect("Task was not redelivered");
+    assert_eq!(redelivered.task.token_id, crashed.task.token_id);
+    queue.ack(&redelivered.receipt).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(queue.reap_expired().await.unwrap(), 0);
+    assert!(queue.pop().await.unwrap().is_none());
+}
+
+#[tokio::test]
+#[ignore] // Requires a local Redis
+async fn test_redis_late_ack_keeps_the_redelivered_task() {
+    let client = redis::Client::open("redis://:difyai123456@localhost:6379/6").expect("Invalid Redis URL");
+    let queue_key = format!("skript:test:late-ack:{}", Uuid::new_v4());
+    let queue = RedisTaskQueue::new(client, queue_key)
+        .with_visibility_timeout(Duration::from_millis(100));
+
+    queue.push(task(1)).await.unwrap();
+    let slow = queue.pop().await.unwrap().expect("Task was not delivered");
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(queue.reap_expired().await.unwrap(), 1);
+    let redelivered = queue.pop().await.unwrap().expect("Task was not redelivered");
+    assert_ne!(redelivered.receipt, slow.receipt);
+
+    // The slow worker finally acks: the second delivery must stay leased and be reaped again
+    queue.ack(&slow.receipt).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(queue.reap_expired().await.unwrap(), 1);
+    let again = queue.pop().await.unwrap().expect("Redelivered task was lost");
+    assert_eq!(again.task.token_id, slow.task.token_id);
+    queue.ack(&again.receipt).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore] // Requires a local Redis
+async fn test_redis_stream_claims_stalled_entries() {
+    let client = redis::Client::open("redis://:difyai123456@localhost:6379/6").expect("Invalid Redis URL");
+    let stream_key = format!("skript:test:stream:{}", Uuid::new_v4());
+    let crashed = RedisStreamTaskQueue::new(client.clone(), stream_key.clone(), "workers".to_string(), "crashed".to_string())
+        .with_visibility_timeout(Duration::from_millis(100));
+    let healthy = RedisStreamTaskQueue::new(client, stream_key, "workers".to_string(), "healthy".to_string())
+        .with_visibility_timeout(Duration::from_millis(100));
+
+    crashed.push(task(1)).await.unwrap();
+    let lost = crashed.pop().await.unwrap().expect("Task was not delivered");
+
+    let info = healthy.info().await.unwrap();
+    assert_eq!(info.length, 1);
+    assert_eq!(info.pending, 1);
+    assert_eq!(info.consumers.iter().find(|c| c.name == "crashed").unwrap().pending, 1);
+
+    // Still within the visibility timeout: nobody else gets it
+    assert!(healthy.claim_stalled().await.unwrap().is_none());
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let claimed = healthy.claim_stalled().await.unwrap().expect("Stalled task was not claimed");
+    assert_eq!(claimed.task.token_id, lost.task.token_id);
+    healthy.ack(&claimed.receipt).await.unwrap();
+
+    let info = healthy.info().await.unwrap();
+    assert_eq!(info.length, 0);
+    assert_eq!(info.pending, 0);
+}
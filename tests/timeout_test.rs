@@ -0,0 +1,111 @@
+mod common;
+
+use common::SlowAction;
+use skript::dsl::builder::WorkflowBuilder;
+use skript::compiler::core::Compiler;
+use skript::runtime::instance::{InstanceStatus, InstanceFailure};
+use skript::runtime::error::ErrorKind;
+use skript::runtime::retry::RetryPolicy;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+async fn run(builder: WorkflowBuilder, action: SlowAction) -> (InstanceStatus, Option<InstanceFailure>) {
+    let workflow = builder
+        .end("end", "result")
+        .connect("start", "call")
+        .connect("call", "end")
+        .build();
+    let blueprint_id = workflow.id.clone();
+
+    let mut compiler = Compiler::new();
+    let blueprint = compiler.compile(workflow).expect("Compilation failed");
+
+    let engine = common::engine(blueprint, vec![Arc::new(action)]);
+
+    let instance_id = engine.start_workflow(&blueprint_id, HashMap::new())
+        .await
+        .expect("Failed to start workflow");
+
+    let worker_engine = engine.clone();
+    let worker = tokio::spawn(async move {
+        worker_engine.run_worker().await;
+    });
+
+    let status = engine.await_completion(instance_id, Duration::from_secs(5))
+        .await
+        .expect("Instance did not reach a terminal state");
+    worker.abort();
+
+    (status, engine.get_failure(instance_id).await.unwrap())
+}
+
+fn slow(calls: &Arc<AtomicUsize>, delay_ms: u64, handler_timeout: Option<Duration>) -> SlowAction {
+    SlowAction { calls: calls.clone(), delay: Duration::from_millis(delay_ms), handler_timeout }
+}
+
+#[tokio::test]
+async fn test_node_timeout_fails_instance() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let builder = WorkflowBuilder::new("node-timeout")
+        .start("start")
+        .function("call", "slow").output("result").timeout(50).build();
+
+    let (status, failure) = run(builder, slow(&calls, 1000, None)).await;
+
+    assert_eq!(status, InstanceStatus::Failed);
+    assert_eq!(failure.unwrap().error, "Node timed out after 50ms");
+}
+
+#[tokio::test]
+async fn test_timeout_precedence() {
+    // The handler default overrides the workflow default
+    let calls = Arc::new(AtomicUsize::new(0));
+    let builder = WorkflowBuilder::new("handler-timeout")
+        .default_timeout(5000)
+        .start("start")
+        .function("call", "slow").output("result").build();
+
+    let (status, failure) = run(builder, slow(&calls, 1000, Some(Duration::from_millis(50)))).await;
+    assert_eq!(status, InstanceStatus::Failed);
+    assert_eq!(failure.unwrap().error, "Node timed out after 50ms");
+
+    // The DSL node timeout overrides the handler default
+    let builder = WorkflowBuilder::new("dsl-timeout")
+        .start("start")
+        .function("call", "slow").output("result").timeout(500).build();
+
+    let (status, _) = run(builder, slow(&calls, 100, Some(Duration::from_millis(50)))).await;
+    assert_eq!(status, InstanceStatus::Completed);
+}
+
+#[tokio::test]
+async fn test_timeouts_are_retryable() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let retry = RetryPolicy { max_attempts: 3, initial_delay: 10, retry_on: vec![ErrorKind::Timeout], ..Default::default() };
+    let builder = WorkflowBuilder::new("timeout-retry")
+        .start("start")
+        .function("call", "slow").output("result").timeout(20).retry(retry).build();
+
+    let (status, _) = run(builder, slow(&calls, 1000, None)).await;
+
+    assert_eq!(status, InstanceStatus::Failed);
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_deadline_stops_retries() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let retry = RetryPolicy { max_attempts: 100, initial_delay: 10, max_delay: 10, ..Default::default() };
+    let builder = WorkflowBuilder::new("deadline")
+        .deadline(200)
+        .start("start")
+        .function("call", "slow").output("result").timeout(30).retry(retry).build();
+
+    let (status, failure) = run(builder, slow(&calls, 1000, None)).await;
+
+    assert_eq!(status, InstanceStatus::Failed);
+    assert_eq!(failure.unwrap().error, "Instance deadline exceeded");
+    assert!(calls.load(Ordering::SeqCst) < 100);
+}
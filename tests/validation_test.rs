@@ -0,0 +1,86 @@
+use skript::dsl::builder::WorkflowBuilder;
+use skript::compiler::core::Compiler;
+use skript::runtime::engine::Engine;
+use skript::actions::builtin::{AssignAction, LogAction};
+use skript::actions::http::HttpAction;
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use skript::nodes::flow::IfDefinition;
+use std::sync::Arc;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_node(Box::new(IfDefinition));
+    engine.register_function(Arc::new(LogAction));
+    engine.register_function(Arc::new(AssignAction));
+    engine.register_function(Arc::new(HttpAction::new()));
+    engine
+}
+
+fn compile_error(builder: WorkflowBuilder) -> String {
+    let engine = engine();
+    let mut compiler = Compiler::new().with_registry(engine.node_registry());
+    compiler.compile(builder.build()).unwrap_err().to_string()
+}
+
+#[test]
+fn test_registered_nodes_compile() {
+    let engine = engine();
+    let workflow = WorkflowBuilder::new("valid-flow")
+        .start("start")
+        .function("fetch", "http").param("url", "https://example.com").output("page").build()
+        .end("end", "page")
+        .connect("start", "fetch")
+        .connect("fetch", "end")
+        .build();
+
+    let mut compiler = Compiler::new().with_registry(engine.node_registry());
+    assert!(compiler.compile(workflow).is_ok());
+}
+
+#[test]
+fn test_unknown_function_is_rejected() {
+    let err = compile_error(WorkflowBuilder::new("unknown-flow")
+        .start("start")
+        .function("mail", "send_mail").build()
+        .end("end", "")
+        .connect("start", "mail")
+        .connect("mail", "end"));
+    assert_eq!(err, "Node mail: unknown node kind `send_mail`");
+}
+
+#[test]
+fn test_missing_required_param_is_rejected() {
+    let err = compile_error(WorkflowBuilder::new("http-flow")
+        .start("start")
+        .function("fetch", "http").param("method", "GET").build()
+        .end("end", "")
+        .connect("start", "fetch")
+        .connect("fetch", "end"));
+    assert_eq!(err, "Node fetch: invalid `http` params: Missing required parameter: url");
+}
+
+#[test]
+fn test_malformed_condition_is_rejected() {
+    let err = compile_error(WorkflowBuilder::new("if-flow")
+        .start("start")
+        .if_node("check")
+        .end("yes", "")
+        .end("no", "")
+        .connect("start", "check")
+        .connect_if("check", "yes", "(${count} > 1")
+        .connect_else("check", "no"));
+    assert!(err.starts_with("Node check: invalid `if` params: Invalid condition `(${count} > 1`"), "{}", err);
+}
+
+#[test]
+fn test_reserved_policy_param_is_rejected() {
+    let err = compile_error(WorkflowBuilder::new("reserved-flow")
+        .start("start")
+        .function("fetch", "http").param("url", "https://example.com").param("_policy", serde_json::json!({})).build()
+        .end("end", "")
+        .connect("start", "fetch")
+        .connect("fetch", "end"));
+    assert_eq!(err, "Node fetch: `_policy` is reserved for execution policies");
+}
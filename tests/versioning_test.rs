@@ -0,0 +1,85 @@
+use skript::dsl::builder::WorkflowBuilder;
+use skript::dsl::Workflow;
+use skript::compiler::core::Compiler;
+use skript::runtime::engine::Engine;
+use skript::runtime::blueprint::Blueprint;
+use skript::runtime::instance::InstanceStatus;
+use skript::actions::builtin::AssignAction;
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn workflow(values: &[i64]) -> Workflow {
+    let mut builder = WorkflowBuilder::new("versioned-flow").start("start");
+    let mut previous = "start".to_string();
+    let mut edges = Vec::new();
+    for (i, value) in values.iter().enumerate() {
+        let id = format!("set_{}", i);
+        builder = builder.function(&id, "assign").param("value", *value).output("answer").build();
+        edges.push((previous, id.clone()));
+        previous = id;
+    }
+    builder = builder.end("end", "answer");
+    edges.push((previous, "end".to_string()));
+    for (from, to) in edges {
+        builder = builder.connect(&from, &to);
+    }
+    builder.build()
+}
+
+#[test]
+fn test_version_follows_content() {
+    let v1 = Compiler::new().compile(workflow(&[1])).unwrap();
+    let v1_again = Compiler::new().compile(workflow(&[1])).unwrap();
+    let v2 = Compiler::new().compile(workflow(&[1, 2])).unwrap();
+
+    assert!(!v1.version.is_empty());
+    assert_eq!(v1.version, v1_again.version);
+    assert_ne!(v1.version, v2.version);
+}
+
+#[tokio::test]
+async fn test_running_instance_keeps_its_version() {
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_function(Arc::new(AssignAction));
+    let engine = Arc::new(engine);
+
+    engine.register_blueprint(Compiler::new().compile(workflow(&[1])).unwrap());
+    let old_instance = engine.start_workflow("versioned-flow", HashMap::new()).await.unwrap();
+
+    // Re-register with more nodes while the first instance's task is still queued
+    engine.register_blueprint(Compiler::new().compile(workflow(&[10, 20, 30])).unwrap());
+    let new_instance = engine.start_workflow("versioned-flow", HashMap::new()).await.unwrap();
+
+    let workers = engine.spawn_workers(1);
+    let old_status = engine.await_completion(old_instance, Duration::from_secs(5)).await.unwrap();
+    let new_status = engine.await_completion(new_instance, Duration::from_secs(5)).await.unwrap();
+    workers.abort();
+
+    assert_eq!(old_status, InstanceStatus::Completed);
+    assert_eq!(new_status, InstanceStatus::Completed);
+    assert_eq!(engine.get_output(old_instance).await.unwrap(), Some(json!(1)));
+    assert_eq!(engine.get_output(new_instance).await.unwrap(), Some(json!(30)));
+}
+
+#[test]
+fn test_version_is_stable_digest() {
+    // Persisted versions must not change with the toolchain: pin the SHA-256 of the canonical JSON
+    let blueprint: Blueprint = serde_json::from_value(json!({
+        "id": "pinned-flow",
+        "name": "",
+        "nodes": [
+            { "id": "start", "kind": "start", "params": { "next": 1 } },
+            { "id": "end", "kind": "end", "params": { "output": "answer" } }
+        ],
+        "start_index": 0,
+        "variables": { "b": 2, "a": 1 },
+        "version": "ignored"
+    })).unwrap();
+
+    assert_eq!(blueprint.content_hash(), "3e912b59413aba6ec2c838dbf14fea5139390d3eafaf76e4fad1e8aa80d8bb25");
+}
@@ -0,0 +1,172 @@
+use skript::dsl::builder::WorkflowBuilder;
+use skript::compiler::core::Compiler;
+use skript::runtime::engine::Engine;
+use skript::runtime::context::Context;
+use skript::runtime::instance::InstanceStatus;
+use skript::runtime::retry::RetryPolicy;
+use skript::actions::FunctionHandler;
+use skript::nodes::common::{StartDefinition, EndDefinition};
+use async_trait::async_trait;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Sleeps for a while and records the highest number of overlapping calls.
+#[derive(Debug, Default)]
+struct ConcurrencyProbe {
+    running: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+#[async_trait]
+impl FunctionHandler for ConcurrencyProbe {
+    fn name(&self) -> &str { "probe" }
+    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    async fn execute(&self, _params: Value, _ctx: &Context) -> Result<Value> {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        Ok(json!("done"))
+    }
+}
+
+fn engine(probe: Arc<ConcurrencyProbe>) -> Arc<Engine> {
+    let workflow = WorkflowBuilder::new("pool-test")
+        .start("start")
+        .function("call", "probe").output("result").build()
+        .end("end", "result")
+        .connect("start", "call")
+        .connect("call", "end")
+        .build();
+    let blueprint = Compiler::new().compile(workflow).expect("Compilation failed");
+
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_function(probe);
+    engine.register_blueprint(blueprint);
+    Arc::new(engine)
+}
+
+#[tokio::test]
+async fn test_pool_limits_concurrency_and_counts_tasks() {
+    let probe = Arc::new(ConcurrencyProbe::default());
+    let engine = engine(probe.clone());
+
+    let mut instances = Vec::new();
+    for _ in 0..8 {
+        instances.push(engine.start_workflow("pool-test", HashMap::new()).await.unwrap());
+    }
+
+    let pool = engine.spawn_workers(4);
+    assert_eq!(pool.size(), 4);
+
+    for id in instances {
+        let status = engine.await_completion(id, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(status, InstanceStatus::Completed);
+    }
+
+    let peak = probe.peak.load(Ordering::SeqCst);
+    assert!(peak > 1 && peak <= 4, "unexpected peak concurrency {}", peak);
+
+    // start + call + end for each instance
+    let total = pool.total_stats();
+    assert_eq!(total.succeeded, 24);
+    assert_eq!(total.failed, 0);
+    assert_eq!(pool.stats().iter().map(|s| s.processed()).sum::<u64>(), 24);
+
+    tokio::time::timeout(Duration::from_secs(3), pool.shutdown_and_join())
+        .await
+        .expect("Pool did not stop after shutdown");
+}
+
+#[tokio::test]
+async fn test_single_worker_runs_serially() {
+    let probe = Arc::new(ConcurrencyProbe::default());
+    let engine = engine(probe.clone());
+
+    let first = engine.start_workflow("pool-test", HashMap::new()).await.unwrap();
+    let second = engine.start_workflow("pool-test", HashMap::new()).await.unwrap();
+
+    let pool = engine.spawn_workers(1);
+    for id in [first, second] {
+        engine.await_completion(id, Duration::from_secs(5)).await.unwrap();
+    }
+    pool.abort();
+
+    assert_eq!(probe.peak.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_pool_shutdown_leaves_other_pools_running() {
+    let probe = Arc::new(ConcurrencyProbe::default());
+    let engine = engine(probe.clone());
+
+    let stopped = engine.spawn_workers(2);
+    let running = engine.spawn_workers(1);
+    tokio::time::timeout(Duration::from_secs(3), stopped.shutdown_and_join())
+        .await
+        .expect("Pool did not stop after shutdown");
+    assert!(!engine.shutdown_handle().is_shutdown());
+
+    let id = engine.start_workflow("pool-test", HashMap::new()).await.unwrap();
+    let status = engine.await_completion(id, Duration::from_secs(5)).await.unwrap();
+    assert_eq!(status, InstanceStatus::Completed);
+    assert_eq!(running.total_stats().succeeded, 3);
+
+    running.abort();
+}
+
+/// Fails on its first call only.
+#[derive(Debug, Default)]
+struct FlakyOnce {
+    calls: AtomicUsize,
+}
+
+#[async_trait]
+impl FunctionHandler for FlakyOnce {
+    fn name(&self) -> &str { "flaky" }
+    fn validate(&self, _params: &Value) -> Result<()> { Ok(()) }
+    async fn execute(&self, _params: Value, _ctx: &Context) -> Result<Value> {
+        if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            return Err(anyhow!("Temporary failure"));
+        }
+        Ok(json!("done"))
+    }
+}
+
+#[tokio::test]
+async fn test_retried_attempts_are_not_counted_as_failed() {
+    let workflow = WorkflowBuilder::new("flaky-pool-test")
+        .start("start")
+        .function("call", "flaky").output("result")
+            .retry(RetryPolicy { max_attempts: 2, initial_delay: 10, ..Default::default() })
+            .build()
+        .end("end", "result")
+        .connect("start", "call")
+        .connect("call", "end")
+        .build();
+    let mut engine = Engine::new();
+    engine.register_node(Box::new(StartDefinition));
+    engine.register_node(Box::new(EndDefinition));
+    engine.register_function(Arc::new(FlakyOnce::default()));
+    engine.register_blueprint(Compiler::new().compile(workflow).expect("Compilation failed"));
+    let engine = Arc::new(engine);
+
+    let pool = engine.spawn_workers(1);
+    let id = engine.start_workflow("flaky-pool-test", HashMap::new()).await.unwrap();
+    let status = engine.await_completion(id, Duration::from_secs(5)).await.unwrap();
+    assert_eq!(status, InstanceStatus::Completed);
+
+    let total = pool.total_stats();
+    assert_eq!(total.succeeded, 3);
+    assert_eq!(total.retried, 1);
+    assert_eq!(total.failed, 0);
+    assert_eq!(total.processed(), 4);
+
+    pool.abort();
+}